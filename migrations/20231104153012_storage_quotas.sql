@@ -0,0 +1,16 @@
+-- Add migration script here
+create table plans
+(
+    id                 serial primary key,
+    name               varchar(255) unique not null,
+    storage_quota      bigint              not null,
+    max_files_per_note integer             not null
+);
+
+insert into plans (name, storage_quota, max_files_per_note)
+values ('free', 1073741824, 20),
+       ('premium', 10737418240, 100);
+
+alter table users
+    add column plan_id       integer not null default 1 references plans (id),
+    add column storage_quota bigint;
@@ -0,0 +1,6 @@
+-- Add migration script here
+alter table note_attachments
+    add column checksum varchar(64);
+
+alter table users
+    add column is_admin boolean not null default false;
@@ -0,0 +1,44 @@
+-- Add migration script here
+alter table note_attachments
+    rename to attachments;
+
+alter table attachments
+    drop constraint note_attachments_note_id_fkey;
+
+alter table attachments
+    rename column note_id to item_id;
+
+alter table attachments
+    add column item_type varchar(32) not null default 'secured_note';
+
+alter table attachments
+    alter column item_type drop default;
+
+create index attachments_item_idx on attachments (item_type, item_id);
+
+-- Without the foreign key, attachment rows are removed together with their item here.
+CREATE OR REPLACE FUNCTION delete_item_attachments()
+    RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM attachments WHERE item_type = TG_ARGV[0] AND item_id = OLD.id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_delete_login_attachments
+    AFTER DELETE ON logins
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_attachments('login');
+
+CREATE TRIGGER trigger_delete_payment_attachments
+    AFTER DELETE ON payments
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_attachments('payment');
+
+CREATE TRIGGER trigger_delete_secured_note_attachments
+    AFTER DELETE ON secured_notes
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_attachments('secured_note');
+
+alter table plans
+    rename column max_files_per_note to max_files_per_item;
@@ -0,0 +1,17 @@
+-- Add migration script here
+create table sends
+(
+    id         serial primary key,
+    token      varchar(64) unique not null,
+    owner_id   integer            not null references users (id) on delete cascade,
+    kind       varchar(16)        not null,
+    content    text,
+    file_name  varchar(255),
+    file_size  integer,
+    password   varchar(255),
+    hide_email boolean            not null default false,
+    max_views  integer,
+    view_count integer            not null default 0,
+    expires_at timestamp          not null,
+    created_at timestamp          not null default now()
+);
@@ -0,0 +1,37 @@
+-- Add migration script here
+create table item_shares
+(
+    id             serial primary key,
+    item_type      varchar(32) not null,
+    item_id        integer     not null,
+    owner_id       integer     not null references users (id) on delete cascade,
+    shared_with_id integer     not null references users (id) on delete cascade,
+    permission     varchar(16) not null,
+    created_at     timestamp   not null default now(),
+    unique (item_type, item_id, shared_with_id)
+);
+
+create index item_shares_shared_with_idx on item_shares (shared_with_id, item_type);
+
+CREATE OR REPLACE FUNCTION delete_item_shares()
+    RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM item_shares WHERE item_type = TG_ARGV[0] AND item_id = OLD.id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_delete_login_shares
+    AFTER DELETE ON logins
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_shares('login');
+
+CREATE TRIGGER trigger_delete_payment_shares
+    AFTER DELETE ON payments
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_shares('payment');
+
+CREATE TRIGGER trigger_delete_secured_note_shares
+    AFTER DELETE ON secured_notes
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_shares('secured_note');
@@ -0,0 +1,104 @@
+-- Add migration script here
+create table organizations
+(
+    id         serial primary key,
+    name       varchar(255) not null,
+    created_at timestamp    not null default now()
+);
+
+create table organization_members
+(
+    id              serial primary key,
+    organization_id integer     not null references organizations (id) on delete cascade,
+    user_id         integer     not null references users (id) on delete cascade,
+    role            varchar(16) not null,
+    status          varchar(16) not null default 'invited',
+    invited_by      integer references users (id) on delete set null,
+    created_at      timestamp   not null default now(),
+    unique (organization_id, user_id)
+);
+
+create table org_collections
+(
+    id              serial primary key,
+    organization_id integer      not null references organizations (id) on delete cascade,
+    name            varchar(255) not null,
+    created_at      timestamp    not null default now(),
+    unique (organization_id, name)
+);
+
+create table collection_items
+(
+    collection_id integer     not null references org_collections (id) on delete cascade,
+    item_type     varchar(32) not null,
+    item_id       integer     not null,
+    primary key (collection_id, item_type, item_id)
+);
+
+create index collection_items_item_idx on collection_items (item_type, item_id);
+
+create table collection_access
+(
+    collection_id integer     not null references org_collections (id) on delete cascade,
+    member_id     integer     not null references organization_members (id) on delete cascade,
+    access        varchar(16) not null,
+    primary key (collection_id, member_id)
+);
+
+CREATE OR REPLACE FUNCTION delete_item_collection_entries()
+    RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM collection_items WHERE item_type = TG_ARGV[0] AND item_id = OLD.id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_delete_login_collection_entries
+    AFTER DELETE ON logins
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_collection_entries('login');
+
+CREATE TRIGGER trigger_delete_payment_collection_entries
+    AFTER DELETE ON payments
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_collection_entries('payment');
+
+CREATE TRIGGER trigger_delete_secured_note_collection_entries
+    AFTER DELETE ON secured_notes
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_collection_entries('secured_note');
+
+-- Everything granted to non-owners, from direct shares and from organization collections.
+-- Owners and admins manage every collection of their organization, managers the ones they
+-- were given access to. `rank` orders permissions: hide_passwords < read < write.
+CREATE VIEW item_permissions AS
+SELECT item_type,
+       item_id,
+       shared_with_id as user_id,
+       permission,
+       CASE permission WHEN 'write' THEN 3 ELSE 2 END as rank
+FROM item_shares
+UNION ALL
+SELECT ci.item_type,
+       ci.item_id,
+       m.user_id,
+       CASE
+           WHEN m.role IN ('owner', 'admin') THEN 'write'
+           WHEN m.role = 'manager' AND a.access IS NOT NULL THEN 'write'
+           WHEN a.access = 'manage' THEN 'write'
+           WHEN a.access = 'read_only' THEN 'read'
+           ELSE 'hide_passwords'
+           END as permission,
+       CASE
+           WHEN m.role IN ('owner', 'admin') THEN 3
+           WHEN m.role = 'manager' AND a.access IS NOT NULL THEN 3
+           WHEN a.access = 'manage' THEN 3
+           WHEN a.access = 'read_only' THEN 2
+           ELSE 1
+           END as rank
+FROM collection_items ci
+         JOIN org_collections c ON c.id = ci.collection_id
+         JOIN organization_members m ON m.organization_id = c.organization_id AND m.status = 'accepted'
+         LEFT JOIN collection_access a ON a.collection_id = c.id AND a.member_id = m.id
+WHERE m.role IN ('owner', 'admin')
+   OR a.access IS NOT NULL;
@@ -0,0 +1,15 @@
+-- Add migration script here
+create table emergency_access
+(
+    id                     serial primary key,
+    grantor_id             integer     not null references users (id) on delete cascade,
+    grantee_id             integer     not null references users (id) on delete cascade,
+    access_type            varchar(16) not null,
+    wait_days              integer     not null,
+    status                 varchar(32) not null default 'invited',
+    recovery_initiated_at  timestamp,
+    created_at             timestamp   not null default now(),
+    unique (grantor_id, grantee_id)
+);
+
+create index emergency_access_status_idx on emergency_access (status);
@@ -0,0 +1,29 @@
+-- Add migration script here
+create table events
+(
+    id         bigserial primary key,
+    event_type varchar(64) not null,
+    actor_id   integer,
+    ip_address varchar(64),
+    user_agent text,
+    item_type  varchar(32),
+    item_id    integer,
+    details    text,
+    created_at timestamp   not null default now()
+);
+
+create index events_actor_idx on events (actor_id, created_at);
+create index events_created_at_idx on events (created_at);
+
+-- Events are append-only; only the retention job removes old ones.
+CREATE OR REPLACE FUNCTION reject_event_update()
+    RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'events are append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_reject_event_update
+    BEFORE UPDATE ON events
+    FOR EACH ROW
+EXECUTE FUNCTION reject_event_update();
@@ -0,0 +1,16 @@
+-- Add migration script here
+-- Each event hashes the previous event (global chain) and the previous event of the same actor
+-- (per-user chain). Events written before this migration stay unchained.
+alter table events
+    add column prev_hash       varchar(64),
+    add column actor_prev_hash varchar(64),
+    add column hash            varchar(64);
+
+create table event_checkpoints
+(
+    id         serial primary key,
+    event_id   bigint      not null,
+    hash       varchar(64) not null,
+    signature  text        not null,
+    created_at timestamp   not null default now()
+);
@@ -0,0 +1,28 @@
+-- Add migration script here
+alter table users
+    add column webauthn_id uuid not null default gen_random_uuid();
+
+create table webauthn_credentials
+(
+    id            serial primary key,
+    user_id       integer      not null references users (id) on delete cascade,
+    name          varchar(255) not null,
+    usage         varchar(16)  not null,
+    credential_id text         not null unique,
+    passkey       text         not null,
+    sign_count    bigint       not null default 0,
+    created_at    timestamp    not null default now(),
+    last_used_at  timestamp
+);
+
+create index webauthn_credentials_user_idx on webauthn_credentials (user_id);
+
+-- Server side state of registration and authentication ceremonies in progress.
+create table webauthn_ceremonies
+(
+    id         varchar(64) primary key,
+    user_id    integer     not null references users (id) on delete cascade,
+    kind       varchar(32) not null,
+    state      text        not null,
+    created_at timestamp   not null default now()
+);
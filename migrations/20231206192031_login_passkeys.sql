@@ -0,0 +1,18 @@
+-- Add migration script here
+-- FIDO2 credentials a login holds for its website. private_key is encrypted by the client.
+create table login_passkeys
+(
+    id            serial primary key,
+    login_id      integer      not null references logins (id) on delete cascade,
+    credential_id text         not null,
+    rp_id         varchar(255) not null,
+    user_handle   text         not null,
+    user_name     varchar(255),
+    private_key   text         not null,
+    sign_count    bigint       not null default 0,
+    created_at    timestamp    not null default now(),
+    last_used_at  timestamp,
+    unique (login_id, credential_id)
+);
+
+create index login_passkeys_rp_id_idx on login_passkeys (rp_id);
@@ -0,0 +1,18 @@
+-- Add migration script here
+alter table users
+    add column email_verified boolean not null default false;
+
+-- Single use tokens sent by email. Only their sha256 is stored.
+create table user_tokens
+(
+    id         serial primary key,
+    user_id    integer      not null references users (id) on delete cascade,
+    purpose    varchar(32)  not null,
+    token_hash varchar(64)  not null unique,
+    email      varchar(255) not null,
+    expires_at timestamp    not null,
+    used_at    timestamp,
+    created_at timestamp    not null default now()
+);
+
+create index user_tokens_user_idx on user_tokens (user_id, purpose);
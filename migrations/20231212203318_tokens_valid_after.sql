@@ -0,0 +1,4 @@
+-- Add migration script here
+-- Session tokens issued before this unix timestamp are rejected; bumped to sign out everywhere.
+alter table users
+    add column tokens_valid_after bigint not null default 0;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- Stricter password requirements for members; null leaves the server default in place.
+alter table organizations
+    add column min_password_length integer,
+    add column min_password_score  integer;
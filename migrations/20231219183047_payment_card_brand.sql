@@ -0,0 +1,18 @@
+alter table payments
+    add column brand varchar(32) not null default 'unknown';
+
+-- Existing cards get their brand from the number's prefix, the same way the service detects it.
+update payments
+set brand = case
+    when n ~ '^3[47]' then 'amex'
+    when n ~ '^(6011|64[4-9]|65|622(12[6-9]|1[3-9][0-9]|[2-8][0-9]{2}|9[01][0-9]|92[0-5]))' then 'discover'
+    when n ~ '^(5[1-5]|222[1-9]|22[3-9][0-9]|2[3-6][0-9]{2}|27[01][0-9]|2720)' then 'mastercard'
+    when n ~ '^4' then 'visa'
+    when n ~ '^35(2[89]|[3-8][0-9])' then 'jcb'
+    when n ~ '^(30[0-5]|3[689])' then 'diners_club'
+    when n ~ '^62' then 'unionpay'
+    when n ~ '^(50|5[6-8]|6[0-9])' then 'maestro'
+    else 'unknown'
+end
+from (select id as payment_id, regexp_replace(card_number, '[^0-9]', '', 'g') as n from payments) digits
+where payments.id = digits.payment_id;
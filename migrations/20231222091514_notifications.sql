@@ -0,0 +1,49 @@
+-- When a login's password last changed, for rotation reminders.
+alter table logins
+    add column password_changed_at timestamp not null default now();
+
+CREATE OR REPLACE FUNCTION update_password_changed_at()
+    RETURNS TRIGGER AS $$
+BEGIN
+    NEW.password_changed_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_update_password_changed_at
+    BEFORE UPDATE ON logins
+    FOR EACH ROW
+    WHEN (OLD.password IS DISTINCT FROM NEW.password)
+EXECUTE FUNCTION update_password_changed_at();
+
+-- Logins in a collection should get a new password every this many days. Null disables it.
+alter table org_collections
+    add column rotation_days integer;
+
+-- In-app notifications. `dedup_key` tells reminders about the same thing apart, e.g. the
+-- card's expiry or when the password last changed, so the daily jobs don't repeat them.
+create table notifications
+(
+    id         serial primary key,
+    user_id    integer     not null references users (id) on delete cascade,
+    kind       varchar(32) not null,
+    item_type  varchar(32),
+    item_id    integer,
+    dedup_key  varchar(64) not null default '',
+    message    text        not null,
+    created_at timestamp   not null default now(),
+    emailed_at timestamp,
+    dismissed_at timestamp,
+    unique (user_id, kind, item_type, item_id, dedup_key)
+);
+
+create index notifications_user_idx on notifications (user_id, dismissed_at);
+
+create table notification_preferences
+(
+    user_id           integer primary key references users (id) on delete cascade,
+    card_expiry       boolean not null default true,
+    card_expiry_days  integer not null default 30,
+    password_rotation boolean not null default true,
+    email             boolean not null default false
+);
@@ -0,0 +1,232 @@
+-- Change feed for delta sync. Every change to an item a user can see bumps the user's
+-- revision and records the item at that revision. Rows with `deleted` set are tombstones for
+-- items that were deleted or that the user lost access to.
+create table user_revisions
+(
+    user_id  integer primary key references users (id) on delete cascade,
+    revision bigint not null default 0
+);
+
+create table sync_changes
+(
+    user_id   integer     not null references users (id) on delete cascade,
+    item_type varchar(32) not null,
+    item_id   integer     not null,
+    revision  bigint      not null,
+    deleted   boolean     not null,
+    primary key (user_id, item_type, item_id)
+);
+
+create index sync_changes_revision_idx on sync_changes (user_id, revision);
+
+CREATE OR REPLACE FUNCTION item_owner(p_item_type varchar, p_item_id integer)
+    RETURNS integer AS $$
+SELECT CASE p_item_type
+           WHEN 'login' THEN (SELECT owner_id FROM logins WHERE id = p_item_id)
+           WHEN 'payment' THEN (SELECT owner_id FROM payments WHERE id = p_item_id)
+           WHEN 'secured_note' THEN (SELECT owner_id FROM secured_notes WHERE id = p_item_id)
+           END;
+$$ LANGUAGE sql STABLE;
+
+-- The owner and everyone the item is granted to.
+CREATE OR REPLACE FUNCTION item_users(p_item_type varchar, p_item_id integer, p_owner_id integer)
+    RETURNS TABLE (user_id integer) AS $$
+SELECT p_owner_id WHERE p_owner_id IS NOT NULL
+UNION
+SELECT p.user_id FROM item_permissions p WHERE p.item_type = p_item_type AND p.item_id = p_item_id;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION record_sync_change(p_user_id integer, p_item_type varchar, p_item_id integer, p_deleted boolean)
+    RETURNS void AS $$
+DECLARE
+    next_revision bigint;
+BEGIN
+    -- Users being deleted take their feed with them.
+    IF NOT EXISTS(SELECT 1 FROM users WHERE id = p_user_id) THEN
+        RETURN;
+    END IF;
+
+    INSERT INTO user_revisions (user_id, revision) VALUES (p_user_id, 1)
+    ON CONFLICT (user_id) DO UPDATE SET revision = user_revisions.revision + 1
+    RETURNING revision INTO next_revision;
+
+    INSERT INTO sync_changes (user_id, item_type, item_id, revision, deleted)
+    VALUES (p_user_id, p_item_type, p_item_id, next_revision, p_deleted)
+    ON CONFLICT (user_id, item_type, item_id) DO UPDATE SET revision = EXCLUDED.revision, deleted = EXCLUDED.deleted;
+END;
+$$ LANGUAGE plpgsql;
+
+-- After shares or collections changed: records the item again if the user can see it, or a
+-- tombstone if they could before and no longer can.
+CREATE OR REPLACE FUNCTION record_access_sync_change(p_user_id integer, p_item_type varchar, p_item_id integer)
+    RETURNS void AS $$
+BEGIN
+    IF EXISTS(SELECT 1 FROM item_users(p_item_type, p_item_id, item_owner(p_item_type, p_item_id)) u WHERE u.user_id = p_user_id) THEN
+        PERFORM record_sync_change(p_user_id, p_item_type, p_item_id, false);
+    ELSIF EXISTS(SELECT 1 FROM sync_changes
+                 WHERE user_id = p_user_id AND item_type = p_item_type AND item_id = p_item_id AND NOT deleted) THEN
+        PERFORM record_sync_change(p_user_id, p_item_type, p_item_id, true);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Deletes run before the row is gone, while shares and collection entries still say who had it.
+CREATE OR REPLACE FUNCTION record_item_sync_change()
+    RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM record_sync_change(u.user_id, TG_ARGV[0], OLD.id, true)
+        FROM item_users(TG_ARGV[0], OLD.id, OLD.owner_id) u;
+        RETURN OLD;
+    END IF;
+
+    PERFORM record_sync_change(u.user_id, TG_ARGV[0], NEW.id, false)
+    FROM item_users(TG_ARGV[0], NEW.id, NEW.owner_id) u;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_login_sync
+    AFTER INSERT OR UPDATE ON logins
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('login');
+
+CREATE TRIGGER trigger_login_sync_delete
+    BEFORE DELETE ON logins
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('login');
+
+CREATE TRIGGER trigger_payment_sync
+    AFTER INSERT OR UPDATE ON payments
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('payment');
+
+CREATE TRIGGER trigger_payment_sync_delete
+    BEFORE DELETE ON payments
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('payment');
+
+CREATE TRIGGER trigger_secured_note_sync
+    AFTER INSERT OR UPDATE ON secured_notes
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('secured_note');
+
+CREATE TRIGGER trigger_secured_note_sync_delete
+    BEFORE DELETE ON secured_notes
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('secured_note');
+
+CREATE OR REPLACE FUNCTION record_share_sync_change()
+    RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM record_access_sync_change(OLD.shared_with_id, OLD.item_type, OLD.item_id);
+        RETURN OLD;
+    END IF;
+
+    PERFORM record_access_sync_change(NEW.shared_with_id, NEW.item_type, NEW.item_id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_item_shares_sync
+    AFTER INSERT OR UPDATE OR DELETE ON item_shares
+    FOR EACH ROW
+EXECUTE FUNCTION record_share_sync_change();
+
+CREATE OR REPLACE FUNCTION record_collection_item_sync_change()
+    RETURNS TRIGGER AS $$
+DECLARE
+    changed collection_items%ROWTYPE;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    PERFORM record_access_sync_change(m.user_id, changed.item_type, changed.item_id)
+    FROM org_collections c
+             JOIN organization_members m ON m.organization_id = c.organization_id
+    WHERE c.id = changed.collection_id;
+    RETURN changed;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_collection_items_sync
+    AFTER INSERT OR DELETE ON collection_items
+    FOR EACH ROW
+EXECUTE FUNCTION record_collection_item_sync_change();
+
+-- Empties collections before they go, so members still resolve and get their tombstones.
+CREATE OR REPLACE FUNCTION delete_collection_items()
+    RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM collection_items WHERE collection_id = OLD.id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_delete_collection_items
+    BEFORE DELETE ON org_collections
+    FOR EACH ROW
+EXECUTE FUNCTION delete_collection_items();
+
+CREATE OR REPLACE FUNCTION record_collection_access_sync_change()
+    RETURNS TRIGGER AS $$
+DECLARE
+    changed collection_access%ROWTYPE;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    PERFORM record_access_sync_change(m.user_id, ci.item_type, ci.item_id)
+    FROM organization_members m
+             JOIN collection_items ci ON ci.collection_id = changed.collection_id
+    WHERE m.id = changed.member_id;
+    RETURN changed;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_collection_access_sync
+    AFTER INSERT OR UPDATE OR DELETE ON collection_access
+    FOR EACH ROW
+EXECUTE FUNCTION record_collection_access_sync_change();
+
+-- Accepting an invitation, role changes and leaving change what members see.
+CREATE OR REPLACE FUNCTION record_member_sync_change()
+    RETURNS TRIGGER AS $$
+DECLARE
+    changed organization_members%ROWTYPE;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    PERFORM record_access_sync_change(changed.user_id, ci.item_type, ci.item_id)
+    FROM org_collections c
+             JOIN collection_items ci ON ci.collection_id = c.id
+    WHERE c.organization_id = changed.organization_id;
+    RETURN changed;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_organization_members_sync
+    AFTER UPDATE OR DELETE ON organization_members
+    FOR EACH ROW
+EXECUTE FUNCTION record_member_sync_change();
+
+-- Existing items start out in the feed of everyone who can see them.
+SELECT record_sync_change(u.user_id, 'login', l.id, false)
+FROM logins l, item_users('login', l.id, l.owner_id) u;
+
+SELECT record_sync_change(u.user_id, 'payment', p.id, false)
+FROM payments p, item_users('payment', p.id, p.owner_id) u;
+
+SELECT record_sync_change(u.user_id, 'secured_note', n.id, false)
+FROM secured_notes n, item_users('secured_note', n.id, n.owner_id) u;
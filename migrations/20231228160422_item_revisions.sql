@@ -0,0 +1,10 @@
+-- Per item revision for optimistic concurrency. Updates bump it and are refused when the
+-- client's `If-Match` names an older one.
+alter table logins
+    add column revision bigint not null default 1;
+
+alter table payments
+    add column revision bigint not null default 1;
+
+alter table secured_notes
+    add column revision bigint not null default 1;
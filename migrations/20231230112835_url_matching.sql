@@ -0,0 +1,14 @@
+-- How a login's linked websites are matched against the page the browser extension is on.
+alter table logins
+    add column match_strategy varchar(32) not null default 'base_domain';
+
+-- Domains a user treats as one site for base domain matching, e.g. google.com and youtube.com.
+create table equivalent_domains
+(
+    id         serial primary key,
+    user_id    integer      not null references users (id) on delete cascade,
+    domains    varchar(255) not null,
+    created_at timestamp    not null default now()
+);
+
+create index equivalent_domains_user_idx on equivalent_domains (user_id);
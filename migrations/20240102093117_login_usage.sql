@@ -0,0 +1,5 @@
+-- `used_at` is set whenever a login is filled in or copied, and counted in `use_count`.
+alter table logins
+    add column use_count integer not null default 0;
+
+create index logins_used_at_idx on logins (owner_id, used_at);
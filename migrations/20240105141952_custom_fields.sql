@@ -0,0 +1,38 @@
+-- User defined fields of logins, payments and secured notes.
+create table custom_fields
+(
+    id         serial primary key,
+    item_type  varchar(32)  not null,
+    item_id    integer      not null,
+    name       varchar(255) not null,
+    value      text         not null,
+    field_type varchar(32)  not null,
+    linked_to  varchar(32),
+    position   integer      not null
+);
+
+create index custom_fields_item_idx on custom_fields (item_type, item_id, position);
+
+-- Like attachments, custom fields go together with their item.
+CREATE OR REPLACE FUNCTION delete_item_custom_fields()
+    RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM custom_fields WHERE item_type = TG_ARGV[0] AND item_id = OLD.id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_delete_login_custom_fields
+    AFTER DELETE ON logins
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_custom_fields('login');
+
+CREATE TRIGGER trigger_delete_payment_custom_fields
+    AFTER DELETE ON payments
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_custom_fields('payment');
+
+CREATE TRIGGER trigger_delete_secured_note_custom_fields
+    AFTER DELETE ON secured_notes
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_custom_fields('secured_note');
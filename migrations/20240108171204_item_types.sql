@@ -0,0 +1,282 @@
+-- Identities, SSH keys, API credentials, Wi-Fi networks and software licenses. Like the other
+-- items they have an owner, a revision, custom fields and attachments, and can be shared.
+create table identities
+(
+    id              serial primary key,
+    owner_id        integer      not null,
+    name            varchar(255) not null default '',
+    full_name       varchar(255) not null default '',
+    email           varchar(255) not null default '',
+    phone           varchar(255) not null default '',
+    address         varchar(255) not null default '',
+    city            varchar(255) not null default '',
+    postal_code     varchar(255) not null default '',
+    country         varchar(255) not null default '',
+    passport_number varchar(255) not null default '',
+    passport_expiry date,
+    note            text         not null default '',
+    color           varchar(255) not null default 'red',
+    revision        bigint       not null default 1,
+    created_at      timestamp    not null default now(),
+    modified_at     timestamp    not null default now()
+);
+
+create table ssh_keys
+(
+    id          serial primary key,
+    owner_id    integer      not null,
+    name        varchar(255) not null default '',
+    private_key text         not null default '',
+    public_key  text         not null default '',
+    fingerprint varchar(255) not null default '',
+    passphrase  varchar(255) not null default '',
+    note        text         not null default '',
+    color       varchar(255) not null default 'red',
+    revision    bigint       not null default 1,
+    created_at  timestamp    not null default now(),
+    modified_at timestamp    not null default now()
+);
+
+create table api_credentials
+(
+    id          serial primary key,
+    owner_id    integer      not null,
+    name        varchar(255) not null default '',
+    api_key     varchar(255) not null default '',
+    secret      text         not null default '',
+    endpoint    varchar(255) not null default '',
+    expires_on  date,
+    note        text         not null default '',
+    color       varchar(255) not null default 'red',
+    revision    bigint       not null default 1,
+    created_at  timestamp    not null default now(),
+    modified_at timestamp    not null default now()
+);
+
+create table wifi_networks
+(
+    id            serial primary key,
+    owner_id      integer      not null,
+    name          varchar(255) not null default '',
+    ssid          varchar(255) not null default '',
+    security_type varchar(32)  not null default 'wpa2',
+    password      varchar(255) not null default '',
+    hidden        boolean      not null default false,
+    note          text         not null default '',
+    color         varchar(255) not null default 'red',
+    revision      bigint       not null default 1,
+    created_at    timestamp    not null default now(),
+    modified_at   timestamp    not null default now()
+);
+
+create table software_licenses
+(
+    id            serial primary key,
+    owner_id      integer      not null,
+    name          varchar(255) not null default '',
+    product       varchar(255) not null default '',
+    license_key   text         not null default '',
+    seats         integer      not null default 1,
+    purchase_date date,
+    licensed_to   varchar(255) not null default '',
+    note          text         not null default '',
+    color         varchar(255) not null default 'red',
+    revision      bigint       not null default 1,
+    created_at    timestamp    not null default now(),
+    modified_at   timestamp    not null default now()
+);
+
+create index identities_owner_idx on identities (owner_id);
+create index ssh_keys_owner_idx on ssh_keys (owner_id);
+create index api_credentials_owner_idx on api_credentials (owner_id);
+create index wifi_networks_owner_idx on wifi_networks (owner_id);
+create index software_licenses_owner_idx on software_licenses (owner_id);
+
+CREATE OR REPLACE FUNCTION item_owner(p_item_type varchar, p_item_id integer)
+    RETURNS integer AS $$
+SELECT CASE p_item_type
+           WHEN 'login' THEN (SELECT owner_id FROM logins WHERE id = p_item_id)
+           WHEN 'payment' THEN (SELECT owner_id FROM payments WHERE id = p_item_id)
+           WHEN 'secured_note' THEN (SELECT owner_id FROM secured_notes WHERE id = p_item_id)
+           WHEN 'identity' THEN (SELECT owner_id FROM identities WHERE id = p_item_id)
+           WHEN 'ssh_key' THEN (SELECT owner_id FROM ssh_keys WHERE id = p_item_id)
+           WHEN 'api_credential' THEN (SELECT owner_id FROM api_credentials WHERE id = p_item_id)
+           WHEN 'wifi_network' THEN (SELECT owner_id FROM wifi_networks WHERE id = p_item_id)
+           WHEN 'software_license' THEN (SELECT owner_id FROM software_licenses WHERE id = p_item_id)
+           END;
+$$ LANGUAGE sql STABLE;
+
+CREATE TRIGGER trigger_update_modified_at
+    BEFORE UPDATE ON identities
+    FOR EACH ROW
+EXECUTE FUNCTION update_modified_at();
+
+CREATE TRIGGER trigger_delete_identity_attachments
+    AFTER DELETE ON identities
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_attachments('identity');
+
+CREATE TRIGGER trigger_delete_identity_shares
+    AFTER DELETE ON identities
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_shares('identity');
+
+CREATE TRIGGER trigger_delete_identity_collection_entries
+    AFTER DELETE ON identities
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_collection_entries('identity');
+
+CREATE TRIGGER trigger_delete_identity_custom_fields
+    AFTER DELETE ON identities
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_custom_fields('identity');
+
+CREATE TRIGGER trigger_identity_sync
+    AFTER INSERT OR UPDATE ON identities
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('identity');
+
+CREATE TRIGGER trigger_identity_sync_delete
+    BEFORE DELETE ON identities
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('identity');
+
+CREATE TRIGGER trigger_update_modified_at
+    BEFORE UPDATE ON ssh_keys
+    FOR EACH ROW
+EXECUTE FUNCTION update_modified_at();
+
+CREATE TRIGGER trigger_delete_ssh_key_attachments
+    AFTER DELETE ON ssh_keys
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_attachments('ssh_key');
+
+CREATE TRIGGER trigger_delete_ssh_key_shares
+    AFTER DELETE ON ssh_keys
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_shares('ssh_key');
+
+CREATE TRIGGER trigger_delete_ssh_key_collection_entries
+    AFTER DELETE ON ssh_keys
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_collection_entries('ssh_key');
+
+CREATE TRIGGER trigger_delete_ssh_key_custom_fields
+    AFTER DELETE ON ssh_keys
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_custom_fields('ssh_key');
+
+CREATE TRIGGER trigger_ssh_key_sync
+    AFTER INSERT OR UPDATE ON ssh_keys
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('ssh_key');
+
+CREATE TRIGGER trigger_ssh_key_sync_delete
+    BEFORE DELETE ON ssh_keys
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('ssh_key');
+
+CREATE TRIGGER trigger_update_modified_at
+    BEFORE UPDATE ON api_credentials
+    FOR EACH ROW
+EXECUTE FUNCTION update_modified_at();
+
+CREATE TRIGGER trigger_delete_api_credential_attachments
+    AFTER DELETE ON api_credentials
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_attachments('api_credential');
+
+CREATE TRIGGER trigger_delete_api_credential_shares
+    AFTER DELETE ON api_credentials
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_shares('api_credential');
+
+CREATE TRIGGER trigger_delete_api_credential_collection_entries
+    AFTER DELETE ON api_credentials
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_collection_entries('api_credential');
+
+CREATE TRIGGER trigger_delete_api_credential_custom_fields
+    AFTER DELETE ON api_credentials
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_custom_fields('api_credential');
+
+CREATE TRIGGER trigger_api_credential_sync
+    AFTER INSERT OR UPDATE ON api_credentials
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('api_credential');
+
+CREATE TRIGGER trigger_api_credential_sync_delete
+    BEFORE DELETE ON api_credentials
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('api_credential');
+
+CREATE TRIGGER trigger_update_modified_at
+    BEFORE UPDATE ON wifi_networks
+    FOR EACH ROW
+EXECUTE FUNCTION update_modified_at();
+
+CREATE TRIGGER trigger_delete_wifi_network_attachments
+    AFTER DELETE ON wifi_networks
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_attachments('wifi_network');
+
+CREATE TRIGGER trigger_delete_wifi_network_shares
+    AFTER DELETE ON wifi_networks
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_shares('wifi_network');
+
+CREATE TRIGGER trigger_delete_wifi_network_collection_entries
+    AFTER DELETE ON wifi_networks
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_collection_entries('wifi_network');
+
+CREATE TRIGGER trigger_delete_wifi_network_custom_fields
+    AFTER DELETE ON wifi_networks
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_custom_fields('wifi_network');
+
+CREATE TRIGGER trigger_wifi_network_sync
+    AFTER INSERT OR UPDATE ON wifi_networks
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('wifi_network');
+
+CREATE TRIGGER trigger_wifi_network_sync_delete
+    BEFORE DELETE ON wifi_networks
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('wifi_network');
+
+CREATE TRIGGER trigger_update_modified_at
+    BEFORE UPDATE ON software_licenses
+    FOR EACH ROW
+EXECUTE FUNCTION update_modified_at();
+
+CREATE TRIGGER trigger_delete_software_license_attachments
+    AFTER DELETE ON software_licenses
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_attachments('software_license');
+
+CREATE TRIGGER trigger_delete_software_license_shares
+    AFTER DELETE ON software_licenses
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_shares('software_license');
+
+CREATE TRIGGER trigger_delete_software_license_collection_entries
+    AFTER DELETE ON software_licenses
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_collection_entries('software_license');
+
+CREATE TRIGGER trigger_delete_software_license_custom_fields
+    AFTER DELETE ON software_licenses
+    FOR EACH ROW
+EXECUTE FUNCTION delete_item_custom_fields('software_license');
+
+CREATE TRIGGER trigger_software_license_sync
+    AFTER INSERT OR UPDATE ON software_licenses
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('software_license');
+
+CREATE TRIGGER trigger_software_license_sync_delete
+    BEFORE DELETE ON software_licenses
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('software_license');
@@ -0,0 +1,7 @@
+-- Wrong passwords since the last lockout; the send is locked for a while once it reaches the limit.
+alter table sends
+    add column failed_attempts integer not null default 0,
+    add column locked_until    timestamp;
+
+-- Lets the cleanup job find finished sends without a scan.
+create index sends_expires_at_idx on sends (expires_at);
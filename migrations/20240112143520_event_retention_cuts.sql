@@ -0,0 +1,9 @@
+-- The newest chained event each retention run deleted. The oldest remaining event links to
+-- it, so verification can tell a retention cut from events removed by hand.
+create table event_retention_cuts
+(
+    id         serial primary key,
+    event_id   bigint      not null,
+    hash       varchar(64) not null,
+    created_at timestamp   not null default now()
+);
@@ -0,0 +1,11 @@
+-- Session tokens carry the generation they were issued in and are rejected once it's bumped,
+-- replacing tokens_valid_after, whose one second resolution let tokens issued in the same
+-- second as a password change survive it. Users who already signed out everywhere start at
+-- generation 1, which signs out their older sessions once more.
+alter table users
+    add column token_generation integer not null default 0;
+
+update users set token_generation = 1 where tokens_valid_after > 0;
+
+alter table users
+    drop column tokens_valid_after;
@@ -0,0 +1,5 @@
+-- The brand backfill missed Maestro's 59 prefix, which the service detects as Maestro.
+update payments
+set brand = 'maestro'
+where brand = 'unknown'
+  and regexp_replace(card_number, '[^0-9]', '', 'g') ~ '^59';
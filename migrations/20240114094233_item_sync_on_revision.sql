@@ -0,0 +1,107 @@
+-- Only edits bump an item's revision. Updates that leave it alone, like recording that a login
+-- was used, don't change what devices sync, so they no longer go into the change feed. Inserts
+-- can't look at OLD, so they get a trigger of their own.
+
+DROP TRIGGER trigger_login_sync ON logins;
+
+CREATE TRIGGER trigger_login_sync
+    AFTER INSERT ON logins
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('login');
+
+CREATE TRIGGER trigger_login_sync_update
+    AFTER UPDATE ON logins
+    FOR EACH ROW
+    WHEN (OLD.revision IS DISTINCT FROM NEW.revision)
+EXECUTE FUNCTION record_item_sync_change('login');
+
+DROP TRIGGER trigger_payment_sync ON payments;
+
+CREATE TRIGGER trigger_payment_sync
+    AFTER INSERT ON payments
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('payment');
+
+CREATE TRIGGER trigger_payment_sync_update
+    AFTER UPDATE ON payments
+    FOR EACH ROW
+    WHEN (OLD.revision IS DISTINCT FROM NEW.revision)
+EXECUTE FUNCTION record_item_sync_change('payment');
+
+DROP TRIGGER trigger_secured_note_sync ON secured_notes;
+
+CREATE TRIGGER trigger_secured_note_sync
+    AFTER INSERT ON secured_notes
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('secured_note');
+
+CREATE TRIGGER trigger_secured_note_sync_update
+    AFTER UPDATE ON secured_notes
+    FOR EACH ROW
+    WHEN (OLD.revision IS DISTINCT FROM NEW.revision)
+EXECUTE FUNCTION record_item_sync_change('secured_note');
+
+DROP TRIGGER trigger_identity_sync ON identities;
+
+CREATE TRIGGER trigger_identity_sync
+    AFTER INSERT ON identities
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('identity');
+
+CREATE TRIGGER trigger_identity_sync_update
+    AFTER UPDATE ON identities
+    FOR EACH ROW
+    WHEN (OLD.revision IS DISTINCT FROM NEW.revision)
+EXECUTE FUNCTION record_item_sync_change('identity');
+
+DROP TRIGGER trigger_ssh_key_sync ON ssh_keys;
+
+CREATE TRIGGER trigger_ssh_key_sync
+    AFTER INSERT ON ssh_keys
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('ssh_key');
+
+CREATE TRIGGER trigger_ssh_key_sync_update
+    AFTER UPDATE ON ssh_keys
+    FOR EACH ROW
+    WHEN (OLD.revision IS DISTINCT FROM NEW.revision)
+EXECUTE FUNCTION record_item_sync_change('ssh_key');
+
+DROP TRIGGER trigger_api_credential_sync ON api_credentials;
+
+CREATE TRIGGER trigger_api_credential_sync
+    AFTER INSERT ON api_credentials
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('api_credential');
+
+CREATE TRIGGER trigger_api_credential_sync_update
+    AFTER UPDATE ON api_credentials
+    FOR EACH ROW
+    WHEN (OLD.revision IS DISTINCT FROM NEW.revision)
+EXECUTE FUNCTION record_item_sync_change('api_credential');
+
+DROP TRIGGER trigger_wifi_network_sync ON wifi_networks;
+
+CREATE TRIGGER trigger_wifi_network_sync
+    AFTER INSERT ON wifi_networks
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('wifi_network');
+
+CREATE TRIGGER trigger_wifi_network_sync_update
+    AFTER UPDATE ON wifi_networks
+    FOR EACH ROW
+    WHEN (OLD.revision IS DISTINCT FROM NEW.revision)
+EXECUTE FUNCTION record_item_sync_change('wifi_network');
+
+DROP TRIGGER trigger_software_license_sync ON software_licenses;
+
+CREATE TRIGGER trigger_software_license_sync
+    AFTER INSERT ON software_licenses
+    FOR EACH ROW
+EXECUTE FUNCTION record_item_sync_change('software_license');
+
+CREATE TRIGGER trigger_software_license_sync_update
+    AFTER UPDATE ON software_licenses
+    FOR EACH ROW
+    WHEN (OLD.revision IS DISTINCT FROM NEW.revision)
+EXECUTE FUNCTION record_item_sync_change('software_license');
@@ -0,0 +1,222 @@
+use jsonwebtoken::EncodingKey;
+use log::{error, info};
+use rocket::{get, post, State};
+use rocket::http::{Cookie, CookieJar};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::mailer::{EmailTemplate, Mailer};
+use crate::models::auth_model::PasswordChangeDto;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::password_policy_model::PasswordPolicy;
+use crate::models::push_model::PushEvent;
+use crate::models::user_model::User;
+use crate::models::user_token_model::{PasswordForgotDto, PasswordResetDto, TokenDto, TokenPurpose};
+use crate::models::validation_model::Validated;
+use crate::persistence::auth_dao::issue_token;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::organization_dao::OrganizationDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::user_token_dao::UserTokenDao;
+use crate::persistence::users_dao::UsersDao;
+
+/// Fails with every rule `password` breaks. `identity` holds the user's username, email and
+/// names, which the password may not contain.
+pub fn check_password(policy: &PasswordPolicy, password: &str, identity: &[&str]) -> Result<(), APIError> {
+    let violations = policy.check(password, identity);
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(APIError::PasswordPolicy(violations))
+    }
+}
+
+/// The server policy, tightened by every organization `user_id` is a member of.
+pub async fn effective_password_policy(user_id: i32, policy: &PasswordPolicy, organization_dao: &(dyn OrganizationDao + Sync + Send)) -> Result<PasswordPolicy, APIError> {
+    let org_policy = organization_dao.get_member_password_policy(user_id).await?;
+
+    Ok(policy.stricter(&org_policy))
+}
+
+/// Checks a new password for an existing user against their effective policy.
+pub async fn require_password_policy(user: &User, password: &str, policy: &PasswordPolicy, organization_dao: &(dyn OrganizationDao + Sync + Send)) -> Result<(), APIError> {
+    let policy = effective_password_policy(user.id, policy, organization_dao).await?;
+
+    check_password(&policy, password, &[&user.username, &user.email, &user.first_name, &user.last_name])
+}
+
+/// Emails `user` a link to prove they own `email`, either their current address or the one
+/// they want to change to. Mail failures are logged rather than failing the request.
+pub async fn send_email_verification(user: &User, email: &str, purpose: TokenPurpose, mailer: &Mailer, token_dao: &(dyn UserTokenDao + Sync + Send)) -> Result<(), APIError> {
+    let token = token_dao.create_token(user.id, purpose, email).await?;
+
+    let (template, path) = match purpose {
+        TokenPurpose::ChangeEmail => (EmailTemplate::ChangeEmail, "/confirm-email"),
+        _ => (EmailTemplate::VerifyEmail, "/verify-email"),
+    };
+    let link = mailer.link(path, &token);
+    let valid_hours = (purpose.valid_minutes() / 60).to_string();
+
+    if let Err(err) = mailer.send(email, template, &[
+        ("first_name", &user.first_name),
+        ("email", email),
+        ("link", &link),
+        ("valid_hours", &valid_hours),
+    ]).await {
+        error!("Failed to send verification email to user {}: {}", user.id, err);
+    }
+
+    Ok(())
+}
+
+#[post("/user/verify-email", data = "<token>")]
+pub async fn verify_email(token: Json<TokenDto>, token_dao: &State<Box<dyn UserTokenDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>) -> Result<(), APIError> {
+    let token = token_dao.redeem_token(&token.token, TokenPurpose::VerifyEmail).await?
+        .ok_or_else(|| APIError::BadRequest(String::from("Invalid or expired link.")))?;
+
+    let verified = users_dao.verify_email(token.user_id, &token.email).await?;
+
+    if !verified {
+        return Err(APIError::BadRequest(String::from("The email address was changed since this link was sent.")));
+    }
+
+    Ok(())
+}
+
+#[post("/user/verify-email/resend")]
+pub async fn resend_email_verification(user: User, mailer: &State<Mailer>, token_dao: &State<Box<dyn UserTokenDao + Sync + Send>>) -> Result<(), APIError> {
+    if user.email_verified {
+        return Err(APIError::BadRequest(String::from("Email address is already verified.")));
+    }
+
+    send_email_verification(&user, &user.email, TokenPurpose::VerifyEmail, mailer, token_dao.as_ref()).await
+}
+
+/// Completes an email change started with `PUT /user/<id>`, and lets the old address know.
+#[post("/user/confirm-email", data = "<token>")]
+pub async fn confirm_email(token: Json<TokenDto>, mailer: &State<Mailer>, token_dao: &State<Box<dyn UserTokenDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<(), APIError> {
+    let token = token_dao.redeem_token(&token.token, TokenPurpose::ChangeEmail).await?
+        .ok_or_else(|| APIError::BadRequest(String::from("Invalid or expired link.")))?;
+
+    let user = users_dao.get_user(token.user_id).await?;
+
+    users_dao.set_email(user.id, &token.email).await
+        .map_err(|_| APIError::BadRequest(format!("{} is already in use.", token.email)))?;
+
+    events.log(NewEvent::new(EventType::EmailChanged, Some(user.id), &client).details(token.email.clone()));
+
+    if let Err(err) = mailer.send(&user.email, EmailTemplate::EmailChanged, &[
+        ("first_name", &user.first_name),
+        ("email", &token.email),
+    ]).await {
+        error!("Failed to notify user {} of their email change: {}", user.id, err);
+    }
+
+    Ok(())
+}
+
+/// Emails a password reset link. Always succeeds, so it can't be used to find out which
+/// addresses have an account.
+#[post("/password/forgot", data = "<forgot>")]
+pub async fn forgot_password(forgot: Validated<PasswordForgotDto>, mailer: &State<Mailer>, token_dao: &State<Box<dyn UserTokenDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>) -> Result<(), APIError> {
+    let user = match users_dao.get_user_by_email(forgot.email.trim()).await {
+        Ok(user) => user,
+        Err(_) => {
+            info!("Password reset requested for unknown address.");
+            return Ok(());
+        }
+    };
+
+    let token = token_dao.create_token(user.id, TokenPurpose::ResetPassword, &user.email).await?;
+    let link = mailer.link("/reset-password", &token);
+    let valid_minutes = TokenPurpose::ResetPassword.valid_minutes().to_string();
+
+    // Sent in the background, so the response time doesn't reveal whether the address exists.
+    let mailer = mailer.inner().clone();
+    tokio::spawn(async move {
+        if let Err(err) = mailer.send(&user.email, EmailTemplate::PasswordReset, &[
+            ("first_name", &user.first_name),
+            ("username", &user.username),
+            ("link", &link),
+            ("valid_minutes", &valid_minutes),
+        ]).await {
+            error!("Failed to send password reset email to user {}: {}", user.id, err);
+        }
+    });
+
+    Ok(())
+}
+
+#[post("/password/reset", data = "<reset>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn reset_password(
+    reset: Json<PasswordResetDto>,
+    token_dao: &State<Box<dyn UserTokenDao + Sync + Send>>,
+    users_dao: &State<Box<dyn UsersDao + Sync + Send>>,
+    organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>,
+    policy: &State<PasswordPolicy>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
+    push: &State<PushHub>,
+) -> Result<(), APIError> {
+    let invalid = || APIError::BadRequest(String::from("Invalid or expired link."));
+
+    // Check the password before using up the token, so a rejected one can be retried.
+    let token = token_dao.find_token(&reset.token, TokenPurpose::ResetPassword).await?
+        .ok_or_else(invalid)?;
+    let user = users_dao.get_user(token.user_id).await?;
+    require_password_policy(&user, &reset.password, policy, organization_dao.as_ref()).await?;
+
+    token_dao.redeem_token(&reset.token, TokenPurpose::ResetPassword).await?
+        .ok_or_else(invalid)?;
+
+    users_dao.set_password(user.id, &reset.password).await?;
+
+    events.log(NewEvent::new(EventType::PasswordReset, Some(user.id), &client));
+    push.publish(vec![user.id], PushEvent::Logout);
+
+    Ok(())
+}
+
+/// Changes the master password and signs out every other session. The session making the
+/// request gets a fresh token.
+#[post("/me/password", data = "<change>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn change_password(
+    user: User,
+    change: Json<PasswordChangeDto>,
+    users_dao: &State<Box<dyn UsersDao + Sync + Send>>,
+    organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>,
+    policy: &State<PasswordPolicy>,
+    jwt_encoding_key: &State<EncodingKey>,
+    jar: &CookieJar<'_>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
+    push: &State<PushHub>,
+) -> Result<(), APIError> {
+    let correct = users_dao.check_password(user.id, &change.current_password).await?;
+    if !correct {
+        return Err(APIError::InvalidCredentials(String::from("Current password is wrong.")));
+    }
+
+    if change.new_password == change.current_password {
+        return Err(APIError::BadRequest(String::from("New password must differ from the current one.")));
+    }
+    require_password_policy(&user, &change.new_password, policy, organization_dao.as_ref()).await?;
+
+    let generation = users_dao.set_password(user.id, &change.new_password).await?;
+
+    jar.add_private(Cookie::new("Authorization", issue_token(user.id, generation, jwt_encoding_key).0));
+    events.log(NewEvent::new(EventType::PasswordChanged, Some(user.id), &client));
+    push.publish(vec![user.id], PushEvent::Logout);
+
+    Ok(())
+}
+
+/// The password requirements that apply to the user, for clients to check passwords up front.
+#[get("/me/password-policy")]
+pub async fn get_password_policy(user: User, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, policy: &State<PasswordPolicy>) -> Result<Json<PasswordPolicy>, APIError> {
+    effective_password_policy(user.id, policy, organization_dao.as_ref()).await
+        .map(Json)
+}
@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use log::{error, info};
+use rocket::{get, post, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::internal_error;
+use crate::models::event_model::{BrokenLink, ChainVerification, ClientInfo, EventFilter, EventPage, EventType, NewEvent};
+use crate::models::storage_model::{FsckReport, UnreadableFile};
+use crate::models::user_model::Admin;
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_chain::CheckpointSigner;
+use crate::persistence::event_dao::EventDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::file_storage;
+
+/// Uploads insert their row before the file is committed, so rows and files younger than this
+/// may be mid-upload and are left out of the comparison.
+const FSCK_GRACE_PERIOD: Duration = Duration::from_secs(15 * 60);
+
+/// Cross-checks attachment rows against `upload/`. With `repair`, rows whose file is missing
+/// are deleted together with files that no row points to and leftover staged uploads.
+/// Corrupted and unreadable files are only reported.
+#[post("/admin/attachments/fsck?<repair>")]
+pub async fn fsck_attachments(admin: Admin, repair: Option<bool>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<FsckReport>, APIError> {
+    // Files are listed before rows: a listed file was committed after its row was inserted,
+    // so it can't be mistaken for an orphan.
+    let stored_files: HashSet<String> = file_storage::stored_files(FSCK_GRACE_PERIOD).await
+        .map_err(|err| internal_error(&err))?
+        .into_iter().collect();
+    let mut staged_files = file_storage::staged_files(FSCK_GRACE_PERIOD).await
+        .map_err(|err| internal_error(&err))?;
+    staged_files.sort();
+    let attachments = attachment_dao.get_all_attachments(FSCK_GRACE_PERIOD).await?;
+
+    let known_ids: HashSet<String> = attachments.iter().map(|(a, _)| a.id.to_string()).collect();
+    let mut orphaned_files: Vec<String> = stored_files.difference(&known_ids).cloned().collect();
+    orphaned_files.sort();
+
+    let mut missing_files = vec![];
+    let mut corrupted_files = vec![];
+    let mut unreadable_files = vec![];
+
+    for (attachment, settled) in attachments {
+        if !stored_files.contains(&attachment.id.to_string()) {
+            if settled {
+                missing_files.push(attachment);
+            }
+            continue;
+        }
+
+        if let Some(expected) = &attachment.checksum {
+            match file_storage::checksum(&file_storage::attachment_path(attachment.id)).await {
+                Ok(actual) if &actual != expected => corrupted_files.push(attachment),
+                Ok(_) => {}
+                Err(err) => {
+                    error!("Failed to checksum attachment {}: {:?}", attachment.id, err);
+                    unreadable_files.push(UnreadableFile { attachment, error: err.to_string() });
+                }
+            }
+        }
+    }
+
+    let repair = repair.unwrap_or(false);
+    info!("Attachment fsck by user {}: {} missing, {} orphaned, {} corrupted, {} unreadable, {} staged, repair = {}",
+        admin.0.id, missing_files.len(), orphaned_files.len(), corrupted_files.len(), unreadable_files.len(), staged_files.len(), repair);
+
+    if repair {
+        let missing_ids: Vec<i32> = missing_files.iter().map(|a| a.id).collect();
+        attachment_dao.delete_attachments(&missing_ids).await?;
+
+        for name in &orphaned_files {
+            if let Err(err) = tokio::fs::remove_file(Path::new(file_storage::UPLOAD_DIR).join(name)).await {
+                error!("Failed to remove orphaned file {}: {:?}", name, err);
+            }
+        }
+
+        for name in &staged_files {
+            if let Err(err) = tokio::fs::remove_file(Path::new(file_storage::TMP_DIR).join(name)).await {
+                error!("Failed to remove staged file {}: {:?}", name, err);
+            }
+        }
+
+        events.log(NewEvent::new(EventType::AttachmentsRepaired, Some(admin.0.id), &client)
+            .details(format!("{} missing rows, {} orphaned files, {} staged files removed", missing_files.len(), orphaned_files.len(), staged_files.len())));
+    }
+
+    Ok(Json(FsckReport { missing_files, orphaned_files, corrupted_files, unreadable_files, staged_files, repaired: repair }))
+}
+
+/// Events of all users, newest first.
+#[get("/admin/events?<filter..>")]
+pub async fn get_all_events(_admin: Admin, filter: EventFilter, event_dao: &State<Box<dyn EventDao + Sync + Send>>) -> Result<Json<EventPage>, APIError> {
+    let query = filter.into_query(None).map_err(APIError::BadRequest)?;
+
+    event_dao.get_events(&query).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+const VERIFY_PAGE_SIZE: i64 = 1000;
+
+/// Walks the audit log in insertion order and stops at the first event that doesn't link to
+/// its predecessor, globally or within its actor's chain, or no longer matches its own hash.
+/// Unchained events are only accepted before the first chained one, and a missing prefix only
+/// up to the latest retention cut. Checkpoints are checked against the signing key and the
+/// hashes of the events they cover; every checkpoint whose event is never reached is flagged.
+#[post("/admin/events/verify")]
+pub async fn verify_events(admin: Admin, event_dao: &State<Box<dyn EventDao + Sync + Send>>, signer: &State<CheckpointSigner>) -> Result<Json<ChainVerification>, APIError> {
+    let checkpoints = event_dao.get_checkpoints().await?;
+    let retention_cut = event_dao.get_retention_cut().await?;
+
+    let mut invalid_checkpoints = Vec::new();
+    let mut checkpointed: HashMap<i64, Vec<(i32, String)>> = HashMap::new();
+    if signer.enabled() {
+        for checkpoint in &checkpoints {
+            if signer.verify(checkpoint.event_id, &checkpoint.hash, &checkpoint.signature) != Some(true) {
+                invalid_checkpoints.push(checkpoint.id);
+                continue;
+            }
+            checkpointed.entry(checkpoint.event_id).or_default().push((checkpoint.id, checkpoint.hash.clone()));
+        }
+    }
+
+    let mut events_checked = 0;
+    let mut unchained_events = 0;
+    let mut broken_link = None;
+    let mut prev_hash: Option<String> = None;
+    let mut actor_hashes: HashMap<i32, String> = HashMap::new();
+    let mut after_id = 0;
+
+    'walk: loop {
+        let links = event_dao.get_links(after_id, VERIFY_PAGE_SIZE).await?;
+        if links.is_empty() {
+            break;
+        }
+
+        for stored in links {
+            after_id = stored.id;
+
+            let broken = |chain: &str, expected: &str, actual: &str| Some(BrokenLink {
+                event_id: stored.id,
+                chain: chain.to_string(),
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            });
+
+            let Some(hash) = stored.hash else {
+                // Events logged before chaining was introduced can't follow a chained one.
+                if prev_hash.is_some() {
+                    broken_link = broken("unchained", "", "");
+                    break 'walk;
+                }
+                unchained_events += 1;
+                continue;
+            };
+
+            // The first chained event links to the genesis or, once retention deleted the
+            // events before it, to the newest event removed by retention.
+            let from_cut = prev_hash.is_none() && retention_cut.as_ref()
+                .is_some_and(|(cut_id, cut_hash)| stored.id > *cut_id && &stored.link.prev_hash == cut_hash);
+            let expected = prev_hash.clone().unwrap_or_default();
+            if !from_cut && stored.link.prev_hash != expected {
+                broken_link = broken("global", &expected, &stored.link.prev_hash);
+                break 'walk;
+            }
+            if let Some(actor_id) = stored.link.actor_id {
+                // An actor's earlier events may have been deleted by retention as well.
+                let expected = actor_hashes.get(&actor_id);
+                let valid = match expected {
+                    Some(prev) => &stored.link.actor_prev_hash == prev,
+                    None => stored.link.actor_prev_hash.is_empty() || retention_cut.is_some(),
+                };
+                if !valid {
+                    broken_link = broken("actor", expected.map_or("", String::as_str), &stored.link.actor_prev_hash);
+                    break 'walk;
+                }
+            }
+            let actual = stored.link.hash();
+            if actual != hash {
+                broken_link = broken("content", &hash, &actual);
+                break 'walk;
+            }
+
+            if let Some(covering) = checkpointed.remove(&stored.id) {
+                invalid_checkpoints.extend(covering.into_iter()
+                    .filter(|(_, checkpoint_hash)| checkpoint_hash != &hash)
+                    .map(|(checkpoint_id, _)| checkpoint_id));
+            }
+
+            if let Some(actor_id) = stored.link.actor_id {
+                actor_hashes.insert(actor_id, hash.clone());
+            }
+            prev_hash = Some(hash);
+            events_checked += 1;
+        }
+    }
+
+    // Checkpoints left over cover events that are gone, unless retention deleted them. After a
+    // broken link the rest of the log is unverified, so they can't be judged.
+    if broken_link.is_none() {
+        let cut_id = retention_cut.as_ref().map_or(0, |(cut_id, _)| *cut_id);
+        invalid_checkpoints.extend(checkpointed.into_iter()
+            .filter(|(event_id, _)| *event_id > cut_id)
+            .flat_map(|(_, covering)| covering.into_iter().map(|(checkpoint_id, _)| checkpoint_id)));
+    }
+    invalid_checkpoints.sort_unstable();
+
+    info!("Audit log verification by user {}: {} events checked, broken link: {:?}, invalid checkpoints: {:?}",
+        admin.0.id, events_checked, broken_link.as_ref().map(|l| l.event_id), invalid_checkpoints);
+
+    Ok(Json(ChainVerification {
+        events_checked,
+        unchained_events,
+        broken_link,
+        checkpoints_checked: if signer.enabled() { checkpoints.len() as i64 } else { 0 },
+        invalid_checkpoints,
+        checkpoints_verifiable: signer.enabled(),
+        public_key: signer.public_key(),
+    }))
+}
@@ -0,0 +1,42 @@
+use rocket::{delete, get, post, put, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::item_handler::{create_item, delete_item, get_item, get_items, update_item};
+use crate::models::api_credential_model::{ApiCredential, ApiCredentialDto};
+use crate::models::event_model::ClientInfo;
+use crate::models::revision_model::{IfMatch, Tagged};
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
+
+#[post("/api_credentials", data = "<api_credential>")]
+pub async fn create_api_credential(user: User, api_credential: Validated<ApiCredentialDto>, api_credential_dao: &State<Box<dyn ItemDao<ApiCredential> + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<ApiCredential>>, APIError> {
+    create_item(user, api_credential.0, api_credential_dao, client, events, push).await
+}
+
+#[get("/api_credentials/<id>")]
+pub async fn get_api_credential(user: User, id: i32, api_credential_dao: &State<Box<dyn ItemDao<ApiCredential> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Tagged<Json<ApiCredential>>, APIError> {
+    get_item(user, id, api_credential_dao, share_dao, client, events).await
+}
+
+#[get("/api_credentials")]
+pub async fn get_api_credentials(user: User, api_credential_dao: &State<Box<dyn ItemDao<ApiCredential> + Sync + Send>>) -> Result<Json<Vec<ApiCredential>>, APIError> {
+    get_items(user, api_credential_dao).await
+}
+
+#[put("/api_credentials/<id>", data = "<api_credential>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_api_credential(user: User, id: i32, api_credential: Validated<ApiCredentialDto>, if_match: IfMatch, api_credential_dao: &State<Box<dyn ItemDao<ApiCredential> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<ApiCredential>>, APIError> {
+    update_item(user, id, api_credential.0, if_match, api_credential_dao, share_dao, client, events, push).await
+}
+
+#[delete("/api_credentials/<id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_api_credential(user: User, id: i32, api_credential_dao: &State<Box<dyn ItemDao<ApiCredential> + Sync + Send>>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    delete_item(user, id, api_credential_dao, attachment_dao, share_dao, client, events, push).await
+}
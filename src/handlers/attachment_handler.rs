@@ -0,0 +1,213 @@
+use log::error;
+use rocket::{Data, delete, get, post, State};
+use rocket::data::ToByteUnit;
+use rocket::http::ContentType;
+use rocket::serde::json::Json;
+use rocket_multipart_form_data::{MultipartFormData, MultipartFormDataError, MultipartFormDataField, MultipartFormDataOptions};
+use tokio::fs;
+
+use crate::APIError;
+use crate::handlers::internal_error;
+use crate::handlers::item_access::require_permission;
+use crate::models::attachment_model::{File, FileDto};
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::item_model::ItemType;
+use crate::models::share_model::Permission;
+use crate::models::storage_model::{AttachmentPolicy, ContentLength};
+use crate::models::user_model::User;
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::file_storage;
+use crate::persistence::share_dao::ShareDao;
+use crate::persistence::storage_dao::StorageDao;
+
+/// Items of every type take attachments, e.g. `/identities/<id>/attachments`.
+#[allow(clippy::too_many_arguments)]
+#[post("/<item_type>/<id>/attachments", data = "<paste>")]
+pub async fn upload_attachment<'r>(
+    user: User,
+    item_type: ItemType,
+    id: i32,
+    ct: &ContentType,
+    content_length: Option<ContentLength>,
+    paste: Data<'r>,
+    attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>,
+    share_dao: &State<Box<dyn ShareDao + Sync + Send>>,
+    storage_dao: &State<Box<dyn StorageDao + Sync + Send>>,
+    attachment_policy: &State<AttachmentPolicy>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
+) -> Result<Json<Vec<File>>, APIError> {
+    upload(user, item_type, id, ct, content_length, paste, attachment_dao, share_dao, storage_dao, attachment_policy, client, events).await
+}
+
+#[get("/<item_type>/<id>/attachments")]
+pub async fn item_attachments(user: User, item_type: ItemType, id: i32, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>) -> Result<Json<Vec<File>>, APIError> {
+    require_permission(user.id, item_type, id, Permission::Read, share_dao).await?;
+
+    let files = attachment_dao.get_attachments(item_type, &[id]).await?;
+
+    Ok(Json(files))
+}
+
+#[get("/attachments/<id>")]
+pub async fn download_attachment(user: User, id: i32, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<fs::File, APIError> {
+    let file = attachment_dao.get_attachment(id).await?;
+
+    require_permission(user.id, file.item_type, file.item_id, Permission::Read, share_dao).await?;
+
+    let path = file_storage::attachment_path(file.id);
+
+    if let Some(expected) = &file.checksum {
+        let actual = file_storage::checksum(&path).await
+            .map_err(|err| internal_error(&err))?;
+
+        if &actual != expected {
+            error!("Attachment {} failed integrity check: expected {}, got {}", file.id, expected, actual);
+            return Err(APIError::InternalError(String::from("Attachment failed integrity check.")));
+        }
+    }
+
+    let attachment = fs::File::open(path).await
+        .map_err(|err| internal_error(&err))?;
+
+    events.log(NewEvent::new(EventType::AttachmentDownloaded, Some(user.id), &client)
+        .item(file.item_type, file.item_id)
+        .details(format!("attachment {}", file.id)));
+
+    Ok(attachment)
+}
+
+#[delete("/attachments/<id>")]
+pub async fn delete_attachment(user: User, id: i32, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<(), APIError> {
+    let file = attachment_dao.get_attachment(id).await?;
+
+    require_permission(user.id, file.item_type, file.item_id, Permission::Write, share_dao).await?;
+
+    attachment_dao.delete_attachment(id).await?;
+
+    if let Err(err) = file_storage::remove(&file_storage::attachment_path(id)).await {
+        error!("Failed to remove attachment {}: {:?}", id, err);
+    }
+
+    events.log(NewEvent::new(EventType::AttachmentDeleted, Some(user.id), &client)
+        .item(file.item_type, file.item_id)
+        .details(format!("attachment {}", file.id)));
+
+    Ok(())
+}
+
+/// Removes the stored files of attachments whose rows were already deleted along with their item.
+/// Rows go first: a file left behind is an orphan for fsck to collect, whereas a row without
+/// its file would be handed out as a broken download.
+pub async fn remove_attachment_files(attachments: &[File]) {
+    for attachment in attachments {
+        if let Err(err) = file_storage::remove(&file_storage::attachment_path(attachment.id)).await {
+            error!("Failed to remove attachment {}: {:?}", attachment.id, err);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn upload(
+    user: User,
+    item_type: ItemType,
+    item_id: i32,
+    ct: &ContentType,
+    content_length: Option<ContentLength>,
+    paste: Data<'_>,
+    attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>,
+    share_dao: &State<Box<dyn ShareDao + Sync + Send>>,
+    storage_dao: &State<Box<dyn StorageDao + Sync + Send>>,
+    attachment_policy: &State<AttachmentPolicy>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
+) -> Result<Json<Vec<File>>, APIError> {
+    require_permission(user.id, item_type, item_id, Permission::Write, share_dao).await?;
+
+    // These checks turn uploads away before their body is read. Concurrent uploads may still
+    // pass them together, so `save_file` checks each file again when it is stored.
+    let usage = storage_dao.get_storage_usage(user.id).await?;
+    let item_files = attachment_dao.count_attachments(item_type, item_id).await?;
+
+    if item_files >= usage.max_files_per_item as i64 {
+        return Err(APIError::PayloadTooLarge(format!("Item already has the maximum of {} attachments.", usage.max_files_per_item)));
+    }
+
+    // Content-Length covers the whole multipart body, so it is an upper bound of the file size.
+    if let Some(ContentLength(length)) = content_length {
+        if length > usage.remaining() as u64 {
+            return Err(APIError::PayloadTooLarge(format!("Upload exceeds your storage quota ({} of {} bytes used).", usage.used, usage.quota)));
+        }
+    }
+
+    let size_limit = 100.megabytes().as_u64().min(usage.remaining() as u64);
+    let options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
+        MultipartFormDataField::file("file").size_limit(size_limit),
+    ]);
+
+    let multipart_form = MultipartFormData::parse(ct, paste, options).await
+        .map_err(|err| match err {
+            MultipartFormDataError::DataTooLargeError(_) => APIError::PayloadTooLarge(format!("Upload exceeds the limit of {} bytes.", size_limit)),
+            err => APIError::BadRequest(err.to_string()),
+        })?;
+    let file_data = multipart_form.files.get("file")
+        .ok_or(APIError::BadRequest(String::from("Missing file field.")))?;
+
+    // Several parts may arrive in one body, and chunked bodies carry no Content-Length,
+    // so both limits are enforced per part rather than only against the request.
+    if file_data.len() as i64 > usage.max_files_per_item as i64 - item_files {
+        return Err(APIError::PayloadTooLarge(format!("Item can take only {} more attachments.", usage.max_files_per_item as i64 - item_files)));
+    }
+
+    let mut remaining = usage.remaining() as u64;
+    let mut response_files = vec![];
+
+    for file in file_data {
+        let file_name = file.file_name.as_ref()
+            .ok_or(APIError::BadRequest(String::from("Missing file name.")))?;
+        let file_type = file.content_type.as_ref().map(|t| t.to_string()).unwrap_or_default();
+
+        if !attachment_policy.permits(&file_type) {
+            return Err(APIError::UnsupportedMediaType(format!("Attachments of type '{}' are not allowed.", file_type)));
+        }
+
+        let staged = file_storage::stage(&file.path).await
+            .map_err(|err| internal_error(&err))?;
+
+        if staged.size > remaining {
+            file_storage::discard(&staged).await;
+            return Err(APIError::PayloadTooLarge(format!("Upload exceeds your storage quota ({} of {} bytes used).", usage.quota as u64 - remaining, usage.quota)));
+        }
+        remaining -= staged.size;
+
+        let file_dto = FileDto {
+            name: file_name.to_owned(),
+            file_type,
+            size: staged.size as i32,
+            checksum: staged.checksum.clone(),
+        };
+
+        let saved = match attachment_dao.save_file(user.id, file_dto, item_type, item_id, &usage).await {
+            Ok(saved) => saved,
+            Err(err) => {
+                file_storage::discard(&staged).await;
+                return Err(APIError::from(err));
+            }
+        };
+
+        if let Err(err) = file_storage::commit(&staged, &file_storage::attachment_path(saved.id)).await {
+            file_storage::discard(&staged).await;
+            attachment_dao.delete_attachment(saved.id).await?;
+            return Err(internal_error(&err));
+        }
+
+        events.log(NewEvent::new(EventType::AttachmentUploaded, Some(user.id), &client)
+            .item(item_type, item_id)
+            .details(format!("attachment {}", saved.id)));
+
+        response_files.push(saved);
+    }
+
+    Ok(Json(response_files))
+}
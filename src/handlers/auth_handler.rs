@@ -7,8 +7,13 @@ use rocket::serde::json::Json;
 
 use crate::APIError;
 use crate::models::auth_model::{Credentials, TokenClaims};
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
 use crate::models::user_model::User;
 use crate::persistence::auth_dao::AuthDao;
+use crate::handlers::webauthn_handler::require_second_factor;
+use crate::models::webauthn_model::CredentialUsage;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::webauthn_dao::WebauthnDao;
 
 pub struct Token(pub String);
 
@@ -16,6 +21,7 @@ pub struct Token(pub String);
 pub enum TokenError {
     Missing,
     Invalid,
+    Forbidden,
 }
 
 #[rocket::async_trait]
@@ -32,26 +38,44 @@ impl<'r> FromRequest<'r> for Token {
 }
 
 
+/// Password login. Users with a security key get a pending second factor instead of a session,
+/// to be completed through `/webauthn/second-factor`.
 #[post("/login", data = "<credentials>")]
 pub async fn login<'a>(
     credentials: Json<Credentials>,
     auth_dao: &State<Box<dyn AuthDao + Sync + Send>>,
+    webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>,
     jwt_encoding_key: &State<EncodingKey>,
     jar: &'a CookieJar<'_>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
 ) -> Result<Json<User>, APIError> {
+    let username = credentials.username.clone();
+
     match auth_dao.login(credentials.0, jwt_encoding_key.inner()).await {
         Ok(u) => {
+            let second_factor = webauthn_dao.has_credentials(u.0.id, CredentialUsage::SecondFactor).await?;
+            if second_factor {
+                require_second_factor(u.0.id, jar);
+                return Err(APIError::Forbidden(String::from("Second factor required.")));
+            }
+
             jar.add_private(Cookie::new("Authorization", u.1.0.clone()));
+            events.log(NewEvent::new(EventType::LoginSucceeded, Some(u.0.id), &client));
             Ok(Json(u.0))
         }
-        Err(err) => Err(APIError::InvalidCredentials(err.to_string())),
+        Err(err) => {
+            events.log(NewEvent::new(EventType::LoginFailed, None, &client).details(username));
+            Err(APIError::InvalidCredentials(err.to_string()))
+        }
     }
 }
 
 #[get("/logout")]
-pub async fn logout<'a>(_user: User, token: Token, auth_dao: &State<Box<dyn AuthDao + Sync + Send>>, jar: &'a CookieJar<'_>) -> Result<(), APIError> {
+pub async fn logout<'a>(user: User, token: Token, auth_dao: &State<Box<dyn AuthDao + Sync + Send>>, jar: &'a CookieJar<'_>, client: ClientInfo, events: &State<EventLogger>) -> Result<(), APIError> {
     jar.remove_private(Cookie::named("Authorization"));
-    auth_dao.logout(token).await.map_err(|e| APIError::InternalError(e.to_string()))?;
+    auth_dao.logout(token).await?;
+    events.log(NewEvent::new(EventType::Logout, Some(user.id), &client));
     Ok(())
 }
 
@@ -62,10 +86,13 @@ pub async fn status<'a>(jar: &'a CookieJar<'_>, decoding_key: &State<DecodingKey
         let authorization = authorization.to_string();
         let authorization = authorization.split("=").skip(1).next().unwrap();
 
-        let claims = jsonwebtoken::decode::<TokenClaims>(authorization, decoding_key, &jsonwebtoken::Validation::default());
+        let claims = match jsonwebtoken::decode::<TokenClaims>(authorization, decoding_key, &jsonwebtoken::Validation::default()) {
+            Ok(claims) => claims.claims,
+            Err(_) => return Json(false),
+        };
 
-        if let Ok(blacklisted) = auth_dao.token_blacklisted(Token(authorization.to_owned())).await {
-            return Json(!blacklisted && claims.is_ok());
+        if let (Ok(blacklisted), Ok(revoked)) = (auth_dao.token_blacklisted(Token(authorization.to_owned())).await, auth_dao.token_revoked(&claims).await) {
+            return Json(!blacklisted && !revoked);
         }
     }
 
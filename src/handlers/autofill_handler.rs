@@ -0,0 +1,65 @@
+use rocket::{delete, get, post, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::models::url_match_model::{CompiledPatterns, EquivalentDomains, EquivalentDomainsDto, LoginMatch, PageUrl};
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::equivalent_domains_dao::EquivalentDomainsDao;
+use crate::persistence::login_dao::LoginDao;
+
+/// Logins to suggest for the page at `url`, each matched by its own strategy. Best matches come
+/// first and, among equally good ones, the most recently used.
+#[get("/logins/match?<url>")]
+pub async fn match_logins(user: User, url: &str, login_dao: &State<Box<dyn LoginDao + Sync + Send>>, equivalent_domains_dao: &State<Box<dyn EquivalentDomainsDao + Sync + Send>>) -> Result<Json<Vec<LoginMatch>>, APIError> {
+    let page = PageUrl::parse(url).ok_or_else(|| APIError::BadRequest(format!("{} is not a URL with a host.", url)))?;
+    let equivalent_domains = equivalent_domains_dao.get_equivalent_domains(user.id).await?;
+
+    let logins = login_dao.get_logins(user.id, None).await?;
+    let patterns = CompiledPatterns::of(&logins);
+
+    let mut matches: Vec<LoginMatch> = logins.into_iter()
+        .filter_map(|mut login| {
+            login.mask_custom_fields();
+            let strength = login.linked_websites.iter()
+                .filter(|website| !website.is_empty())
+                .filter_map(|website| login.match_strategy.matches(website, &page, &equivalent_domains, &patterns))
+                .max()?;
+            Some(LoginMatch { strength, login })
+        })
+        .collect();
+    // `used_at` is always formatted the same way, so it sorts as text.
+    matches.sort_by(|a, b| b.strength.cmp(&a.strength).then_with(|| b.login.used_at.cmp(&a.login.used_at)));
+
+    Ok(Json(matches))
+}
+
+#[get("/equivalent_domains")]
+pub async fn get_equivalent_domains(user: User, equivalent_domains_dao: &State<Box<dyn EquivalentDomainsDao + Sync + Send>>) -> Result<Json<Vec<EquivalentDomains>>, APIError> {
+    equivalent_domains_dao.get_equivalent_domains(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Adds a group of domains to treat as one site. They are stored as their base domains, so
+/// `www.google.com` stands for all of `google.com`.
+#[post("/equivalent_domains", data = "<group>")]
+pub async fn create_equivalent_domains(user: User, group: Validated<EquivalentDomainsDto>, equivalent_domains_dao: &State<Box<dyn EquivalentDomainsDao + Sync + Send>>) -> Result<Json<EquivalentDomains>, APIError> {
+    let domains = group.base_domains();
+    if domains.len() < 2 {
+        return Err(APIError::UnprocessableEntity(String::from("A group needs at least two different base domains.")));
+    }
+
+    let group = equivalent_domains_dao.create_equivalent_domains(user.id, &domains).await?;
+
+    Ok(Json(group))
+}
+
+#[delete("/equivalent_domains/<id>")]
+pub async fn delete_equivalent_domains(user: User, id: i32, equivalent_domains_dao: &State<Box<dyn EquivalentDomainsDao + Sync + Send>>) -> Result<(), APIError> {
+    if !equivalent_domains_dao.delete_equivalent_domains(id, user.id).await? {
+        return Err(APIError::NotFound(String::from("Equivalent domains not found.")));
+    }
+
+    Ok(())
+}
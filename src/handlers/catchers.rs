@@ -0,0 +1,47 @@
+use rocket::{catch, Request};
+use rocket::http::Status;
+use rocket::response::status::Custom;
+use rocket::serde::json::Json;
+
+use crate::models::error_model::{ErrorCode, ErrorResponse};
+use crate::models::validation_model::RejectedBody;
+
+// Errors Rocket raises before or instead of a handler, e.g. failing guards, unknown routes or
+// bodies that don't parse. They get the same envelope as `APIError`.
+
+#[catch(400)]
+pub fn bad_request(request: &Request) -> Json<ErrorResponse> {
+    Json(rejected_body(request).unwrap_or(ErrorResponse::new(ErrorCode::BadRequest, "The request is malformed.")))
+}
+
+#[catch(401)]
+pub fn unauthorized() -> Json<ErrorResponse> {
+    Json(ErrorResponse::new(ErrorCode::Unauthorized, "Not signed in or the session has expired."))
+}
+
+#[catch(404)]
+pub fn not_found(request: &Request) -> Json<ErrorResponse> {
+    Json(ErrorResponse::new(ErrorCode::NotFound, format!("No resource at {}.", request.uri().path())))
+}
+
+#[catch(422)]
+pub fn unprocessable_entity(request: &Request) -> Json<ErrorResponse> {
+    Json(rejected_body(request).unwrap_or(ErrorResponse::new(ErrorCode::ValidationFailed, "The request body is missing fields or has fields of the wrong type.")))
+}
+
+#[catch(500)]
+pub fn internal_error() -> Json<ErrorResponse> {
+    Json(ErrorResponse::new(ErrorCode::InternalError, "Something went wrong! Please try again."))
+}
+
+#[catch(default)]
+pub fn default(status: Status, request: &Request) -> Custom<Json<ErrorResponse>> {
+    let message = status.reason().unwrap_or("Unknown error.");
+    let response = rejected_body(request).unwrap_or(ErrorResponse::new(ErrorCode::from_status(status), message));
+    Custom(status, Json(response))
+}
+
+/// The details a `Validated` body left behind when it was rejected.
+fn rejected_body(request: &Request) -> Option<ErrorResponse> {
+    request.local_cache(|| RejectedBody(None)).0.clone()
+}
@@ -0,0 +1,19 @@
+use rocket::{get, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::models::custom_field_model::CustomFieldMatch;
+use crate::models::user_model::User;
+use crate::persistence::custom_field_dao::CustomFieldDao;
+
+/// Custom fields whose name contains `name`, ignoring case, with the items they belong to.
+#[get("/custom_fields?<name>")]
+pub async fn search_custom_fields(user: User, name: &str, custom_field_dao: &State<Box<dyn CustomFieldDao + Sync + Send>>) -> Result<Json<Vec<CustomFieldMatch>>, APIError> {
+    if name.trim().is_empty() {
+        return Err(APIError::BadRequest(String::from("Search for at least one character.")));
+    }
+
+    custom_field_dao.search_custom_fields(user.id, name.trim()).await
+        .map(Json)
+        .map_err(APIError::from)
+}
@@ -0,0 +1,220 @@
+use log::{error, info};
+use rocket::{delete, get, post, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::account_handler::require_password_policy;
+use crate::mailer::{EmailTemplate, Mailer};
+use crate::models::api_credential_model::ApiCredential;
+use crate::models::emergency_access_model::{EmergencyAccess, EmergencyAccessDto, EmergencyAccessEvent, EmergencyAccessStatus, EmergencyAccessType, EmergencyVault, TakeoverDto};
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::identity_model::Identity;
+use crate::models::password_policy_model::PasswordPolicy;
+use crate::models::push_model::PushEvent;
+use crate::models::share_model::Permission;
+use crate::models::software_license_model::SoftwareLicense;
+use crate::models::ssh_key_model::SshKey;
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::models::wifi_network_model::WifiNetwork;
+use crate::persistence::emergency_access_dao::EmergencyAccessDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::login_dao::LoginDao;
+use crate::persistence::organization_dao::OrganizationDao;
+use crate::persistence::payment_dao::PaymentDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::secured_note_dao::SecuredNoteDao;
+use crate::persistence::users_dao::UsersDao;
+
+/// Designates a trusted contact. The contact has to accept before they can request access.
+#[post("/emergency-access", data = "<access>")]
+pub async fn create_emergency_access(user: User, access: Validated<EmergencyAccessDto>, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>) -> Result<Json<EmergencyAccess>, APIError> {
+    let grantee = users_dao.get_user_by_username(&access.username).await
+        .map_err(|_| APIError::NotFound(format!("User {} not found.", access.username)))?;
+
+    if grantee.id == user.id {
+        return Err(APIError::BadRequest(String::from("Users can't be their own trusted contact.")));
+    }
+
+    emergency_access_dao.create_emergency_access(user.id, grantee.id, access.access_type, access.wait_days).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Trusted contacts of the user, including pending recovery requests against their vault.
+#[get("/emergency-access/granted")]
+pub async fn get_granted(user: User, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<Json<Vec<EmergencyAccess>>, APIError> {
+    emergency_access_dao.get_granted_by(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Users who designated the user as a trusted contact.
+#[get("/emergency-access/trusted")]
+pub async fn get_trusted(user: User, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<Json<Vec<EmergencyAccess>>, APIError> {
+    emergency_access_dao.get_granted_to(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+#[post("/emergency-access/<id>/accept")]
+pub async fn accept_emergency_access(user: User, id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<Json<EmergencyAccess>, APIError> {
+    let access = get_as_grantee(id, user.id, emergency_access_dao).await?;
+    advance(access, EmergencyAccessEvent::Accept, emergency_access_dao).await.map(Json)
+}
+
+/// Requests access to the grantor's vault, starting the waiting period. The grantor is emailed
+/// so they can reject the request before it is granted.
+#[post("/emergency-access/<id>/initiate")]
+pub async fn initiate_recovery(user: User, id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>, mailer: &State<Mailer>) -> Result<Json<EmergencyAccess>, APIError> {
+    let access = get_as_grantee(id, user.id, emergency_access_dao).await?;
+    let access = advance(access, EmergencyAccessEvent::Initiate, emergency_access_dao).await?;
+
+    info!("User {} requested emergency access to the vault of user {}, granted in {} days unless rejected.",
+        access.grantee_username, access.grantor_username, access.wait_days);
+
+    let grantor = users_dao.get_user(access.grantor_id).await?;
+    let granted_on = (chrono::Utc::now() + chrono::Duration::days(access.wait_days as i64)).format("%Y-%m-%d").to_string();
+    let link = mailer.url("/emergency-access");
+
+    if let Err(err) = mailer.send(&grantor.email, EmailTemplate::EmergencyAccessRequested, &[
+        ("first_name", &grantor.first_name),
+        ("grantee", &access.grantee_username),
+        ("access_type", access.access_type.as_str()),
+        ("granted_on", &granted_on),
+        ("link", &link),
+    ]).await {
+        error!("Failed to email user {} about emergency access {}: {}", grantor.id, access.id, err);
+    }
+
+    Ok(Json(access))
+}
+
+/// Grants a pending request before the waiting period ends.
+#[post("/emergency-access/<id>/approve")]
+pub async fn approve_recovery(user: User, id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<Json<EmergencyAccess>, APIError> {
+    let access = get_as_grantor(id, user.id, emergency_access_dao).await?;
+    advance(access, EmergencyAccessEvent::Approve, emergency_access_dao).await.map(Json)
+}
+
+/// Rejects a pending request, or revokes an approved one, keeping the contact.
+#[post("/emergency-access/<id>/reject")]
+pub async fn reject_recovery(user: User, id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<Json<EmergencyAccess>, APIError> {
+    let access = get_as_grantor(id, user.id, emergency_access_dao).await?;
+    advance(access, EmergencyAccessEvent::Reject, emergency_access_dao).await.map(Json)
+}
+
+/// Removes a trusted contact. The contact may also remove themselves.
+#[delete("/emergency-access/<id>")]
+pub async fn delete_emergency_access(user: User, id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<(), APIError> {
+    let access = get_emergency_access(id, emergency_access_dao).await?;
+
+    if access.grantor_id != user.id && access.grantee_id != user.id {
+        return Err(APIError::Unauthorized(String::from("Emergency access doesn't belong to user.")));
+    }
+
+    emergency_access_dao.delete_emergency_access(id).await
+        .map_err(APIError::from)
+}
+
+/// The grantor's own items, once recovery was approved.
+#[allow(clippy::too_many_arguments)]
+#[get("/emergency-access/<id>/vault")]
+pub async fn get_vault(user: User, id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>, login_dao: &State<Box<dyn LoginDao + Sync + Send>>, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>, secured_note_dao: &State<Box<dyn SecuredNoteDao + Sync + Send>>, identity_dao: &State<Box<dyn ItemDao<Identity> + Sync + Send>>, ssh_key_dao: &State<Box<dyn ItemDao<SshKey> + Sync + Send>>, api_credential_dao: &State<Box<dyn ItemDao<ApiCredential> + Sync + Send>>, wifi_network_dao: &State<Box<dyn ItemDao<WifiNetwork> + Sync + Send>>, software_license_dao: &State<Box<dyn ItemDao<SoftwareLicense> + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<EmergencyVault>, APIError> {
+    let access = get_approved(id, user.id, emergency_access_dao).await?;
+
+    let logins = login_dao.get_logins(access.grantor_id, None).await?;
+    let payments = payment_dao.get_payments(access.grantor_id, None).await?;
+    let secured_notes = secured_note_dao.get_secured_notes(access.grantor_id, None).await?;
+    let identities = identity_dao.get_items(access.grantor_id, None).await?;
+    let ssh_keys = ssh_key_dao.get_items(access.grantor_id, None).await?;
+    let api_credentials = api_credential_dao.get_items(access.grantor_id, None).await?;
+    let wifi_networks = wifi_network_dao.get_items(access.grantor_id, None).await?;
+    let software_licenses = software_license_dao.get_items(access.grantor_id, None).await?;
+
+    events.log(NewEvent::new(EventType::EmergencyVaultViewed, Some(user.id), &client)
+        .details(format!("vault of {}", access.grantor_username)));
+
+    Ok(Json(EmergencyVault {
+        logins: logins.into_iter().filter(|l| l.permission == Permission::Owner).collect(),
+        payments: payments.into_iter().filter(|p| p.permission == Permission::Owner).collect(),
+        secured_notes: secured_notes.into_iter().filter(|n| n.permission == Permission::Owner).collect(),
+        identities: identities.into_iter().filter(|i| i.permission == Permission::Owner).collect(),
+        ssh_keys: ssh_keys.into_iter().filter(|i| i.permission == Permission::Owner).collect(),
+        api_credentials: api_credentials.into_iter().filter(|i| i.permission == Permission::Owner).collect(),
+        wifi_networks: wifi_networks.into_iter().filter(|i| i.permission == Permission::Owner).collect(),
+        software_licenses: software_licenses.into_iter().filter(|i| i.permission == Permission::Owner).collect(),
+    }))
+}
+
+/// Sets a new password on the grantor's account, for contacts with takeover access.
+#[post("/emergency-access/<id>/takeover", data = "<takeover>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn takeover(user: User, id: i32, takeover: Json<TakeoverDto>, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, policy: &State<PasswordPolicy>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    let access = get_approved(id, user.id, emergency_access_dao).await?;
+
+    if access.access_type != EmergencyAccessType::Takeover {
+        return Err(APIError::Unauthorized(String::from("Emergency access is view only.")));
+    }
+
+    let grantor = users_dao.get_user(access.grantor_id).await?;
+    require_password_policy(&grantor, &takeover.new_password, policy, organization_dao.as_ref()).await?;
+
+    users_dao.set_password(access.grantor_id, &takeover.new_password).await?;
+
+    info!("User {} took over the account of user {}.", access.grantee_username, access.grantor_username);
+    events.log(NewEvent::new(EventType::EmergencyTakeover, Some(user.id), &client)
+        .details(format!("account of {}", access.grantor_username)));
+    push.publish(vec![access.grantor_id], PushEvent::Logout);
+
+    Ok(())
+}
+
+async fn advance(access: EmergencyAccess, event: EmergencyAccessEvent, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<EmergencyAccess, APIError> {
+    let next = access.status.apply(event)
+        .ok_or(APIError::BadRequest(format!("Can't {:?} emergency access in state {}.", event, access.status.as_str())))?;
+
+    let moved = emergency_access_dao.transition(access.id, access.status, next).await?;
+
+    if !moved {
+        return Err(APIError::BadRequest(String::from("Emergency access changed in the meantime.")));
+    }
+
+    get_emergency_access(access.id, emergency_access_dao).await
+}
+
+async fn get_emergency_access(id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<EmergencyAccess, APIError> {
+    emergency_access_dao.get_emergency_access(id).await
+        .map_err(|_| APIError::NotFound(String::from("Emergency access not found.")))
+}
+
+async fn get_as_grantor(id: i32, user_id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<EmergencyAccess, APIError> {
+    let access = get_emergency_access(id, emergency_access_dao).await?;
+
+    if access.grantor_id != user_id {
+        return Err(APIError::Unauthorized(String::from("Emergency access wasn't granted by user.")));
+    }
+
+    Ok(access)
+}
+
+async fn get_as_grantee(id: i32, user_id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<EmergencyAccess, APIError> {
+    let access = get_emergency_access(id, emergency_access_dao).await?;
+
+    if access.grantee_id != user_id {
+        return Err(APIError::Unauthorized(String::from("Emergency access wasn't granted to user.")));
+    }
+
+    Ok(access)
+}
+
+async fn get_approved(id: i32, user_id: i32, emergency_access_dao: &State<Box<dyn EmergencyAccessDao + Sync + Send>>) -> Result<EmergencyAccess, APIError> {
+    let access = get_as_grantee(id, user_id, emergency_access_dao).await?;
+
+    if access.status != EmergencyAccessStatus::RecoveryApproved {
+        return Err(APIError::Unauthorized(String::from("Emergency access hasn't been approved.")));
+    }
+
+    Ok(access)
+}
@@ -0,0 +1,17 @@
+use rocket::{get, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::models::event_model::{EventFilter, EventPage};
+use crate::models::user_model::User;
+use crate::persistence::event_dao::EventDao;
+
+/// Events the user caused, newest first.
+#[get("/events?<filter..>")]
+pub async fn get_events(user: User, filter: EventFilter, event_dao: &State<Box<dyn EventDao + Sync + Send>>) -> Result<Json<EventPage>, APIError> {
+    let query = filter.into_query(Some(user.id)).map_err(APIError::BadRequest)?;
+
+    event_dao.get_events(&query).await
+        .map(Json)
+        .map_err(APIError::from)
+}
@@ -1,11 +1,13 @@
-use log::error;
-
+use crate::APIError;
+use crate::models::DBError;
 use crate::persistence::users_dao::UsersDao;
 use crate::models::user_model::{User, UserDto, UserUpdateDto};
 
 #[derive(Debug, PartialEq)]
 pub enum HandlerError {
     BadRequest(String),
+    NotFound(String),
+    Conflict(String),
     InternalError(String),
 }
 
@@ -15,16 +17,24 @@ impl HandlerError {
     }
 }
 
+impl From<DBError> for HandlerError {
+    fn from(err: DBError) -> Self {
+        match APIError::from(err) {
+            APIError::NotFound(s) => HandlerError::NotFound(s),
+            APIError::Conflict(s) => HandlerError::Conflict(s),
+            APIError::UnprocessableEntity(s) => HandlerError::BadRequest(s),
+            _ => HandlerError::default_internal_error(),
+        }
+    }
+}
+
 pub async fn  get_user(id:i32, users_dao: &Box<dyn UsersDao + Sync + Send>,
 ) -> Result<User, HandlerError> {
     let user = users_dao.get_user(id).await;
 
     match user {
         Ok(user) => Ok(user),
-        Err(err) => {
-            error!("{:?}", err);
-            Err(HandlerError::default_internal_error())
-        }
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -37,10 +47,7 @@ pub async fn update_user(
 
     match user {
         Ok(user) => Ok(user),
-        Err(err) => {
-            error!("{:?}", err);
-            Err(HandlerError::default_internal_error())
-        }
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -53,10 +60,7 @@ pub async fn create_user(
 
     match user {
         Ok(user) => Ok(user),
-        Err(err) => {
-            error!("{:?}", err);
-            Err(HandlerError::default_internal_error())
-        }
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -68,9 +72,6 @@ pub async fn delete_user(
 
     match user {
         Ok(user) => Ok(user),
-        Err(err) => {
-            error!("{:?}", err);
-            Err(HandlerError::default_internal_error())
-        }
+        Err(err) => Err(err.into()),
     }
 }
@@ -0,0 +1,42 @@
+use rocket::{delete, get, post, put, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::item_handler::{create_item, delete_item, get_item, get_items, update_item};
+use crate::models::event_model::ClientInfo;
+use crate::models::identity_model::{Identity, IdentityDto};
+use crate::models::revision_model::{IfMatch, Tagged};
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
+
+#[post("/identities", data = "<identity>")]
+pub async fn create_identity(user: User, identity: Validated<IdentityDto>, identity_dao: &State<Box<dyn ItemDao<Identity> + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<Identity>>, APIError> {
+    create_item(user, identity.0, identity_dao, client, events, push).await
+}
+
+#[get("/identities/<id>")]
+pub async fn get_identity(user: User, id: i32, identity_dao: &State<Box<dyn ItemDao<Identity> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Tagged<Json<Identity>>, APIError> {
+    get_item(user, id, identity_dao, share_dao, client, events).await
+}
+
+#[get("/identities")]
+pub async fn get_identities(user: User, identity_dao: &State<Box<dyn ItemDao<Identity> + Sync + Send>>) -> Result<Json<Vec<Identity>>, APIError> {
+    get_items(user, identity_dao).await
+}
+
+#[put("/identities/<id>", data = "<identity>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_identity(user: User, id: i32, identity: Validated<IdentityDto>, if_match: IfMatch, identity_dao: &State<Box<dyn ItemDao<Identity> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<Identity>>, APIError> {
+    update_item(user, id, identity.0, if_match, identity_dao, share_dao, client, events, push).await
+}
+
+#[delete("/identities/<id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_identity(user: User, id: i32, identity_dao: &State<Box<dyn ItemDao<Identity> + Sync + Send>>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    delete_item(user, id, identity_dao, attachment_dao, share_dao, client, events, push).await
+}
@@ -0,0 +1,25 @@
+use rocket::State;
+
+use crate::APIError;
+use crate::models::item_model::ItemType;
+use crate::models::share_model::Permission;
+use crate::persistence::share_dao::ShareDao;
+
+/// Resolves what `user_id` may do with an item and fails unless it is at least `required`.
+/// Owners hold every permission; everyone else gets the strongest of what the item was shared
+/// with them with and what their organization collections grant.
+pub async fn require_permission(user_id: i32, item_type: ItemType, item_id: i32, required: Permission, share_dao: &State<Box<dyn ShareDao + Sync + Send>>) -> Result<Permission, APIError> {
+    let owner_id = share_dao.get_item_owner(item_type, item_id).await?;
+
+    let permission = if owner_id == user_id {
+        Some(Permission::Owner)
+    } else {
+        share_dao.get_permission(item_type, item_id, user_id).await?
+    };
+
+    match permission {
+        Some(permission) if permission >= required => Ok(permission),
+        Some(_) => Err(APIError::Unauthorized(format!("Insufficient permission on {} {}.", item_type, item_id))),
+        None => Err(APIError::Unauthorized(format!("Item {} {} doesn't belong to user.", item_type, item_id))),
+    }
+}
@@ -0,0 +1,87 @@
+use rocket::State;
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::attachment_handler::remove_attachment_files;
+use crate::handlers::item_access::require_permission;
+use crate::models::DBError;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::item_model::VaultItem;
+use crate::models::push_model::PushEvent;
+use crate::models::revision_model::{EditConflict, IfMatch, Tagged};
+use crate::models::share_model::Permission;
+use crate::models::user_model::User;
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
+
+// The create, read, update and delete flow of item types without rules of their own. Their
+// routes check what is particular to the type and hand over to these.
+
+pub async fn create_item<T: VaultItem>(user: User, item: T::Dto, item_dao: &State<Box<dyn ItemDao<T> + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<T>>, APIError> {
+    let item = item_dao.create_item(item, user.id).await?;
+
+    events.log(NewEvent::new(EventType::ItemCreated, Some(user.id), &client).item(T::ITEM_TYPE, item.id()));
+    push.publish(vec![user.id], PushEvent::ItemCreated { item_type: T::ITEM_TYPE, item_id: item.id() });
+
+    Ok(Tagged::new(item.revision(), Json(item)))
+}
+
+pub async fn get_item<T: VaultItem>(user: User, id: i32, item_dao: &State<Box<dyn ItemDao<T> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Tagged<Json<T>>, APIError> {
+    let permission = require_permission(user.id, T::ITEM_TYPE, id, Permission::HidePasswords, share_dao).await?;
+
+    let mut item = item_dao.get_item(id).await?;
+    if permission == Permission::HidePasswords {
+        item.hide_secrets();
+    }
+    item.set_permission(permission);
+
+    events.log(NewEvent::new(EventType::ItemViewed, Some(user.id), &client).item(T::ITEM_TYPE, id));
+
+    Ok(Tagged::new(item.revision(), Json(item)))
+}
+
+pub async fn get_items<T: VaultItem>(user: User, item_dao: &State<Box<dyn ItemDao<T> + Sync + Send>>) -> Result<Json<Vec<T>>, APIError> {
+    let mut items = item_dao.get_items(user.id, None).await?;
+    items.iter_mut().for_each(T::mask_custom_fields);
+
+    Ok(Json(items))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn update_item<T: VaultItem>(user: User, id: i32, item: T::Dto, if_match: IfMatch, item_dao: &State<Box<dyn ItemDao<T> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<T>>, APIError> {
+    let permission = require_permission(user.id, T::ITEM_TYPE, id, Permission::Write, share_dao).await?;
+    let submitted = item.clone();
+    let mut item = match item_dao.update_item(id, item, if_match.0).await {
+        Err(DBError::StaleRevision) => {
+            let mut current = item_dao.get_item(id).await?;
+            current.set_permission(permission);
+            return Err(APIError::EditConflict(Box::new(EditConflict::new(&current, &submitted))));
+        }
+        result => result?,
+    };
+    item.set_permission(permission);
+
+    events.log(NewEvent::new(EventType::ItemUpdated, Some(user.id), &client).item(T::ITEM_TYPE, id));
+    push.publish(share_dao.get_item_users(T::ITEM_TYPE, id).await?, PushEvent::ItemUpdated { item_type: T::ITEM_TYPE, item_id: id });
+
+    Ok(Tagged::new(item.revision(), Json(item)))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_item<T: VaultItem>(user: User, id: i32, item_dao: &State<Box<dyn ItemDao<T> + Sync + Send>>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    require_permission(user.id, T::ITEM_TYPE, id, Permission::Owner, share_dao).await?;
+    let user_ids = share_dao.get_item_users(T::ITEM_TYPE, id).await?;
+    let attachments = attachment_dao.get_attachments(T::ITEM_TYPE, &[id]).await?;
+
+    item_dao.delete_item(id).await?;
+
+    remove_attachment_files(&attachments).await;
+
+    events.log(NewEvent::new(EventType::ItemDeleted, Some(user.id), &client).item(T::ITEM_TYPE, id));
+    push.publish(user_ids, PushEvent::ItemDeleted { item_type: T::ITEM_TYPE, item_id: id });
+
+    Ok(())
+}
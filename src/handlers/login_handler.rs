@@ -3,64 +3,169 @@ use rocket::form::Form;
 use rocket::serde::json::Json;
 
 use crate::APIError;
-use crate::models::login_model::{Login, LoginDto};
+use crate::handlers::attachment_handler::remove_attachment_files;
+use crate::handlers::item_access::require_permission;
+use crate::models::DBError;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::item_model::ItemType;
+use crate::models::login_model::{Login, LoginDto, USAGE_VIEW_LIMIT};
+use crate::models::push_model::PushEvent;
+use crate::models::revision_model::{EditConflict, IfMatch, Tagged};
+use crate::models::share_model::Permission;
+use crate::models::url_match_model::{MatchStrategy, validate_regex_list};
 use crate::models::user_model::User;
+use crate::models::validation_model::{field_error, Validated, validate_url_list};
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
 use crate::persistence::login_dao::{Collection, LoginDao};
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
 
 #[post("/logins", data = "<login>")]
-pub async fn create_login(user: User, login: Json<LoginDto>, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Json<Login>, APIError> {
-    return login_dao.create_login(login.0, user.id).await
-        .map(|login| Json(login))
-        .map_err(|err| APIError::InternalError(err.to_string()));
+pub async fn create_login(user: User, login: Validated<LoginDto>, login_dao: &State<Box<dyn LoginDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<Login>>, APIError> {
+    if let Some(websites) = &login.linked_websites {
+        require_linked_websites(login.match_strategy.unwrap_or_default(), websites)?;
+    }
+
+    let login = login_dao.create_login(login.0, user.id).await?;
+
+    events.log(NewEvent::new(EventType::ItemCreated, Some(user.id), &client).item(ItemType::Login, login.id));
+    push.publish(vec![user.id], PushEvent::ItemCreated { item_type: ItemType::Login, item_id: login.id });
+
+    Ok(Tagged::new(login.revision, Json(login)))
 }
 
 #[get("/logins")]
 pub async fn get_logins(user: User, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Json<Vec<Login>>, APIError> {
-    return login_dao.get_logins(user.id).await
-        .map(|logins| Json(logins))
-        .map_err(|err| APIError::InternalError(err.to_string()));
+    let mut logins = login_dao.get_logins(user.id, None).await?;
+    logins.iter_mut().for_each(Login::mask_custom_fields);
+
+    Ok(Json(logins))
+}
+
+
+/// The user's logins that were ever used, most recently used first.
+#[get("/logins/recent?<limit>")]
+pub async fn get_recent_logins(user: User, limit: Option<usize>, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Json<Vec<Login>>, APIError> {
+    let mut logins = get_used_logins(user.id, login_dao).await?;
+    // `used_at` is always formatted the same way, so it sorts as text.
+    logins.sort_by(|a, b| b.used_at.cmp(&a.used_at));
+    logins.truncate(limit.unwrap_or(USAGE_VIEW_LIMIT));
+
+    Ok(Json(logins))
 }
 
+/// The user's logins that were ever used, most used first.
+#[get("/logins/most_used?<limit>")]
+pub async fn get_most_used_logins(user: User, limit: Option<usize>, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Json<Vec<Login>>, APIError> {
+    let mut logins = get_used_logins(user.id, login_dao).await?;
+    logins.sort_by(|a, b| b.use_count.cmp(&a.use_count).then_with(|| b.used_at.cmp(&a.used_at)));
+    logins.truncate(limit.unwrap_or(USAGE_VIEW_LIMIT));
+
+    Ok(Json(logins))
+}
+
+/// Called by autofill and copy actions, so the usage views know the login was used.
+#[post("/logins/<id>/used")]
+pub async fn record_login_use(user: User, id: i32, login_dao: &State<Box<dyn LoginDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>) -> Result<(), APIError> {
+    require_permission(user.id, ItemType::Login, id, Permission::HidePasswords, share_dao).await?;
+    login_dao.record_login_use(id).await?;
+
+    Ok(())
+}
 
 #[get("/logins/<id>")]
-pub async fn get_login(id: i32, user: User, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Json<Login>, APIError> {
-    validate_user_owns_login(user.id, id, login_dao).await?;
+pub async fn get_login(id: i32, user: User, login_dao: &State<Box<dyn LoginDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Tagged<Json<Login>>, APIError> {
+    let permission = require_permission(user.id, ItemType::Login, id, Permission::HidePasswords, share_dao).await?;
+
+    let mut login = login_dao.get_login(id).await?;
+    if permission == Permission::HidePasswords {
+        login.hide_secrets();
+    }
+    login.shared = permission != Permission::Owner;
+    login.permission = permission;
+
+    events.log(NewEvent::new(EventType::ItemViewed, Some(user.id), &client).item(ItemType::Login, id));
 
-    return login_dao.get_login(id).await
-        .map(|login| Json(login))
-        .map_err(|err| APIError::InternalError(err.to_string()));
+    Ok(Tagged::new(login.revision, Json(login)))
 }
 
 
 #[delete("/logins", data = "<collection>")]
-pub async fn delete_login(collection: Form<Collection>, user: User, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Json<Vec<i32>>, APIError> {
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_login(collection: Form<Collection>, user: User, login_dao: &State<Box<dyn LoginDao + Sync + Send>>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Json<Vec<i32>>, APIError> {
+    let mut item_users = Vec::with_capacity(collection.ids.len());
     for id in &collection.ids {
-        validate_user_owns_login(user.id, *id, login_dao).await?;
+        require_permission(user.id, ItemType::Login, *id, Permission::Owner, share_dao).await?;
+        item_users.push(share_dao.get_item_users(ItemType::Login, *id).await?);
     }
 
-    login_dao.delete_logins(&collection.ids).await
-        .map_err(|err| APIError::InternalError(err.to_string()))?;
+    let attachments = attachment_dao.get_attachments(ItemType::Login, &collection.ids).await?;
+
+    login_dao.delete_logins(&collection.ids).await?;
+
+    remove_attachment_files(&attachments).await;
+
+    for (id, user_ids) in collection.ids.iter().zip(item_users) {
+        events.log(NewEvent::new(EventType::ItemDeleted, Some(user.id), &client).item(ItemType::Login, *id));
+        push.publish(user_ids, PushEvent::ItemDeleted { item_type: ItemType::Login, item_id: *id });
+    }
 
     return Ok(Json(collection.ids.clone()));
 }
 
 #[put("/logins/<id>", data = "<login>")]
-pub async fn update_login(id: i32, login: Json<LoginDto>, user: User, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Json<Login>, APIError> {
-    validate_user_owns_login(user.id, id, login_dao).await?;
-
-    let result = login_dao.update_login(id, login.0).await
-        .map_err(|err| APIError::InternalError(err.to_string()))?;
+#[allow(clippy::too_many_arguments)]
+pub async fn update_login(id: i32, login: Validated<LoginDto>, if_match: IfMatch, user: User, login_dao: &State<Box<dyn LoginDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<Login>>, APIError> {
+    let permission = require_permission(user.id, ItemType::Login, id, Permission::Write, share_dao).await?;
+
+    if login.linked_websites.is_some() || login.match_strategy.is_some() {
+        let stored = login_dao.get_login(id).await?;
+        let strategy = login.match_strategy.unwrap_or(stored.match_strategy);
+        match &login.linked_websites {
+            Some(websites) => require_linked_websites(strategy, websites)?,
+            // Logins stored without websites have a single empty one.
+            None => {
+                let websites: Vec<String> = stored.linked_websites.into_iter().filter(|website| !website.is_empty()).collect();
+                require_linked_websites(strategy, &websites)?;
+            }
+        }
+    }
 
-    return Ok(Json(result));
+    let submitted = login.0.clone();
+    let mut result = match login_dao.update_login(id, login.0, if_match.0).await {
+        Err(DBError::StaleRevision) => {
+            let mut current = login_dao.get_login(id).await?;
+            current.shared = permission != Permission::Owner;
+            current.permission = permission;
+            return Err(APIError::EditConflict(Box::new(EditConflict::new(&current, &submitted))));
+        }
+        result => result?,
+    };
+    result.shared = permission != Permission::Owner;
+    result.permission = permission;
+
+    events.log(NewEvent::new(EventType::ItemUpdated, Some(user.id), &client).item(ItemType::Login, id));
+    push.publish(share_dao.get_item_users(ItemType::Login, id).await?, PushEvent::ItemUpdated { item_type: ItemType::Login, item_id: id });
+
+    return Ok(Tagged::new(result.revision, Json(result)));
 }
 
-async fn validate_user_owns_login(user_id: i32, login_id: i32, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<(), APIError> {
-    let login_owner = login_dao.get_login_owner(login_id).await
-        .map_err(|err| APIError::InternalError(err.to_string()))?;
+/// Linked websites have to be URLs, or regexes for logins matched by regex: the strategy sent
+/// along, or else the stored one.
+fn require_linked_websites(strategy: MatchStrategy, websites: &[String]) -> Result<(), APIError> {
+    let result = match strategy {
+        MatchStrategy::Regex => validate_regex_list(websites),
+        _ => validate_url_list(websites),
+    };
 
-    if login_owner != user_id {
-        return Err(APIError::Unauthorized(String::from("Login doesn't belong to user.")));
-    }
+    result.map_err(|err| APIError::Validation(vec![field_error("linked_websites", &err)]))
+}
 
-    Ok(())
+async fn get_used_logins(user_id: i32, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Vec<Login>, APIError> {
+    let mut logins = login_dao.get_logins(user_id, None).await?;
+    logins.retain(|login| login.use_count > 0);
+    logins.iter_mut().for_each(Login::mask_custom_fields);
+
+    Ok(logins)
 }
@@ -0,0 +1,109 @@
+use base64::{Engine as _, engine::general_purpose};
+use rocket::{delete, get, post, put, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::item_access::require_permission;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::item_model::ItemType;
+use crate::models::login_passkey_model::{LoginPasskey, LoginPasskeyDto, SignCountDto};
+use crate::models::share_model::Permission;
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::login_passkey_dao::LoginPasskeyDao;
+use crate::persistence::share_dao::ShareDao;
+
+/// Saves a passkey a client created for a website onto the login for that website.
+#[post("/logins/<id>/passkeys", data = "<passkey>")]
+pub async fn create_passkey(id: i32, user: User, passkey: Validated<LoginPasskeyDto>, passkey_dao: &State<Box<dyn LoginPasskeyDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<LoginPasskey>, APIError> {
+    let permission = require_permission(user.id, ItemType::Login, id, Permission::Write, share_dao).await?;
+
+    if passkey.rp_id.trim().is_empty() || passkey.private_key.is_empty() {
+        return Err(APIError::BadRequest(String::from("Passkeys need a relying party id and a private key.")));
+    }
+    for (field, value) in [("credential_id", &passkey.credential_id), ("user_handle", &passkey.user_handle)] {
+        if value.is_empty() || general_purpose::URL_SAFE_NO_PAD.decode(value).is_err() {
+            return Err(APIError::BadRequest(format!("{} must be base64url without padding.", field)));
+        }
+    }
+
+    let mut passkey = passkey_dao.create_passkey(id, passkey.0).await?;
+    passkey.permission = permission;
+
+    events.log(NewEvent::new(EventType::ItemUpdated, Some(user.id), &client)
+        .item(ItemType::Login, id)
+        .details(format!("passkey added for {}", passkey.rp_id)));
+
+    Ok(Json(passkey))
+}
+
+#[get("/logins/<id>/passkeys")]
+pub async fn get_passkeys(id: i32, user: User, passkey_dao: &State<Box<dyn LoginPasskeyDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>) -> Result<Json<Vec<LoginPasskey>>, APIError> {
+    let permission = require_permission(user.id, ItemType::Login, id, Permission::HidePasswords, share_dao).await?;
+
+    let mut passkeys = passkey_dao.get_passkeys(id).await?;
+
+    for passkey in passkeys.iter_mut() {
+        if permission == Permission::HidePasswords {
+            passkey.hide_secrets();
+        }
+        passkey.permission = permission;
+    }
+
+    Ok(Json(passkeys))
+}
+
+#[delete("/logins/<id>/passkeys/<passkey_id>")]
+pub async fn delete_passkey(id: i32, passkey_id: i32, user: User, passkey_dao: &State<Box<dyn LoginPasskeyDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<(), APIError> {
+    require_permission(user.id, ItemType::Login, id, Permission::Write, share_dao).await?;
+
+    let passkey = get_passkey_of(id, passkey_id, passkey_dao).await?;
+
+    passkey_dao.delete_passkey(passkey.id).await?;
+
+    events.log(NewEvent::new(EventType::ItemUpdated, Some(user.id), &client)
+        .item(ItemType::Login, id)
+        .details(format!("passkey removed for {}", passkey.rp_id)));
+
+    Ok(())
+}
+
+/// For browser extensions: every passkey the user can sign with on the relying party `rp_id`.
+#[get("/passkeys?<rp_id>")]
+pub async fn find_passkeys(rp_id: &str, user: User, passkey_dao: &State<Box<dyn LoginPasskeyDao + Sync + Send>>) -> Result<Json<Vec<LoginPasskey>>, APIError> {
+    passkey_dao.find_passkeys(user.id, rp_id.trim()).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Records the counter of an assertion the client just signed. Counters only move forward;
+/// anything else is refused, as it suggests a copy of the key was used elsewhere.
+#[put("/passkeys/<id>/sign-count", data = "<count>")]
+pub async fn update_sign_count(id: i32, count: Json<SignCountDto>, user: User, passkey_dao: &State<Box<dyn LoginPasskeyDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>) -> Result<Json<LoginPasskey>, APIError> {
+    let passkey = passkey_dao.get_passkey(id).await
+        .map_err(|_| APIError::NotFound(format!("Passkey {} not found.", id)))?;
+
+    let permission = require_permission(user.id, ItemType::Login, passkey.login_id, Permission::Read, share_dao).await?;
+
+    if count.sign_count < 0 || count.sign_count > u32::MAX as i64 {
+        return Err(APIError::BadRequest(String::from("Signature counters are 32 bit unsigned integers.")));
+    }
+
+    let mut passkey = passkey_dao.update_sign_count(id, count.sign_count).await?
+        .ok_or_else(|| APIError::BadRequest(format!("Signature counter must be greater than {}.", passkey.sign_count)))?;
+    passkey.permission = permission;
+
+    Ok(Json(passkey))
+}
+
+async fn get_passkey_of(login_id: i32, passkey_id: i32, passkey_dao: &State<Box<dyn LoginPasskeyDao + Sync + Send>>) -> Result<LoginPasskey, APIError> {
+    let passkey = passkey_dao.get_passkey(passkey_id).await
+        .map_err(|_| APIError::NotFound(format!("Passkey {} not found.", passkey_id)))?;
+
+    if passkey.login_id != login_id {
+        return Err(APIError::NotFound(format!("Passkey {} not found.", passkey_id)));
+    }
+
+    Ok(passkey)
+}
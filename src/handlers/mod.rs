@@ -1,31 +1,185 @@
-use rocket::{options, Responder, routes};
+use log::error;
+use rocket::{catchers, options, Request, response, routes};
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::response::status::Custom;
+use rocket::serde::json::Json;
 
 use handlers_inner::*;
 
+use crate::models::{DBError, postgres_error_codes};
+use crate::models::error_model::{ErrorCode, ErrorResponse, FieldError};
+use crate::models::password_policy_model::PolicyViolation;
+use crate::models::revision_model::EditConflict;
+
 mod handlers_inner;
 mod user_handler;
+pub mod account_handler;
 pub mod auth_handler;
 mod login_handler;
+pub mod login_passkey_handler;
 mod payment_handler;
 mod secured_note_handler;
+mod identity_handler;
+mod ssh_key_handler;
+mod api_credential_handler;
+mod wifi_network_handler;
+mod software_license_handler;
+pub mod storage_handler;
+pub mod admin_handler;
+pub mod attachment_handler;
+pub mod send_handler;
+pub mod share_handler;
+pub mod organization_handler;
+pub mod emergency_access_handler;
+pub mod event_handler;
+pub mod webauthn_handler;
+pub mod notification_handler;
+pub mod push_handler;
+pub mod sync_handler;
+pub mod autofill_handler;
+pub mod report_handler;
+pub mod custom_field_handler;
+mod item_access;
+mod item_handler;
+pub mod catchers;
 
 
-#[derive(Responder)]
 pub enum APIError {
-    #[response(status = 400)]
     BadRequest(String),
-    #[response(status = 401)]
+    /// A password that breaks the password policy, with every rule it broke.
+    PasswordPolicy(Vec<PolicyViolation>),
     Unauthorized(String),
-    #[response(status = 500)]
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    /// An `If-Match` that names an older revision than the stored one.
+    PreconditionFailed(String),
+    /// Like `PreconditionFailed`, with both versions of the item.
+    EditConflict(Box<EditConflict>),
+    UnprocessableEntity(String),
+    /// A request body that failed validation, with every failing field.
+    Validation(Vec<FieldError>),
     InternalError(String),
-    #[response(status = 401)]
     InvalidCredentials(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    TooManyRequests(String),
+}
+
+impl APIError {
+    pub fn status(&self) -> Status {
+        match self {
+            APIError::BadRequest(_) | APIError::PasswordPolicy(_) => Status::BadRequest,
+            APIError::Unauthorized(_) | APIError::InvalidCredentials(_) => Status::Unauthorized,
+            APIError::Forbidden(_) => Status::Forbidden,
+            APIError::NotFound(_) => Status::NotFound,
+            APIError::Conflict(_) => Status::Conflict,
+            APIError::PreconditionFailed(_) | APIError::EditConflict(_) => Status::PreconditionFailed,
+            APIError::UnprocessableEntity(_) | APIError::Validation(_) => Status::UnprocessableEntity,
+            APIError::PayloadTooLarge(_) => Status::PayloadTooLarge,
+            APIError::UnsupportedMediaType(_) => Status::UnsupportedMediaType,
+            APIError::TooManyRequests(_) => Status::TooManyRequests,
+            APIError::InternalError(_) => Status::InternalServerError,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            APIError::BadRequest(_) => ErrorCode::BadRequest,
+            APIError::PasswordPolicy(_) => ErrorCode::PasswordPolicy,
+            APIError::Unauthorized(_) => ErrorCode::Unauthorized,
+            APIError::InvalidCredentials(_) => ErrorCode::InvalidCredentials,
+            APIError::Forbidden(_) => ErrorCode::Forbidden,
+            APIError::NotFound(_) => ErrorCode::NotFound,
+            APIError::Conflict(_) => ErrorCode::Conflict,
+            APIError::PreconditionFailed(_) | APIError::EditConflict(_) => ErrorCode::PreconditionFailed,
+            APIError::UnprocessableEntity(_) | APIError::Validation(_) => ErrorCode::ValidationFailed,
+            APIError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            APIError::UnsupportedMediaType(_) => ErrorCode::UnsupportedMediaType,
+            APIError::TooManyRequests(_) => ErrorCode::TooManyRequests,
+            APIError::InternalError(_) => ErrorCode::InternalError,
+        }
+    }
+
+    fn into_response(self) -> ErrorResponse {
+        let code = self.code();
+        match self {
+            APIError::PasswordPolicy(violations) => {
+                let fields = violations.into_iter()
+                    .map(|violation| FieldError {
+                        field: String::from("password"),
+                        code: violation.rule.as_str().to_string(),
+                        message: violation.message,
+                    })
+                    .collect();
+                ErrorResponse::new(code, "Password does not meet the password policy.").fields(fields)
+            }
+            APIError::Validation(fields) => ErrorResponse::new(code, "Validation failed.").fields(fields),
+            APIError::EditConflict(conflict) => ErrorResponse::new(code, STALE_REVISION_MESSAGE).conflict(*conflict),
+            APIError::BadRequest(message)
+            | APIError::Unauthorized(message)
+            | APIError::Forbidden(message)
+            | APIError::NotFound(message)
+            | APIError::Conflict(message)
+            | APIError::PreconditionFailed(message)
+            | APIError::UnprocessableEntity(message)
+            | APIError::InternalError(message)
+            | APIError::InvalidCredentials(message)
+            | APIError::PayloadTooLarge(message)
+            | APIError::UnsupportedMediaType(message)
+            | APIError::TooManyRequests(message) => ErrorResponse::new(code, message),
+        }
+    }
+}
+
+const STALE_REVISION_MESSAGE: &str = "The item was changed in the meantime. Merge the changes and retry with its current ETag.";
+
+impl<'r> Responder<'r, 'static> for APIError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let status = self.status();
+        Custom(status, Json(self.into_response())).respond_to(request)
+    }
+}
+
+/// Turns database failures into the status a client can act on. Anything unexpected is logged
+/// and reported without the underlying error, which can leak queries and schema details.
+impl From<DBError> for APIError {
+    fn from(err: DBError) -> Self {
+        let source = match err {
+            DBError::Other(source) => source,
+            DBError::StaleRevision => return APIError::PreconditionFailed(String::from(STALE_REVISION_MESSAGE)),
+            DBError::LimitExceeded(message) => return APIError::PayloadTooLarge(message),
+            invalid => return APIError::UnprocessableEntity(invalid.to_string()),
+        };
+
+        match source.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => APIError::NotFound(String::from("Not found.")),
+            Some(sqlx::Error::Database(db_err)) => match db_err.code().as_deref() {
+                Some(postgres_error_codes::UNIQUE_VIOLATION) => APIError::Conflict(String::from("Already exists.")),
+                Some(postgres_error_codes::FOREIGN_KEY_VIOLATION) => APIError::Conflict(String::from("Refers to a record that doesn't exist or is still in use.")),
+                Some(postgres_error_codes::NOT_NULL_VIOLATION)
+                | Some(postgres_error_codes::CHECK_VIOLATION)
+                | Some(postgres_error_codes::INVALID_TEXT_REPRESENTATION) => APIError::UnprocessableEntity(db_err.message().to_string()),
+                _ => internal_error(source.as_ref()),
+            },
+            _ => internal_error(source.as_ref()),
+        }
+    }
+}
+
+/// Logs the error and answers with a generic message, so internals don't leak to clients.
+pub(crate) fn internal_error(err: &(dyn std::error::Error + Send + Sync)) -> APIError {
+    error!("{}", err);
+    APIError::InternalError(String::from("Something went wrong! Please try again."))
 }
 
 impl From<HandlerError> for APIError {
     fn from(value: HandlerError) -> Self {
         match value {
             HandlerError::BadRequest(s) => Self::BadRequest(s),
+            HandlerError::NotFound(s) => Self::NotFound(s),
+            HandlerError::Conflict(s) => Self::Conflict(s),
             HandlerError::InternalError(s) => Self::InternalError(s),
         }
     }
@@ -44,21 +198,54 @@ pub fn app_routes() -> Vec<rocket::Route> {
         auth_handler::status,
         auth_handler::login,
         auth_handler::logout,
+        // WEBAUTHN
+        webauthn_handler::start_registration,
+        webauthn_handler::finish_registration,
+        webauthn_handler::get_credentials,
+        webauthn_handler::rename_credential,
+        webauthn_handler::delete_credential,
+        webauthn_handler::start_passkey_login,
+        webauthn_handler::finish_passkey_login,
+        webauthn_handler::start_second_factor,
+        webauthn_handler::finish_second_factor,
         // USER
         user_handler::get_user,
         user_handler::create_user,
         user_handler::update_user,
         user_handler::delete_user,
+        // ACCOUNT
+        account_handler::verify_email,
+        account_handler::resend_email_verification,
+        account_handler::confirm_email,
+        account_handler::forgot_password,
+        account_handler::reset_password,
+        account_handler::change_password,
+        account_handler::get_password_policy,
          // LOGIN
         login_handler::create_login,
         login_handler::get_logins,
         login_handler::get_login,
         login_handler::delete_login,
         login_handler::update_login,
+        login_handler::get_recent_logins,
+        login_handler::get_most_used_logins,
+        login_handler::record_login_use,
+        // AUTOFILL
+        autofill_handler::match_logins,
+        autofill_handler::get_equivalent_domains,
+        autofill_handler::create_equivalent_domains,
+        autofill_handler::delete_equivalent_domains,
+        // LOGIN PASSKEY
+        login_passkey_handler::create_passkey,
+        login_passkey_handler::get_passkeys,
+        login_passkey_handler::delete_passkey,
+        login_passkey_handler::find_passkeys,
+        login_passkey_handler::update_sign_count,
         // PAYMENT
         payment_handler::create_payment,
         payment_handler::get_payment,
         payment_handler::get_payments,
+        payment_handler::reveal_payment,
         payment_handler::delete_payment,
         payment_handler::update_payment,
         // SECURED NOTE
@@ -67,9 +254,115 @@ pub fn app_routes() -> Vec<rocket::Route> {
         secured_note_handler::get_secured_notes,
         secured_note_handler::update_secured_note,
         secured_note_handler::delete_secured_note,
-        secured_note_handler::upload,
-        secured_note_handler::secured_note_attachments,
-        secured_note_handler::download_attachment,
-        secured_note_handler::delete_attachment,
+        // IDENTITY
+        identity_handler::create_identity,
+        identity_handler::get_identity,
+        identity_handler::get_identities,
+        identity_handler::update_identity,
+        identity_handler::delete_identity,
+        // SSH KEY
+        ssh_key_handler::create_ssh_key,
+        ssh_key_handler::get_ssh_key,
+        ssh_key_handler::get_ssh_keys,
+        ssh_key_handler::update_ssh_key,
+        ssh_key_handler::delete_ssh_key,
+        // API CREDENTIAL
+        api_credential_handler::create_api_credential,
+        api_credential_handler::get_api_credential,
+        api_credential_handler::get_api_credentials,
+        api_credential_handler::update_api_credential,
+        api_credential_handler::delete_api_credential,
+        // WIFI NETWORK
+        wifi_network_handler::create_wifi_network,
+        wifi_network_handler::get_wifi_network,
+        wifi_network_handler::get_wifi_networks,
+        wifi_network_handler::update_wifi_network,
+        wifi_network_handler::delete_wifi_network,
+        // SOFTWARE LICENSE
+        software_license_handler::create_software_license,
+        software_license_handler::get_software_license,
+        software_license_handler::get_software_licenses,
+        software_license_handler::update_software_license,
+        software_license_handler::delete_software_license,
+        // ATTACHMENT
+        attachment_handler::upload_attachment,
+        attachment_handler::item_attachments,
+        attachment_handler::download_attachment,
+        attachment_handler::delete_attachment,
+        // STORAGE
+        storage_handler::get_storage_usage,
+        // SHARE
+        share_handler::create_share,
+        share_handler::get_shares_with_me,
+        share_handler::get_shares_by_me,
+        share_handler::delete_share,
+        // ORGANIZATION
+        organization_handler::create_organization,
+        organization_handler::get_organizations,
+        organization_handler::delete_organization,
+        organization_handler::accept_invitation,
+        organization_handler::get_members,
+        organization_handler::invite_member,
+        organization_handler::update_member,
+        organization_handler::delete_member,
+        organization_handler::create_collection,
+        organization_handler::get_collections,
+        organization_handler::delete_collection,
+        organization_handler::set_collection_rotation,
+        organization_handler::set_collection_access,
+        organization_handler::delete_collection_access,
+        organization_handler::get_collection_items,
+        organization_handler::add_collection_item,
+        organization_handler::remove_collection_item,
+        organization_handler::get_password_policy,
+        organization_handler::set_password_policy,
+        // EMERGENCY ACCESS
+        emergency_access_handler::create_emergency_access,
+        emergency_access_handler::get_granted,
+        emergency_access_handler::get_trusted,
+        emergency_access_handler::accept_emergency_access,
+        emergency_access_handler::initiate_recovery,
+        emergency_access_handler::approve_recovery,
+        emergency_access_handler::reject_recovery,
+        emergency_access_handler::delete_emergency_access,
+        emergency_access_handler::get_vault,
+        emergency_access_handler::takeover,
+        // EVENT
+        event_handler::get_events,
+        // NOTIFICATION
+        notification_handler::get_notifications,
+        notification_handler::dismiss_notification,
+        notification_handler::get_preferences,
+        notification_handler::set_preferences,
+        // SYNC
+        sync_handler::sync,
+        // CUSTOM FIELD
+        custom_field_handler::search_custom_fields,
+        // REPORT
+        report_handler::get_security_report,
+        // PUSH
+        push_handler::push_events,
+        // SEND
+        send_handler::create_send,
+        send_handler::create_file_send,
+        send_handler::get_sends,
+        send_handler::delete_send,
+        send_handler::access_send,
+        send_handler::download_send,
+        // ADMIN
+        admin_handler::fsck_attachments,
+        admin_handler::get_all_events,
+        admin_handler::verify_events,
+    ]
+}
+
+pub fn app_catchers() -> Vec<rocket::Catcher> {
+    catchers![
+        catchers::bad_request,
+        catchers::unauthorized,
+        catchers::not_found,
+        catchers::unprocessable_entity,
+        catchers::internal_error,
+        catchers::default,
     ]
 }
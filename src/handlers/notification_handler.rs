@@ -0,0 +1,40 @@
+use rocket::{get, post, put, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::models::notification_model::{Notification, NotificationPreferences};
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::notification_dao::NotificationDao;
+
+/// The user's reminders, newest first. Dismissed ones only with `include_dismissed=true`.
+#[get("/notifications?<include_dismissed>")]
+pub async fn get_notifications(user: User, include_dismissed: Option<bool>, notification_dao: &State<Box<dyn NotificationDao + Sync + Send>>) -> Result<Json<Vec<Notification>>, APIError> {
+    notification_dao.get_notifications(user.id, include_dismissed.unwrap_or(false)).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+#[post("/notifications/<id>/dismiss")]
+pub async fn dismiss_notification(user: User, id: i32, notification_dao: &State<Box<dyn NotificationDao + Sync + Send>>) -> Result<(), APIError> {
+    if !notification_dao.dismiss_notification(id, user.id).await? {
+        return Err(APIError::NotFound(String::from("Notification not found.")));
+    }
+
+    Ok(())
+}
+
+#[get("/notifications/preferences")]
+pub async fn get_preferences(user: User, notification_dao: &State<Box<dyn NotificationDao + Sync + Send>>) -> Result<Json<NotificationPreferences>, APIError> {
+    notification_dao.get_preferences(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Changes which reminders the user gets. New settings apply from the next daily run.
+#[put("/notifications/preferences", data = "<preferences>")]
+pub async fn set_preferences(user: User, preferences: Validated<NotificationPreferences>, notification_dao: &State<Box<dyn NotificationDao + Sync + Send>>) -> Result<Json<NotificationPreferences>, APIError> {
+    notification_dao.set_preferences(user.id, &preferences).await?;
+
+    Ok(Json(preferences.0))
+}
@@ -0,0 +1,443 @@
+use rocket::{delete, get, post, put, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::item_access::require_permission;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::item_model::ItemType;
+use crate::models::organization_model::{CollectionAccess, CollectionAccessDto, CollectionItem, CollectionRotationDto, Member, MemberDto, MemberRoleDto, OrgCollection, OrgCollectionDto, Organization, OrganizationDto, OrgRole};
+use crate::models::password_policy_model::OrgPasswordPolicy;
+use crate::models::push_model::PushEvent;
+use crate::models::share_model::Permission;
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::organization_dao::OrganizationDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
+use crate::persistence::users_dao::UsersDao;
+
+#[post("/organizations", data = "<organization>")]
+pub async fn create_organization(user: User, organization: Validated<OrganizationDto>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<Organization>, APIError> {
+    let organization = organization_dao.create_organization(&organization.name, user.id).await?;
+
+    events.log(NewEvent::new(EventType::OrganizationCreated, Some(user.id), &client)
+        .details(format!("organization {}", organization.id)));
+
+    Ok(Json(organization))
+}
+
+/// Organizations of the user, including pending invitations.
+#[get("/organizations")]
+pub async fn get_organizations(user: User, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Json<Vec<Organization>>, APIError> {
+    organization_dao.get_organizations(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+#[delete("/organizations/<id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_organization(user: User, id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    let owner = require_role(id, user.id, OrgRole::Owner, organization_dao).await?;
+
+    let items = member_items(id, &owner, organization_dao).await?;
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.delete_organization(id).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, None, push);
+    events.log(NewEvent::new(EventType::OrganizationDeleted, Some(user.id), &client)
+        .details(format!("organization {}", id)));
+
+    Ok(())
+}
+
+#[post("/organizations/<id>/accept")]
+pub async fn accept_invitation(user: User, id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Json<Member>, APIError> {
+    let member = organization_dao.get_membership(id, user.id).await?
+        .ok_or(APIError::NotFound(String::from("Invitation not found.")))?;
+
+    let items = member_items(id, &member, organization_dao).await?;
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.accept_invitation(member.id).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, None, push);
+
+    events.log(NewEvent::new(EventType::MemberJoined, Some(user.id), &client)
+        .details(format!("organization {}, member {}", id, member.id)));
+
+    Ok(Json(Member { accepted: true, ..member }))
+}
+
+#[get("/organizations/<id>/members")]
+pub async fn get_members(user: User, id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Json<Vec<Member>>, APIError> {
+    require_role(id, user.id, OrgRole::User, organization_dao).await?;
+
+    organization_dao.get_members(id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Invites a user. The invitation shows up in their organizations until they accept it.
+#[post("/organizations/<id>/members", data = "<member>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn invite_member(user: User, id: i32, member: Validated<MemberDto>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<Member>, APIError> {
+    let inviter = require_role(id, user.id, OrgRole::Admin, organization_dao).await?;
+
+    if member.role > inviter.role {
+        return Err(APIError::Unauthorized(String::from("Can't grant a role above your own.")));
+    }
+
+    let invitee = users_dao.get_user_by_username(&member.username).await
+        .map_err(|_| APIError::NotFound(format!("User {} not found.", member.username)))?;
+
+    let existing = organization_dao.get_membership(id, invitee.id).await?;
+    if existing.is_some() {
+        return Err(APIError::BadRequest(format!("User {} is already a member.", member.username)));
+    }
+
+    let invited = organization_dao.invite_member(id, invitee.id, member.role, user.id).await?;
+
+    events.log(NewEvent::new(EventType::MemberInvited, Some(user.id), &client)
+        .details(format!("organization {}, member {}, role {}", id, invited.id, invited.role.as_str())));
+
+    Ok(Json(invited))
+}
+
+#[put("/organizations/<id>/members/<member_id>", data = "<member>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_member(user: User, id: i32, member_id: i32, member: Json<MemberRoleDto>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Json<Member>, APIError> {
+    let actor = require_role(id, user.id, OrgRole::Admin, organization_dao).await?;
+    let target = get_member_of(id, member_id, organization_dao).await?;
+
+    if member.role > actor.role || target.role > actor.role {
+        return Err(APIError::Unauthorized(String::from("Can't change a role above your own.")));
+    }
+    if target.role == OrgRole::Owner && member.role != OrgRole::Owner {
+        require_other_owner(id, &target, organization_dao).await?;
+    }
+
+    // Collections only open up with higher roles, so the higher of the two covers both.
+    let widest = Member { role: target.role.max(member.role), ..target.clone() };
+    let items = member_items(id, &widest, organization_dao).await?;
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.update_member_role(member_id, member.role).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, Some(target.user_id), push);
+
+    events.log(NewEvent::new(EventType::MemberRoleChanged, Some(user.id), &client)
+        .details(format!("organization {}, member {}, role {} -> {}", id, member_id, target.role.as_str(), member.role.as_str())));
+
+    Ok(Json(Member { role: member.role, ..target }))
+}
+
+/// Removes a member. Members may also remove themselves to leave or to decline an invitation.
+#[delete("/organizations/<id>/members/<member_id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_member(user: User, id: i32, member_id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    let target = get_member_of(id, member_id, organization_dao).await?;
+
+    if target.user_id != user.id {
+        let actor = require_role(id, user.id, OrgRole::Admin, organization_dao).await?;
+        if target.role > actor.role {
+            return Err(APIError::Unauthorized(String::from("Can't remove a member above your own role.")));
+        }
+    }
+    if target.role == OrgRole::Owner {
+        require_other_owner(id, &target, organization_dao).await?;
+    }
+
+    let items = member_items(id, &target, organization_dao).await?;
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.delete_member(member_id).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, None, push);
+    events.log(NewEvent::new(EventType::MemberRemoved, Some(user.id), &client)
+        .details(format!("organization {}, member {}", id, member_id)));
+
+    Ok(())
+}
+
+/// Managers get manage access to the collections they create.
+#[post("/organizations/<id>/collections", data = "<collection>")]
+pub async fn create_collection(user: User, id: i32, collection: Validated<OrgCollectionDto>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<OrgCollection>, APIError> {
+    let member = require_role(id, user.id, OrgRole::Manager, organization_dao).await?;
+
+    let collection = organization_dao.create_collection(id, &collection.name).await?;
+
+    if member.role == OrgRole::Manager {
+        organization_dao.set_collection_access(collection.id, member.id, CollectionAccess::Manage).await?;
+    }
+
+    events.log(NewEvent::new(EventType::CollectionCreated, Some(user.id), &client)
+        .details(format!("organization {}, collection {}", id, collection.id)));
+
+    Ok(Json(collection))
+}
+
+#[get("/organizations/<id>/collections")]
+pub async fn get_collections(user: User, id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Json<Vec<OrgCollection>>, APIError> {
+    let member = require_role(id, user.id, OrgRole::User, organization_dao).await?;
+
+    organization_dao.get_collections(id, &member).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+#[delete("/organizations/<id>/collections/<collection_id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_collection(user: User, id: i32, collection_id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    require_role(id, user.id, OrgRole::Admin, organization_dao).await?;
+    get_collection_of(id, collection_id, organization_dao).await?;
+
+    let items = organization_dao.get_collection_items(collection_id).await?;
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.delete_collection(collection_id).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, None, push);
+
+    events.log(NewEvent::new(EventType::CollectionDeleted, Some(user.id), &client)
+        .details(format!("organization {}, collection {}", id, collection_id)));
+
+    Ok(())
+}
+
+/// Sets how often passwords of logins in the collection should change; `null` turns rotation
+/// reminders off.
+#[put("/organizations/<id>/collections/<collection_id>/rotation", data = "<rotation>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn set_collection_rotation(user: User, id: i32, collection_id: i32, rotation: Validated<CollectionRotationDto>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<(), APIError> {
+    require_collection_manager(id, collection_id, user.id, organization_dao).await?;
+
+    organization_dao.set_collection_rotation(collection_id, rotation.rotation_days).await?;
+
+    let rotation_days = rotation.rotation_days.map_or(String::from("off"), |days| format!("{} days", days));
+    events.log(NewEvent::new(EventType::CollectionRotationChanged, Some(user.id), &client)
+        .details(format!("collection {}, rotation {}", collection_id, rotation_days)));
+
+    Ok(())
+}
+
+#[put("/organizations/<id>/collections/<collection_id>/access", data = "<access>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn set_collection_access(user: User, id: i32, collection_id: i32, access: Json<CollectionAccessDto>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    require_collection_manager(id, collection_id, user.id, organization_dao).await?;
+    let member = get_member_of(id, access.member_id, organization_dao).await?;
+
+    let items = organization_dao.get_collection_items(collection_id).await?;
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.set_collection_access(collection_id, access.member_id, access.access).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, Some(member.user_id), push);
+
+    events.log(NewEvent::new(EventType::CollectionAccessChanged, Some(user.id), &client)
+        .details(format!("collection {}, member {}, access {}", collection_id, access.member_id, access.access.as_str())));
+
+    Ok(())
+}
+
+#[delete("/organizations/<id>/collections/<collection_id>/access/<member_id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_collection_access(user: User, id: i32, collection_id: i32, member_id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    require_collection_manager(id, collection_id, user.id, organization_dao).await?;
+
+    let items = organization_dao.get_collection_items(collection_id).await?;
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.delete_collection_access(collection_id, member_id).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, None, push);
+
+    events.log(NewEvent::new(EventType::CollectionAccessChanged, Some(user.id), &client)
+        .details(format!("collection {}, member {}, access none", collection_id, member_id)));
+
+    Ok(())
+}
+
+#[get("/organizations/<id>/collections/<collection_id>/items")]
+pub async fn get_collection_items(user: User, id: i32, collection_id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Json<Vec<CollectionItem>>, APIError> {
+    let member = require_role(id, user.id, OrgRole::User, organization_dao).await?;
+    get_collection_of(id, collection_id, organization_dao).await?;
+
+    if member.role < OrgRole::Admin {
+        organization_dao.get_collection_access(collection_id, member.id).await?
+            .ok_or(APIError::Unauthorized(String::from("No access to collection.")))?;
+    }
+
+    organization_dao.get_collection_items(collection_id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Adds one of the user's own items to a collection they manage.
+#[post("/organizations/<id>/collections/<collection_id>/items", data = "<item>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn add_collection_item(user: User, id: i32, collection_id: i32, item: Json<CollectionItem>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    require_collection_manager(id, collection_id, user.id, organization_dao).await?;
+    require_permission(user.id, item.item_type, item.item_id, Permission::Owner, share_dao).await?;
+
+    let items = [CollectionItem { item_type: item.item_type, item_id: item.item_id }];
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.add_collection_item(collection_id, item.item_type, item.item_id).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, None, push);
+
+    events.log(NewEvent::new(EventType::CollectionItemAdded, Some(user.id), &client)
+        .item(item.item_type, item.item_id)
+        .details(format!("collection {}", collection_id)));
+
+    Ok(())
+}
+
+/// Removes an item from a collection. Collection managers and the item owner may do so.
+/// `item_type` is named as in the item routes, e.g. `logins`.
+#[delete("/organizations/<id>/collections/<collection_id>/items/<item_type>/<item_id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn remove_collection_item(user: User, id: i32, collection_id: i32, item_type: ItemType, item_id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    if require_collection_manager(id, collection_id, user.id, organization_dao).await.is_err() {
+        require_permission(user.id, item_type, item_id, Permission::Owner, share_dao).await?;
+    }
+
+    let items = [CollectionItem { item_type, item_id }];
+    let before = item_audiences(&items, share_dao).await?;
+
+    organization_dao.remove_collection_item(collection_id, item_type, item_id).await?;
+
+    publish_audience_changes(&items, before, item_audiences(&items, share_dao).await?, None, push);
+
+    events.log(NewEvent::new(EventType::CollectionItemRemoved, Some(user.id), &client)
+        .item(item_type, item_id)
+        .details(format!("collection {}", collection_id)));
+
+    Ok(())
+}
+
+#[get("/organizations/<id>/password-policy")]
+pub async fn get_password_policy(user: User, id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Json<OrgPasswordPolicy>, APIError> {
+    require_role(id, user.id, OrgRole::User, organization_dao).await?;
+
+    organization_dao.get_password_policy(id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Sets stricter password requirements for members. They apply the next time a member sets a
+/// password; the server policy stays the floor.
+#[put("/organizations/<id>/password-policy", data = "<policy>")]
+pub async fn set_password_policy(user: User, id: i32, policy: Validated<OrgPasswordPolicy>, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<OrgPasswordPolicy>, APIError> {
+    require_role(id, user.id, OrgRole::Admin, organization_dao).await?;
+
+    organization_dao.set_password_policy(id, &policy).await?;
+
+    events.log(NewEvent::new(EventType::PasswordPolicyChanged, Some(user.id), &client)
+        .details(format!("organization {}", id)));
+
+    Ok(Json(policy.0))
+}
+
+/// Accepted membership with at least the given role.
+async fn require_role(organization_id: i32, user_id: i32, role: OrgRole, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Member, APIError> {
+    let member = organization_dao.get_membership(organization_id, user_id).await?;
+
+    match member {
+        Some(member) if member.accepted && member.role >= role => Ok(member),
+        Some(member) if member.accepted => Err(APIError::Unauthorized(format!("Requires the {} role.", role.as_str()))),
+        _ => Err(APIError::Unauthorized(String::from("Not a member of the organization."))),
+    }
+}
+
+/// Owners and admins manage every collection, managers the ones they have access to,
+/// and users the ones they were given manage access to.
+async fn require_collection_manager(organization_id: i32, collection_id: i32, user_id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Member, APIError> {
+    let member = require_role(organization_id, user_id, OrgRole::User, organization_dao).await?;
+    get_collection_of(organization_id, collection_id, organization_dao).await?;
+
+    if member.role >= OrgRole::Admin {
+        return Ok(member);
+    }
+
+    let access = organization_dao.get_collection_access(collection_id, member.id).await?;
+
+    match access {
+        Some(CollectionAccess::Manage) => Ok(member),
+        Some(_) if member.role == OrgRole::Manager => Ok(member),
+        _ => Err(APIError::Unauthorized(String::from("Can't manage collection."))),
+    }
+}
+
+async fn get_member_of(organization_id: i32, member_id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Member, APIError> {
+    match organization_dao.get_member(member_id).await {
+        Ok(member) if member.organization_id == organization_id => Ok(member),
+        _ => Err(APIError::NotFound(String::from("Member not found."))),
+    }
+}
+
+async fn get_collection_of(organization_id: i32, collection_id: i32, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<OrgCollection, APIError> {
+    match organization_dao.get_collection(collection_id).await {
+        Ok(collection) if collection.organization_id == organization_id => Ok(collection),
+        _ => Err(APIError::NotFound(String::from("Collection not found."))),
+    }
+}
+
+/// Items of the collections `member` can see.
+async fn member_items(organization_id: i32, member: &Member, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<Vec<CollectionItem>, APIError> {
+    let mut items = Vec::new();
+    for collection in organization_dao.get_collections(organization_id, member).await? {
+        items.extend(organization_dao.get_collection_items(collection.id).await?);
+    }
+
+    Ok(items)
+}
+
+/// Who can see each of the items, owner first.
+async fn item_audiences(items: &[CollectionItem], share_dao: &State<Box<dyn ShareDao + Sync + Send>>) -> Result<Vec<Vec<i32>>, APIError> {
+    let mut audiences = Vec::with_capacity(items.len());
+    for item in items {
+        audiences.push(share_dao.get_item_users(item.item_type, item.item_id).await?);
+    }
+
+    Ok(audiences)
+}
+
+/// Tells devices about items that appeared or disappeared for their user after a change to
+/// collections or members. `changed_user_id`, whose access level changed, also hears about
+/// the items they keep, since what they may see of them changed.
+fn publish_audience_changes(items: &[CollectionItem], before: Vec<Vec<i32>>, after: Vec<Vec<i32>>, changed_user_id: Option<i32>, push: &State<PushHub>) {
+    for ((item, before), after) in items.iter().zip(before).zip(after) {
+        let (item_type, item_id) = (item.item_type, item.item_id);
+
+        let gained: Vec<i32> = after.iter().filter(|id| !before.contains(id)).copied().collect();
+        if !gained.is_empty() {
+            push.publish(gained, PushEvent::ItemCreated { item_type, item_id });
+        }
+
+        let lost: Vec<i32> = before.iter().filter(|id| !after.contains(id)).copied().collect();
+        if !lost.is_empty() {
+            push.publish(lost, PushEvent::ItemDeleted { item_type, item_id });
+        }
+
+        if let Some(user_id) = changed_user_id.filter(|id| before.contains(id) && after.contains(id)) {
+            push.publish(vec![user_id], PushEvent::ItemUpdated { item_type, item_id });
+        }
+    }
+}
+
+/// An organization must keep at least one accepted owner.
+async fn require_other_owner(organization_id: i32, owner: &Member, organization_dao: &State<Box<dyn OrganizationDao + Sync + Send>>) -> Result<(), APIError> {
+    let members = organization_dao.get_members(organization_id).await?;
+
+    let has_other_owner = members.iter()
+        .any(|m| m.id != owner.id && m.role == OrgRole::Owner && m.accepted);
+
+    if has_other_owner {
+        Ok(())
+    } else {
+        Err(APIError::BadRequest(String::from("Organization must keep an owner.")))
+    }
+}
@@ -2,43 +2,141 @@ use rocket::{delete, get, post, put, State};
 use rocket::serde::json::Json;
 
 use crate::APIError;
-use crate::models::payment_model::{Payment, PaymentDto};
+use crate::handlers::attachment_handler::remove_attachment_files;
+use crate::handlers::item_access::require_permission;
+use crate::models::DBError;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::item_model::ItemType;
+use crate::models::payment_model::{CardBrand, normalize_card_number, Payment, PaymentDto, validate_security_code};
+use crate::models::push_model::PushEvent;
+use crate::models::revision_model::{EditConflict, IfMatch, Tagged};
+use crate::models::share_model::Permission;
 use crate::models::user_model::User;
+use crate::models::validation_model::{field_error, Validated};
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
 use crate::persistence::payment_dao::PaymentDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
 
 #[post("/payments", data = "<payment>")]
-pub async fn create_payment(user: User, payment: Json<PaymentDto>, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>) -> Result<Json<Payment>, APIError> {
-    return payment_dao.create_payment(payment.0, user.id).await
-        .map(|payment| Json(payment))
-        .map_err(|err| APIError::InternalError(err.to_string()));
+pub async fn create_payment(user: User, payment: Validated<PaymentDto>, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<Payment>>, APIError> {
+    if let Some(number) = &payment.card_number {
+        require_security_code(CardBrand::detect(&normalize_card_number(number)), payment.security_code)?;
+    }
+
+    let mut payment = payment_dao.create_payment(payment.0, user.id).await?;
+
+    events.log(NewEvent::new(EventType::ItemCreated, Some(user.id), &client).item(ItemType::Payment, payment.id));
+    push.publish(vec![user.id], PushEvent::ItemCreated { item_type: ItemType::Payment, item_id: payment.id });
+
+    payment.mask();
+    Ok(Tagged::new(payment.revision, Json(payment)))
 }
 
 
 #[get("/payments/<id>")]
-pub async fn get_payment(user: User, id: i32, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>) -> Result<Json<Payment>, APIError> {
-    return payment_dao.get_payment(id).await
-        .map(|payment| Json(payment))
-        .map_err(|err| APIError::InternalError(err.to_string()));
+pub async fn get_payment(user: User, id: i32, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Tagged<Json<Payment>>, APIError> {
+    let permission = require_permission(user.id, ItemType::Payment, id, Permission::HidePasswords, share_dao).await?;
+
+    let mut payment = payment_dao.get_payment(id).await?;
+    if permission == Permission::HidePasswords {
+        payment.hide_secrets();
+    }
+    payment.mask();
+    payment.shared = permission != Permission::Owner;
+    payment.permission = permission;
+
+    events.log(NewEvent::new(EventType::ItemViewed, Some(user.id), &client).item(ItemType::Payment, id));
+
+    Ok(Tagged::new(payment.revision, Json(payment)))
+}
+
+/// The full card number and security code. Every reveal is logged, and members who may only
+/// see the card with hidden passwords can't reveal it.
+#[get("/payments/<id>/reveal")]
+pub async fn reveal_payment(user: User, id: i32, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<Payment>, APIError> {
+    let permission = require_permission(user.id, ItemType::Payment, id, Permission::Read, share_dao).await?;
+
+    let mut payment = payment_dao.get_payment(id).await?;
+    payment.shared = permission != Permission::Owner;
+    payment.permission = permission;
+
+    events.log(NewEvent::new(EventType::PaymentRevealed, Some(user.id), &client).item(ItemType::Payment, id));
+
+    Ok(Json(payment))
 }
 
 
 #[get("/payments")]
 pub async fn get_payments(user: User, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>) -> Result<Json<Vec<Payment>>, APIError> {
-    return payment_dao.get_payments(user.id).await
-        .map(|payment| Json(payment))
-        .map_err(|err| APIError::InternalError(err.to_string()));
+    let mut payments = payment_dao.get_payments(user.id, None).await?;
+    payments.iter_mut().for_each(Payment::mask);
+
+    Ok(Json(payments))
 }
 
 #[delete("/payments/<id>")]
-pub async fn delete_payment(user: User, id: i32, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>) -> Result<(), APIError> {
-    return payment_dao.delete_payment(id).await
-        .map_err(|err| APIError::InternalError(err.to_string()));
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_payment(user: User, id: i32, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    require_permission(user.id, ItemType::Payment, id, Permission::Owner, share_dao).await?;
+    let user_ids = share_dao.get_item_users(ItemType::Payment, id).await?;
+
+    let attachments = attachment_dao.get_attachments(ItemType::Payment, &[id]).await?;
+
+    payment_dao.delete_payment(id).await?;
+
+    remove_attachment_files(&attachments).await;
+
+    events.log(NewEvent::new(EventType::ItemDeleted, Some(user.id), &client).item(ItemType::Payment, id));
+    push.publish(user_ids, PushEvent::ItemDeleted { item_type: ItemType::Payment, item_id: id });
+
+    Ok(())
 }
 
 
 #[put("/payments/<id>", data = "<payment>")]
-pub async fn update_payment(user: User, id: i32, payment: Json<PaymentDto>, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>) -> Result<Json<Payment>, APIError> {
-    return payment_dao.update_payment(id, payment.0).await
-        .map(|payment| Ok(Json(payment)))
-        .map_err(|err| APIError::InternalError(err.to_string()))?;
+#[allow(clippy::too_many_arguments)]
+pub async fn update_payment(user: User, id: i32, payment: Validated<PaymentDto>, if_match: IfMatch, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<Payment>>, APIError> {
+    let permission = require_permission(user.id, ItemType::Payment, id, Permission::Write, share_dao).await?;
+
+    match (&payment.card_number, payment.security_code) {
+        (Some(number), Some(code)) => require_security_code(CardBrand::detect(&normalize_card_number(number)), Some(code))?,
+        (None, None) => {}
+        (number, code) => {
+            let stored = payment_dao.get_payment(id).await?;
+            let brand = number.as_deref().map_or(stored.brand, |number| CardBrand::detect(&normalize_card_number(number)));
+            require_security_code(brand, code.or(Some(stored.security_code)))?;
+        }
+    }
+
+    let submitted = payment.0.clone();
+    let mut payment = match payment_dao.update_payment(id, payment.0, if_match.0).await {
+        Err(DBError::StaleRevision) => {
+            let mut current = payment_dao.get_payment(id).await?;
+            current.mask();
+            current.shared = permission != Permission::Owner;
+            current.permission = permission;
+            return Err(APIError::EditConflict(Box::new(EditConflict::new(&current, &submitted))));
+        }
+        result => result?,
+    };
+    payment.shared = permission != Permission::Owner;
+    payment.permission = permission;
+
+    events.log(NewEvent::new(EventType::ItemUpdated, Some(user.id), &client).item(ItemType::Payment, id));
+    push.publish(share_dao.get_item_users(ItemType::Payment, id).await?, PushEvent::ItemUpdated { item_type: ItemType::Payment, item_id: id });
+
+    payment.mask();
+    Ok(Tagged::new(payment.revision, Json(payment)))
+}
+
+/// The security code has to fit the card's brand. On updates whichever of the two isn't sent
+/// is taken from the stored card, so a new number is checked against the stored code too.
+fn require_security_code(brand: CardBrand, code: Option<i16>) -> Result<(), APIError> {
+    match code {
+        Some(code) => validate_security_code(brand, code)
+            .map_err(|err| APIError::Validation(vec![field_error("security_code", &err)])),
+        None => Ok(()),
+    }
 }
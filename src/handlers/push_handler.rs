@@ -0,0 +1,35 @@
+use rocket::{get, Shutdown, State};
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+
+use crate::models::push_model::PushEvent;
+use crate::models::user_model::User;
+use crate::persistence::push_hub::PushHub;
+
+/// Server-sent events for one of the user's devices, so clients can refetch what changed
+/// instead of polling. The stream ends after a `logout` event.
+#[get("/push")]
+pub fn push_events(user: User, push: &State<PushHub>, mut shutdown: Shutdown) -> EventStream![] {
+    let mut receiver = push.subscribe();
+
+    EventStream! {
+        loop {
+            let event = select! {
+                message = receiver.recv() => match message {
+                    Ok(message) if message.user_ids.contains(&user.id) => message.event,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => PushEvent::Resync,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+
+            yield Event::json(&event).event(event.name());
+
+            if event == PushEvent::Logout {
+                break;
+            }
+        }
+    }
+}
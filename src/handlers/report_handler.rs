@@ -0,0 +1,15 @@
+use rocket::{get, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::models::report_model::{SecurityReport, UNUSED_LOGIN_DAYS};
+use crate::models::user_model::User;
+use crate::persistence::login_dao::LoginDao;
+
+/// What in the user's own vault needs attention.
+#[get("/reports/security")]
+pub async fn get_security_report(user: User, login_dao: &State<Box<dyn LoginDao + Sync + Send>>) -> Result<Json<SecurityReport>, APIError> {
+    let unused_logins = login_dao.find_unused_logins(user.id, UNUSED_LOGIN_DAYS).await?;
+
+    Ok(Json(SecurityReport { unused_logins }))
+}
@@ -0,0 +1,227 @@
+use base64::{Engine as _, engine::general_purpose};
+use log::error;
+use rand::random;
+use rocket::{Data, delete, get, post, State};
+use rocket::data::ToByteUnit;
+use rocket::http::ContentType;
+use rocket::serde::json::Json;
+use rocket_multipart_form_data::{MultipartFormData, MultipartFormDataError, MultipartFormDataField, MultipartFormDataOptions};
+use tokio::fs;
+use validator::Validate;
+
+use crate::APIError;
+use crate::handlers::internal_error;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::send_model::{SecureSend, SecureSendAccessDto, SecureSendDto, SecureSendRecord, SecureSendView};
+use crate::models::storage_model::ContentLength;
+use crate::models::user_model::User;
+use crate::models::validation_model::{field_errors, Validated};
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::file_storage;
+use crate::persistence::send_dao::SendDao;
+use crate::persistence::storage_dao::StorageDao;
+
+#[post("/sends", data = "<send>")]
+pub async fn create_send(user: User, send: Validated<SecureSendDto>, send_dao: &State<Box<dyn SendDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<SecureSend>, APIError> {
+    if send.text.is_none() {
+        return Err(APIError::BadRequest(String::from("Text send requires text.")));
+    }
+
+    let secure_send = send_dao.create_send(user.id, new_token(), send.0, None).await?;
+
+    events.log(NewEvent::new(EventType::SendCreated, Some(user.id), &client)
+        .details(format!("send {}", secure_send.id)));
+
+    Ok(Json(secure_send))
+}
+
+/// Multipart upload of an already encrypted file, with the options of `SecureSendDto` as text fields.
+/// The file counts against the storage quota of its creator until the send is deleted.
+#[allow(clippy::too_many_arguments)]
+#[post("/sends/file", data = "<paste>")]
+pub async fn create_file_send<'r>(
+    user: User,
+    ct: &ContentType,
+    content_length: Option<ContentLength>,
+    paste: Data<'r>,
+    send_dao: &State<Box<dyn SendDao + Sync + Send>>,
+    storage_dao: &State<Box<dyn StorageDao + Sync + Send>>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
+) -> Result<Json<SecureSend>, APIError> {
+    let usage = storage_dao.get_storage_usage(user.id).await?;
+
+    if let Some(ContentLength(length)) = content_length {
+        if length > usage.remaining() as u64 {
+            return Err(APIError::PayloadTooLarge(format!("Upload exceeds your storage quota ({} of {} bytes used).", usage.used, usage.quota)));
+        }
+    }
+
+    let size_limit = 100.megabytes().as_u64().min(usage.remaining() as u64);
+    let options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
+        MultipartFormDataField::file("file").size_limit(size_limit),
+        MultipartFormDataField::text("expires_in_minutes"),
+        MultipartFormDataField::text("max_views"),
+        MultipartFormDataField::text("password"),
+        MultipartFormDataField::text("hide_email"),
+    ]);
+
+    let multipart_form = MultipartFormData::parse(ct, paste, options).await
+        .map_err(|err| match err {
+            MultipartFormDataError::DataTooLargeError(_) => APIError::PayloadTooLarge(format!("Upload exceeds the limit of {} bytes.", size_limit)),
+            err => APIError::BadRequest(err.to_string()),
+        })?;
+    let file = multipart_form.files.get("file").and_then(|files| files.first())
+        .ok_or(APIError::BadRequest(String::from("Missing file field.")))?;
+    let text = |name: &str| multipart_form.texts.get(name).and_then(|t| t.first()).map(|t| t.text.clone());
+
+    let send = SecureSendDto {
+        text: None,
+        expires_in_minutes: text("expires_in_minutes").map(|v| v.parse()).transpose()
+            .map_err(|_| APIError::BadRequest(String::from("Invalid expires_in_minutes.")))?,
+        max_views: text("max_views").map(|v| v.parse()).transpose()
+            .map_err(|_| APIError::BadRequest(String::from("Invalid max_views.")))?,
+        password: text("password"),
+        hide_email: text("hide_email").map(|v| v == "true"),
+    };
+    send.validate().map_err(|errors| APIError::Validation(field_errors(&errors)))?;
+
+    let staged = file_storage::stage(&file.path).await
+        .map_err(|err| internal_error(&err))?;
+
+    // Without a Content-Length the parser only knows the limit, so check what actually arrived.
+    if staged.size > usage.remaining() as u64 {
+        file_storage::discard(&staged).await;
+        return Err(APIError::PayloadTooLarge(format!("Upload exceeds your storage quota ({} of {} bytes used).", usage.used, usage.quota)));
+    }
+    let file_name = file.file_name.clone().unwrap_or_default();
+
+    let secure_send = match send_dao.create_send(user.id, new_token(), send, Some((file_name, staged.size as i32))).await {
+        Ok(secure_send) => secure_send,
+        Err(err) => {
+            file_storage::discard(&staged).await;
+            return Err(APIError::from(err));
+        }
+    };
+
+    if let Err(err) = file_storage::commit(&staged, &file_storage::send_path(secure_send.id)).await {
+        file_storage::discard(&staged).await;
+        send_dao.delete_send(secure_send.id).await?;
+        return Err(internal_error(&err));
+    }
+
+    events.log(NewEvent::new(EventType::SendCreated, Some(user.id), &client)
+        .details(format!("send {}", secure_send.id)));
+
+    Ok(Json(secure_send))
+}
+
+#[get("/sends")]
+pub async fn get_sends(user: User, send_dao: &State<Box<dyn SendDao + Sync + Send>>) -> Result<Json<Vec<SecureSend>>, APIError> {
+    send_dao.get_sends(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+#[delete("/sends/<id>")]
+pub async fn delete_send(user: User, id: i32, send_dao: &State<Box<dyn SendDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<(), APIError> {
+    let owner_id = send_dao.get_send_owner(id).await?;
+
+    if owner_id != user.id {
+        return Err(APIError::Unauthorized(String::from("Send doesn't belong to user.")));
+    }
+
+    send_dao.delete_send(id).await?;
+
+    if let Err(err) = file_storage::remove(&file_storage::send_path(id)).await {
+        error!("Failed to remove send file {}: {:?}", id, err);
+    }
+
+    events.log(NewEvent::new(EventType::SendDeleted, Some(user.id), &client)
+        .details(format!("send {}", id)));
+
+    Ok(())
+}
+
+/// Public: returns a text send, counting a view, or the metadata of a file send.
+#[post("/sends/<token>/access", data = "<access>")]
+pub async fn access_send(token: &str, access: Json<SecureSendAccessDto>, send_dao: &State<Box<dyn SendDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Json<SecureSendView>, APIError> {
+    let mut send = open_send(token, access.0, send_dao).await?;
+
+    if send.kind == "text" {
+        record_view(&mut send, send_dao).await?;
+    }
+
+    events.log(NewEvent::new(EventType::SendAccessed, None, &client)
+        .details(format!("send {}", send.id)));
+
+    Ok(Json(SecureSendView {
+        views_left: send.max_views.map(|max| max - send.view_count),
+        kind: send.kind,
+        text: send.content,
+        file_name: send.file_name,
+        file_size: send.file_size,
+        creator_email: send.creator_email,
+        expires_at: send.expires_at,
+    }))
+}
+
+/// Public: streams the encrypted file of a file send, counting a view.
+#[post("/sends/<token>/download", data = "<access>")]
+pub async fn download_send(token: &str, access: Json<SecureSendAccessDto>, send_dao: &State<Box<dyn SendDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<fs::File, APIError> {
+    let mut send = open_send(token, access.0, send_dao).await?;
+
+    if send.kind != "file" {
+        return Err(APIError::BadRequest(String::from("Send doesn't contain a file.")));
+    }
+
+    record_view(&mut send, send_dao).await?;
+
+    let file = fs::File::open(file_storage::send_path(send.id)).await
+        .map_err(|err| internal_error(&err))?;
+
+    events.log(NewEvent::new(EventType::SendAccessed, None, &client)
+        .details(format!("send {} downloaded", send.id)));
+
+    Ok(file)
+}
+
+async fn open_send(token: &str, access: SecureSendAccessDto, send_dao: &State<Box<dyn SendDao + Sync + Send>>) -> Result<SecureSendRecord, APIError> {
+    let send = send_dao.get_send_by_token(token).await
+        .map_err(|_| APIError::NotFound(String::from("Send not found.")))?;
+
+    if !send.available {
+        return Err(APIError::NotFound(String::from("Send not found.")));
+    }
+
+    if let Some(hash) = &send.password {
+        let password = access.password
+            .ok_or(APIError::Unauthorized(String::from("Send is password protected.")))?;
+
+        if send.locked {
+            return Err(APIError::TooManyRequests(String::from("Too many wrong passwords. Please try again later.")));
+        }
+
+        if !bcrypt::verify(password, hash).unwrap_or(false) {
+            send_dao.record_failed_password(send.id).await?;
+            return Err(APIError::InvalidCredentials(String::from("Invalid send password.")));
+        }
+    }
+
+    Ok(send)
+}
+
+async fn record_view(send: &mut SecureSendRecord, send_dao: &State<Box<dyn SendDao + Sync + Send>>) -> Result<(), APIError> {
+    let counted = send_dao.record_view(send.id).await?;
+
+    if !counted {
+        return Err(APIError::NotFound(String::from("Send not found.")));
+    }
+
+    send.view_count += 1;
+    Ok(())
+}
+
+fn new_token() -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(random::<[u8; 32]>())
+}
@@ -0,0 +1,73 @@
+use rocket::{delete, get, post, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::item_access::require_permission;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::push_model::PushEvent;
+use crate::models::share_model::{Permission, Share, ShareDto};
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
+use crate::persistence::users_dao::UsersDao;
+
+/// Shares an item with another user, or changes the permission of an existing share.
+#[post("/shares", data = "<share>")]
+pub async fn create_share(user: User, share: Validated<ShareDto>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Json<Share>, APIError> {
+    require_permission(user.id, share.item_type, share.item_id, Permission::Owner, share_dao).await?;
+
+    if !matches!(share.permission, Permission::Read | Permission::Write) {
+        return Err(APIError::BadRequest(String::from("Items can only be shared with read or write permission.")));
+    }
+
+    let recipient = users_dao.get_user_by_username(&share.username).await
+        .map_err(|_| APIError::NotFound(format!("User {} not found.", share.username)))?;
+
+    if recipient.id == user.id {
+        return Err(APIError::BadRequest(String::from("Items can't be shared with their owner.")));
+    }
+
+    let share = share_dao.create_share(user.id, recipient.id, share.item_type, share.item_id, share.permission).await?;
+
+    events.log(NewEvent::new(EventType::ShareCreated, Some(user.id), &client)
+        .item(share.item_type, share.item_id)
+        .details(format!("{} with {}", share.permission.as_str(), share.shared_with_username)));
+    push.publish(vec![share.shared_with_id], PushEvent::ItemCreated { item_type: share.item_type, item_id: share.item_id });
+
+    Ok(Json(share))
+}
+
+#[get("/shares/with-me")]
+pub async fn get_shares_with_me(user: User, share_dao: &State<Box<dyn ShareDao + Sync + Send>>) -> Result<Json<Vec<Share>>, APIError> {
+    share_dao.get_shares_with_user(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+#[get("/shares/by-me")]
+pub async fn get_shares_by_me(user: User, share_dao: &State<Box<dyn ShareDao + Sync + Send>>) -> Result<Json<Vec<Share>>, APIError> {
+    share_dao.get_shares_by_user(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+/// Revokes a share. The recipient may also remove a share to leave it.
+#[delete("/shares/<id>")]
+pub async fn delete_share(user: User, id: i32, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    let share = share_dao.get_share(id).await?;
+
+    if share.owner_id != user.id && share.shared_with_id != user.id {
+        return Err(APIError::Unauthorized(String::from("Share doesn't belong to user.")));
+    }
+
+    share_dao.delete_share(id).await?;
+
+    events.log(NewEvent::new(EventType::ShareDeleted, Some(user.id), &client)
+        .item(share.item_type, share.item_id)
+        .details(format!("with {}", share.shared_with_username)));
+    push.publish(vec![share.shared_with_id], PushEvent::ItemDeleted { item_type: share.item_type, item_id: share.item_id });
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use rocket::{delete, get, post, put, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::item_handler::{create_item, delete_item, get_item, get_items, update_item};
+use crate::models::event_model::ClientInfo;
+use crate::models::revision_model::{IfMatch, Tagged};
+use crate::models::software_license_model::{SoftwareLicense, SoftwareLicenseDto};
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
+
+#[post("/software_licenses", data = "<software_license>")]
+pub async fn create_software_license(user: User, software_license: Validated<SoftwareLicenseDto>, software_license_dao: &State<Box<dyn ItemDao<SoftwareLicense> + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<SoftwareLicense>>, APIError> {
+    create_item(user, software_license.0, software_license_dao, client, events, push).await
+}
+
+#[get("/software_licenses/<id>")]
+pub async fn get_software_license(user: User, id: i32, software_license_dao: &State<Box<dyn ItemDao<SoftwareLicense> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Tagged<Json<SoftwareLicense>>, APIError> {
+    get_item(user, id, software_license_dao, share_dao, client, events).await
+}
+
+#[get("/software_licenses")]
+pub async fn get_software_licenses(user: User, software_license_dao: &State<Box<dyn ItemDao<SoftwareLicense> + Sync + Send>>) -> Result<Json<Vec<SoftwareLicense>>, APIError> {
+    get_items(user, software_license_dao).await
+}
+
+#[put("/software_licenses/<id>", data = "<software_license>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_software_license(user: User, id: i32, software_license: Validated<SoftwareLicenseDto>, if_match: IfMatch, software_license_dao: &State<Box<dyn ItemDao<SoftwareLicense> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<SoftwareLicense>>, APIError> {
+    update_item(user, id, software_license.0, if_match, software_license_dao, share_dao, client, events, push).await
+}
+
+#[delete("/software_licenses/<id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_software_license(user: User, id: i32, software_license_dao: &State<Box<dyn ItemDao<SoftwareLicense> + Sync + Send>>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    delete_item(user, id, software_license_dao, attachment_dao, share_dao, client, events, push).await
+}
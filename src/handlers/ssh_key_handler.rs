@@ -0,0 +1,42 @@
+use rocket::{delete, get, post, put, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::item_handler::{create_item, delete_item, get_item, get_items, update_item};
+use crate::models::event_model::ClientInfo;
+use crate::models::revision_model::{IfMatch, Tagged};
+use crate::models::ssh_key_model::{SshKey, SshKeyDto};
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
+
+#[post("/ssh_keys", data = "<ssh_key>")]
+pub async fn create_ssh_key(user: User, ssh_key: Validated<SshKeyDto>, ssh_key_dao: &State<Box<dyn ItemDao<SshKey> + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<SshKey>>, APIError> {
+    create_item(user, ssh_key.0, ssh_key_dao, client, events, push).await
+}
+
+#[get("/ssh_keys/<id>")]
+pub async fn get_ssh_key(user: User, id: i32, ssh_key_dao: &State<Box<dyn ItemDao<SshKey> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Tagged<Json<SshKey>>, APIError> {
+    get_item(user, id, ssh_key_dao, share_dao, client, events).await
+}
+
+#[get("/ssh_keys")]
+pub async fn get_ssh_keys(user: User, ssh_key_dao: &State<Box<dyn ItemDao<SshKey> + Sync + Send>>) -> Result<Json<Vec<SshKey>>, APIError> {
+    get_items(user, ssh_key_dao).await
+}
+
+#[put("/ssh_keys/<id>", data = "<ssh_key>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_ssh_key(user: User, id: i32, ssh_key: Validated<SshKeyDto>, if_match: IfMatch, ssh_key_dao: &State<Box<dyn ItemDao<SshKey> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<SshKey>>, APIError> {
+    update_item(user, id, ssh_key.0, if_match, ssh_key_dao, share_dao, client, events, push).await
+}
+
+#[delete("/ssh_keys/<id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_ssh_key(user: User, id: i32, ssh_key_dao: &State<Box<dyn ItemDao<SshKey> + Sync + Send>>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    delete_item(user, id, ssh_key_dao, attachment_dao, share_dao, client, events, push).await
+}
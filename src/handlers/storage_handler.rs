@@ -0,0 +1,14 @@
+use rocket::{get, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::models::storage_model::StorageUsage;
+use crate::models::user_model::User;
+use crate::persistence::storage_dao::StorageDao;
+
+#[get("/me/storage")]
+pub async fn get_storage_usage(user: User, storage_dao: &State<Box<dyn StorageDao + Sync + Send>>) -> Result<Json<StorageUsage>, APIError> {
+    storage_dao.get_storage_usage(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
@@ -0,0 +1,66 @@
+use rocket::{get, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::models::api_credential_model::ApiCredential;
+use crate::models::identity_model::Identity;
+use crate::models::item_model::VaultItem;
+use crate::models::login_model::Login;
+use crate::models::payment_model::Payment;
+use crate::models::secured_note::SecuredNote;
+use crate::models::software_license_model::SoftwareLicense;
+use crate::models::ssh_key_model::SshKey;
+use crate::models::sync_model::SyncResponse;
+use crate::models::user_model::User;
+use crate::models::wifi_network_model::WifiNetwork;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::login_dao::LoginDao;
+use crate::persistence::payment_dao::PaymentDao;
+use crate::persistence::secured_note_dao::SecuredNoteDao;
+use crate::persistence::sync_dao::SyncDao;
+
+/// Items changed since revision `since`, and tombstones for the ones gone. Without `since`, or
+/// with a revision the server doesn't know, e.g. after a restore, the whole vault is returned.
+#[get("/sync?<since>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn sync(user: User, since: Option<i64>, sync_dao: &State<Box<dyn SyncDao + Sync + Send>>, login_dao: &State<Box<dyn LoginDao + Sync + Send>>, payment_dao: &State<Box<dyn PaymentDao + Sync + Send>>, secured_note_dao: &State<Box<dyn SecuredNoteDao + Sync + Send>>, identity_dao: &State<Box<dyn ItemDao<Identity> + Sync + Send>>, ssh_key_dao: &State<Box<dyn ItemDao<SshKey> + Sync + Send>>, api_credential_dao: &State<Box<dyn ItemDao<ApiCredential> + Sync + Send>>, wifi_network_dao: &State<Box<dyn ItemDao<WifiNetwork> + Sync + Send>>, software_license_dao: &State<Box<dyn ItemDao<SoftwareLicense> + Sync + Send>>) -> Result<Json<SyncResponse>, APIError> {
+    // Read the revision first: anything that changes while the items are read is sent again
+    // next time rather than missed.
+    let revision = sync_dao.get_revision(user.id).await?;
+    let since = since.filter(|since| *since > 0 && *since <= revision);
+
+    let mut logins = login_dao.get_logins(user.id, since).await?;
+    logins.iter_mut().for_each(Login::mask_custom_fields);
+    let mut payments = payment_dao.get_payments(user.id, since).await?;
+    payments.iter_mut().for_each(Payment::mask);
+    let mut secured_notes = secured_note_dao.get_secured_notes(user.id, since).await?;
+    secured_notes.iter_mut().for_each(SecuredNote::mask_custom_fields);
+    let mut identities = identity_dao.get_items(user.id, since).await?;
+    identities.iter_mut().for_each(Identity::mask_custom_fields);
+    let mut ssh_keys = ssh_key_dao.get_items(user.id, since).await?;
+    ssh_keys.iter_mut().for_each(SshKey::mask_custom_fields);
+    let mut api_credentials = api_credential_dao.get_items(user.id, since).await?;
+    api_credentials.iter_mut().for_each(ApiCredential::mask_custom_fields);
+    let mut wifi_networks = wifi_network_dao.get_items(user.id, since).await?;
+    wifi_networks.iter_mut().for_each(WifiNetwork::mask_custom_fields);
+    let mut software_licenses = software_license_dao.get_items(user.id, since).await?;
+    software_licenses.iter_mut().for_each(SoftwareLicense::mask_custom_fields);
+    let deleted = match since {
+        Some(since) => sync_dao.get_tombstones(user.id, since).await?,
+        None => vec![],
+    };
+
+    Ok(Json(SyncResponse {
+        revision,
+        full: since.is_none(),
+        logins,
+        payments,
+        secured_notes,
+        identities,
+        ssh_keys,
+        api_credentials,
+        wifi_networks,
+        software_licenses,
+        deleted,
+    }))
+}
@@ -1,8 +1,18 @@
 use rocket::{delete, get, post, put, serde::json::Json, State};
 
 use crate::{APIError, persistence::users_dao::UsersDao};
+use crate::handlers::account_handler::{check_password, send_email_verification};
 use crate::handlers::handlers_inner;
+use crate::mailer::Mailer;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::password_policy_model::PasswordPolicy;
+use crate::models::push_model::PushEvent;
 use crate::models::user_model::{User, UserDto, UserUpdateDto};
+use crate::models::user_token_model::TokenPurpose;
+use crate::models::validation_model::Validated;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::user_token_dao::UserTokenDao;
 
 #[get("/user/<id>")]
 pub async fn get_user(
@@ -16,25 +26,57 @@ pub async fn get_user(
     }
 }
 
+/// Signs up a user and emails them a link to verify their address.
 #[post("/user", data = "<user>")]
 pub async fn create_user(
-    user: Json<UserDto>,
+    user: Validated<UserDto>,
     users_dao: &State<Box<dyn UsersDao + Sync + Send>>,
+    token_dao: &State<Box<dyn UserTokenDao + Sync + Send>>,
+    mailer: &State<Mailer>,
+    policy: &State<PasswordPolicy>,
 ) -> Result<Json<User>, APIError> {
+    check_password(policy, &user.password, &[&user.username, &user.email, &user.first_name, &user.last_name])?;
+
     match handlers_inner::create_user(user.0, users_dao.inner()).await {
-        Ok(u) => Ok(Json(u)),
+        Ok(u) => {
+            send_email_verification(&u, &u.email, TokenPurpose::VerifyEmail, mailer, token_dao.as_ref()).await?;
+            Ok(Json(u))
+        }
         Err(err) => Err(err.into()),
     }
 }
 
+/// Updates the user's profile. A new email address only takes effect once it is confirmed
+/// through the link sent to it.
+#[allow(clippy::too_many_arguments)]
 #[put("/user/<id>", data = "<user>")]
 pub async fn update_user(
-    user: Json<UserUpdateDto>,
+    current_user: User,
+    user: Validated<UserUpdateDto>,
     id: i32,
     users_dao: &State<Box<dyn UsersDao + Sync + Send>>,
+    token_dao: &State<Box<dyn UserTokenDao + Sync + Send>>,
+    mailer: &State<Mailer>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
 ) -> Result<Json<User>, APIError> {
-    match handlers_inner::update_user(user.0, id, users_dao.inner()).await {
-        Ok(u) => Ok(Json(u)),
+    if current_user.id != id {
+        return Err(APIError::Unauthorized(String::from("Users can only update their own profile.")));
+    }
+
+    let mut update = user.0;
+    if let Some(email) = update.email.take() {
+        let email = email.trim().to_string();
+        if email != current_user.email {
+            send_email_verification(&current_user, &email, TokenPurpose::ChangeEmail, mailer, token_dao.as_ref()).await?;
+        }
+    }
+
+    match handlers_inner::update_user(update, id, users_dao.inner()).await {
+        Ok(u) => {
+            events.log(NewEvent::new(EventType::ProfileUpdated, Some(id), &client));
+            Ok(Json(u))
+        }
         Err(err) => Err(err.into()),
     }
 }
@@ -42,11 +84,20 @@ pub async fn update_user(
 
 #[delete("/user/<id>")]
 pub async fn delete_user(
+    current_user: Option<User>,
     id: i32,
     users_dao: &State<Box<dyn UsersDao + Sync + Send>>,
+    push: &State<PushHub>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
 ) -> Result<(), APIError> {
     match handlers_inner::delete_user(id, users_dao.inner()).await {
-        Ok(_) => Ok(()),
+        Ok(_) => {
+            events.log(NewEvent::new(EventType::AccountDeleted, current_user.map(|user| user.id), &client)
+                .details(format!("user {}", id)));
+            push.publish(vec![id], PushEvent::Logout);
+            Ok(())
+        }
         Err(err) => Err(err.into()),
     }
 }
@@ -0,0 +1,480 @@
+use base64::{Engine as _, engine::general_purpose};
+use jsonwebtoken::EncodingKey;
+use rand::random;
+use rocket::{delete, get, post, put, State};
+use rocket::http::{Cookie, CookieJar};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::serde::json::{Json, serde_json};
+use webauthn_rs::prelude::{AuthenticationResult, Passkey, PasskeyAuthentication, PasskeyRegistration, Webauthn};
+
+use crate::APIError;
+use crate::handlers::internal_error;
+use crate::models::event_model::{ClientInfo, EventType, NewEvent};
+use crate::models::user_model::User;
+use crate::models::validation_model::Validated;
+use crate::models::webauthn_model::{AuthenticationFinishDto, AuthenticationStart, CredentialNameDto, CredentialUsage, PasskeyLoginDto, RegistrationFinishDto, RegistrationStart, RegistrationStartDto, WebauthnCredential};
+use crate::persistence::auth_dao::issue_token;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::users_dao::UsersDao;
+use crate::persistence::webauthn_dao::WebauthnDao;
+
+/// Private cookie holding the user who passed the password check but still owes a second factor.
+pub const SECOND_FACTOR_COOKIE: &str = "SecondFactor";
+const SECOND_FACTOR_MINUTES: i64 = 5;
+
+const REGISTRATION: &str = "registration";
+const PASSKEY_LOGIN: &str = "passkey_login";
+const SECOND_FACTOR: &str = "second_factor";
+
+#[derive(Serialize, Deserialize)]
+struct PendingRegistration {
+    name: String,
+    usage: CredentialUsage,
+    state: PasskeyRegistration,
+}
+
+/// Remembers that `user_id` logged in with their password and has to present a security key.
+pub fn require_second_factor(user_id: i32, jar: &CookieJar<'_>) {
+    let expires = (chrono::Utc::now() + chrono::Duration::minutes(SECOND_FACTOR_MINUTES)).timestamp();
+    jar.add_private(Cookie::new(SECOND_FACTOR_COOKIE, format!("{}:{}", user_id, expires)));
+}
+
+/// Starts registering a passkey or security key for the logged in user.
+#[post("/webauthn/credentials/register/start", data = "<registration>")]
+pub async fn start_registration(user: User, registration: Validated<RegistrationStartDto>, webauthn: &State<Webauthn>, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<Json<RegistrationStart>, APIError> {
+    if registration.name.trim().is_empty() {
+        return Err(APIError::BadRequest(String::from("Credential name can't be empty.")));
+    }
+
+    let webauthn_id = webauthn_dao.get_webauthn_id(user.id).await?;
+    let existing = webauthn_dao.get_passkeys(user.id, None).await?
+        .into_iter()
+        .map(|(_, passkey)| passkey.cred_id().clone())
+        .collect();
+
+    let display_name = format!("{} {}", user.first_name, user.last_name);
+    let (options, state) = webauthn.start_passkey_registration(webauthn_id, &user.username, &display_name, Some(existing))
+        .map_err(|err| internal_error(&err))?;
+
+    let pending = PendingRegistration { name: registration.0.name, usage: registration.0.usage, state };
+    let ceremony_id = save_ceremony(user.id, REGISTRATION, &pending, webauthn_dao).await?;
+
+    Ok(Json(RegistrationStart { ceremony_id, options }))
+}
+
+#[post("/webauthn/credentials/register/finish", data = "<registration>")]
+pub async fn finish_registration(user: User, registration: Json<RegistrationFinishDto>, webauthn: &State<Webauthn>, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<Json<WebauthnCredential>, APIError> {
+    let (user_id, pending) = take_ceremony::<PendingRegistration>(&registration.ceremony_id, REGISTRATION, webauthn_dao).await?;
+    if user_id != user.id {
+        return Err(APIError::Unauthorized(String::from("Registration belongs to another user.")));
+    }
+
+    let passkey = webauthn.finish_passkey_registration(&registration.credential, &pending.state)
+        .map_err(|err| APIError::BadRequest(format!("Registration failed: {}", err)))?;
+
+    webauthn_dao.create_credential(user.id, pending.name.trim(), pending.usage, &passkey).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+#[get("/webauthn/credentials")]
+pub async fn get_credentials(user: User, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<Json<Vec<WebauthnCredential>>, APIError> {
+    webauthn_dao.get_credentials(user.id).await
+        .map(Json)
+        .map_err(APIError::from)
+}
+
+#[put("/webauthn/credentials/<id>", data = "<credential>")]
+pub async fn rename_credential(user: User, id: i32, credential: Validated<CredentialNameDto>, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<(), APIError> {
+    if credential.name.trim().is_empty() {
+        return Err(APIError::BadRequest(String::from("Credential name can't be empty.")));
+    }
+
+    require_credential_owner(id, user.id, webauthn_dao).await?;
+
+    webauthn_dao.rename_credential(id, credential.name.trim()).await
+        .map_err(APIError::from)
+}
+
+#[delete("/webauthn/credentials/<id>")]
+pub async fn delete_credential(user: User, id: i32, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<(), APIError> {
+    require_credential_owner(id, user.id, webauthn_dao).await?;
+
+    webauthn_dao.delete_credential(id).await
+        .map_err(APIError::from)
+}
+
+/// Starts a login with one of the user's passkeys instead of their password.
+#[post("/webauthn/login/start", data = "<login>")]
+pub async fn start_passkey_login(login: Json<PasskeyLoginDto>, webauthn: &State<Webauthn>, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>, users_dao: &State<Box<dyn UsersDao + Sync + Send>>) -> Result<Json<AuthenticationStart>, APIError> {
+    let user = users_dao.get_user_by_username(&login.username).await
+        .map_err(|_| APIError::InvalidCredentials(String::from("No passkey registered for user.")))?;
+
+    start_authentication(user.id, CredentialUsage::Passkey, PASSKEY_LOGIN, webauthn, webauthn_dao).await
+        .map(Json)
+}
+
+#[post("/webauthn/login/finish", data = "<login>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn finish_passkey_login(
+    login: Json<AuthenticationFinishDto>,
+    webauthn: &State<Webauthn>,
+    webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>,
+    users_dao: &State<Box<dyn UsersDao + Sync + Send>>,
+    jwt_encoding_key: &State<EncodingKey>,
+    jar: &CookieJar<'_>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
+) -> Result<Json<User>, APIError> {
+    let user_id = finish_authentication(login.0, PASSKEY_LOGIN, webauthn, webauthn_dao).await
+        .inspect_err(|_| events.log(NewEvent::new(EventType::LoginFailed, None, &client).details(String::from("passkey"))))?;
+
+    let user = users_dao.get_user(user_id).await?;
+    let generation = users_dao.get_token_generation(user.id).await?;
+
+    jar.add_private(Cookie::new("Authorization", issue_token(user.id, generation, jwt_encoding_key).0));
+    events.log(NewEvent::new(EventType::LoginSucceeded, Some(user.id), &client).details(String::from("passkey")));
+
+    Ok(Json(user))
+}
+
+/// Starts the security key check for a user who already passed `/login`.
+#[post("/webauthn/second-factor/start")]
+pub async fn start_second_factor(jar: &CookieJar<'_>, webauthn: &State<Webauthn>, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<Json<AuthenticationStart>, APIError> {
+    let user_id = pending_second_factor(jar)?;
+
+    start_authentication(user_id, CredentialUsage::SecondFactor, SECOND_FACTOR, webauthn, webauthn_dao).await
+        .map(Json)
+}
+
+#[post("/webauthn/second-factor/finish", data = "<login>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn finish_second_factor(
+    login: Json<AuthenticationFinishDto>,
+    webauthn: &State<Webauthn>,
+    webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>,
+    users_dao: &State<Box<dyn UsersDao + Sync + Send>>,
+    jwt_encoding_key: &State<EncodingKey>,
+    jar: &CookieJar<'_>,
+    client: ClientInfo,
+    events: &State<EventLogger>,
+) -> Result<Json<User>, APIError> {
+    let pending_user_id = pending_second_factor(jar)?;
+
+    let user_id = finish_authentication(login.0, SECOND_FACTOR, webauthn, webauthn_dao).await
+        .inspect_err(|_| events.log(NewEvent::new(EventType::LoginFailed, Some(pending_user_id), &client).details(String::from("second factor"))))?;
+    if user_id != pending_user_id {
+        return Err(APIError::InvalidCredentials(String::from("Second factor belongs to another user.")));
+    }
+
+    let user = users_dao.get_user(user_id).await?;
+    let generation = users_dao.get_token_generation(user.id).await?;
+
+    jar.remove_private(Cookie::named(SECOND_FACTOR_COOKIE));
+    jar.add_private(Cookie::new("Authorization", issue_token(user.id, generation, jwt_encoding_key).0));
+    events.log(NewEvent::new(EventType::LoginSucceeded, Some(user.id), &client).details(String::from("second factor")));
+
+    Ok(Json(user))
+}
+
+async fn start_authentication(user_id: i32, usage: CredentialUsage, kind: &str, webauthn: &State<Webauthn>, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<AuthenticationStart, APIError> {
+    let passkeys: Vec<Passkey> = webauthn_dao.get_passkeys(user_id, Some(usage)).await?
+        .into_iter()
+        .map(|(_, passkey)| passkey)
+        .collect();
+
+    if passkeys.is_empty() {
+        return Err(APIError::InvalidCredentials(String::from("No passkey registered for user.")));
+    }
+
+    let (options, state) = webauthn.start_passkey_authentication(&passkeys)
+        .map_err(|err| internal_error(&err))?;
+
+    let ceremony_id = save_ceremony(user_id, kind, &state, webauthn_dao).await?;
+
+    Ok(AuthenticationStart { ceremony_id, options })
+}
+
+/// Verifies the assertion, stores the new signature counter and returns whose credential it was.
+async fn finish_authentication(login: AuthenticationFinishDto, kind: &str, webauthn: &State<Webauthn>, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<i32, APIError> {
+    let (user_id, state) = take_ceremony::<PasskeyAuthentication>(&login.ceremony_id, kind, webauthn_dao).await?;
+
+    let result = webauthn.finish_passkey_authentication(&login.credential, &state)
+        .map_err(|err| APIError::InvalidCredentials(format!("Authentication failed: {}", err)))?;
+
+    update_credential(user_id, &result, webauthn_dao).await?;
+
+    Ok(user_id)
+}
+
+async fn update_credential(user_id: i32, result: &AuthenticationResult, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<(), APIError> {
+    let passkeys = webauthn_dao.get_passkeys(user_id, None).await?;
+
+    for (id, mut passkey) in passkeys {
+        if passkey.update_credential(result).is_some() {
+            return webauthn_dao.update_passkey(id, &passkey, result.counter()).await
+                .map_err(APIError::from);
+        }
+    }
+
+    Ok(())
+}
+
+fn pending_second_factor(jar: &CookieJar<'_>) -> Result<i32, APIError> {
+    let missing = || APIError::Unauthorized(String::from("Log in with a password first."));
+
+    let cookie = jar.get_private(SECOND_FACTOR_COOKIE).ok_or_else(missing)?;
+    let (user_id, expires) = cookie.value().split_once(':').ok_or_else(missing)?;
+    let user_id = user_id.parse::<i32>().map_err(|_| missing())?;
+    let expires = expires.parse::<i64>().map_err(|_| missing())?;
+
+    if expires < chrono::Utc::now().timestamp() {
+        jar.remove_private(Cookie::named(SECOND_FACTOR_COOKIE));
+        return Err(APIError::Unauthorized(String::from("Second factor check expired, log in again.")));
+    }
+
+    Ok(user_id)
+}
+
+async fn require_credential_owner(id: i32, user_id: i32, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<(), APIError> {
+    let owner_id = webauthn_dao.get_credential_owner(id).await
+        .map_err(|_| APIError::NotFound(format!("Credential {} not found.", id)))?;
+
+    if owner_id != user_id {
+        return Err(APIError::NotFound(format!("Credential {} not found.", id)));
+    }
+
+    Ok(())
+}
+
+async fn save_ceremony<T: Serialize>(user_id: i32, kind: &str, state: &T, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<String, APIError> {
+    let ceremony_id = general_purpose::URL_SAFE_NO_PAD.encode(random::<[u8; 32]>());
+    let state = serde_json::to_string(state)
+        .map_err(|err| internal_error(&err))?;
+
+    webauthn_dao.save_ceremony(&ceremony_id, user_id, kind, &state).await?;
+
+    Ok(ceremony_id)
+}
+
+async fn take_ceremony<T: for<'de> Deserialize<'de>>(ceremony_id: &str, kind: &str, webauthn_dao: &State<Box<dyn WebauthnDao + Sync + Send>>) -> Result<(i32, T), APIError> {
+    let (user_id, state) = webauthn_dao.take_ceremony(ceremony_id, kind).await?
+        .ok_or_else(|| APIError::BadRequest(String::from("Unknown or expired ceremony.")))?;
+
+    let state = serde_json::from_str(&state)
+        .map_err(|err| internal_error(&err))?;
+
+    Ok((user_id, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::DecodingKey;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::{Client, LocalResponse};
+    use rocket::routes;
+    use sqlx::PgPool;
+    use url::Url;
+    use webauthn_authenticator_rs::softpasskey::SoftPasskey;
+    use webauthn_authenticator_rs::WebauthnAuthenticator;
+    use webauthn_rs::WebauthnBuilder;
+
+    use crate::persistence::auth_dao::{AuthDao, AuthDaoImpl};
+    use crate::persistence::event_dao::EventDaoImpl;
+    use crate::persistence::users_dao::UsersDaoImpl;
+    use crate::persistence::webauthn_dao::WebauthnDaoImpl;
+
+    use super::*;
+
+    const ORIGIN: &str = "https://localhost:4100";
+
+    struct TestServer {
+        client: Client,
+        db: PgPool,
+        authenticator: WebauthnAuthenticator<SoftPasskey>,
+        user_id: i32,
+        username: String,
+    }
+
+    impl TestServer {
+        /// Serves the WebAuthn routes against `DATABASE_URL` for a fresh user.
+        async fn new() -> TestServer {
+            let db = PgPool::connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL must be set."))
+                .await
+                .expect("Failed to create Postgres connection pool!");
+
+            let username = format!("webauthn-{}", general_purpose::URL_SAFE_NO_PAD.encode(random::<[u8; 9]>()));
+            let user_id = sqlx::query_scalar::<_, i32>(
+                "INSERT INTO users (username, first_name, last_name, password, email) VALUES ($1, 'Test', 'User', '', $1 || '@example.com') RETURNING id"
+            ).bind(&username).fetch_one(&db).await.unwrap();
+
+            let webauthn = WebauthnBuilder::new("localhost", &Url::parse(ORIGIN).unwrap())
+                .and_then(|builder| builder.build())
+                .unwrap();
+
+            let rocket = rocket::build()
+                .mount("/api", routes![
+                    start_registration, finish_registration,
+                    start_passkey_login, finish_passkey_login,
+                    start_second_factor, finish_second_factor,
+                ])
+                .manage(Box::new(WebauthnDaoImpl::new(db.clone())) as Box<dyn WebauthnDao + Send + Sync>)
+                .manage(Box::new(UsersDaoImpl::new(db.clone())) as Box<dyn UsersDao + Send + Sync>)
+                .manage(Box::new(AuthDaoImpl::new(db.clone())) as Box<dyn AuthDao + Send + Sync>)
+                .manage(EventLogger::spawn(Box::new(EventDaoImpl::new(db.clone()))))
+                .manage(webauthn)
+                .manage(EncodingKey::from_secret(b"test"))
+                .manage(DecodingKey::from_secret(b"test"));
+
+            TestServer {
+                client: Client::untracked(rocket).await.unwrap(),
+                db,
+                authenticator: WebauthnAuthenticator::new(SoftPasskey::new()),
+                user_id,
+                username,
+            }
+        }
+
+        fn session(&self) -> Cookie<'static> {
+            Cookie::new("Authorization", issue_token(self.user_id, 0, &EncodingKey::from_secret(b"test")).0)
+        }
+
+        fn second_factor(&self, expires: i64) -> Cookie<'static> {
+            Cookie::new(SECOND_FACTOR_COOKIE, format!("{}:{}", self.user_id, expires))
+        }
+
+        async fn post<T: Serialize>(&self, path: &str, body: &T, cookies: Vec<Cookie<'static>>) -> LocalResponse<'_> {
+            let mut request = self.client.post(format!("/api{}", path))
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(body).unwrap());
+            for cookie in cookies {
+                request = request.private_cookie(cookie);
+            }
+            request.dispatch().await
+        }
+
+        async fn register(&mut self, usage: CredentialUsage) -> Status {
+            let response = self.post("/webauthn/credentials/register/start", &RegistrationStartDto {
+                name: String::from("Soft key"),
+                usage,
+            }, vec![self.session()]).await;
+            assert_eq!(response.status(), Status::Ok);
+            let start: RegistrationStart = response.into_json().await.unwrap();
+
+            let credential = self.authenticator.do_registration(Url::parse(ORIGIN).unwrap(), start.options).unwrap();
+
+            self.post("/webauthn/credentials/register/finish", &RegistrationFinishDto {
+                ceremony_id: start.ceremony_id,
+                credential,
+            }, vec![self.session()]).await.status()
+        }
+
+        async fn sign(&mut self, start: AuthenticationStart) -> AuthenticationFinishDto {
+            let credential = self.authenticator.do_authentication(Url::parse(ORIGIN).unwrap(), start.options).unwrap();
+
+            AuthenticationFinishDto { ceremony_id: start.ceremony_id, credential }
+        }
+
+        async fn start_passkey_login(&self) -> LocalResponse<'_> {
+            self.post("/webauthn/login/start", &PasskeyLoginDto { username: self.username.clone() }, vec![]).await
+        }
+
+        async fn remove(&self) {
+            sqlx::query("DELETE FROM users WHERE id = $1").bind(self.user_id).execute(&self.db).await.unwrap();
+        }
+    }
+
+    #[rocket::async_test]
+    async fn passkey_registers_and_logs_in() {
+        let mut server = TestServer::new().await;
+        assert_eq!(server.register(CredentialUsage::Passkey).await, Status::Ok);
+
+        let start: AuthenticationStart = server.start_passkey_login().await.into_json().await.unwrap();
+        let finish = server.sign(start).await;
+        let response = server.post("/webauthn/login/finish", &finish, vec![]).await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.cookies().get_private("Authorization").is_some());
+
+        // Each challenge can be answered only once.
+        let replayed = server.post("/webauthn/login/finish", &finish, vec![]).await;
+        assert_eq!(replayed.status(), Status::BadRequest);
+
+        server.remove().await;
+    }
+
+    #[rocket::async_test]
+    async fn registration_requires_session() {
+        let server = TestServer::new().await;
+
+        let response = server.post("/webauthn/credentials/register/start", &RegistrationStartDto {
+            name: String::from("Soft key"),
+            usage: CredentialUsage::Passkey,
+        }, vec![]).await;
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        server.remove().await;
+    }
+
+    #[rocket::async_test]
+    async fn second_factor_key_is_not_a_passkey() {
+        let mut server = TestServer::new().await;
+        assert_eq!(server.register(CredentialUsage::SecondFactor).await, Status::Ok);
+
+        assert_eq!(server.start_passkey_login().await.status(), Status::Unauthorized);
+
+        server.remove().await;
+    }
+
+    #[rocket::async_test]
+    async fn second_factor_completes_login() {
+        let mut server = TestServer::new().await;
+        assert_eq!(server.register(CredentialUsage::SecondFactor).await, Status::Ok);
+
+        let expires = (chrono::Utc::now() + chrono::Duration::minutes(SECOND_FACTOR_MINUTES)).timestamp();
+        let response = server.post("/webauthn/second-factor/start", &(), vec![server.second_factor(expires)]).await;
+        assert_eq!(response.status(), Status::Ok);
+        let start: AuthenticationStart = response.into_json().await.unwrap();
+
+        let finish = server.sign(start).await;
+        let response = server.post("/webauthn/second-factor/finish", &finish, vec![server.second_factor(expires)]).await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.cookies().get_private("Authorization").is_some());
+
+        let sign_count = sqlx::query_scalar::<_, i64>("SELECT sign_count FROM webauthn_credentials WHERE user_id = $1")
+            .bind(server.user_id).fetch_one(&server.db).await.unwrap();
+        assert!(sign_count > 0);
+
+        server.remove().await;
+    }
+
+    #[rocket::async_test]
+    async fn second_factor_requires_password_first() {
+        let mut server = TestServer::new().await;
+        assert_eq!(server.register(CredentialUsage::SecondFactor).await, Status::Ok);
+
+        let response = server.post("/webauthn/second-factor/start", &(), vec![]).await;
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let expired = (chrono::Utc::now() - chrono::Duration::seconds(1)).timestamp();
+        let response = server.post("/webauthn/second-factor/start", &(), vec![server.second_factor(expired)]).await;
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        server.remove().await;
+    }
+
+    #[rocket::async_test]
+    async fn expired_ceremony_is_rejected() {
+        let mut server = TestServer::new().await;
+        assert_eq!(server.register(CredentialUsage::Passkey).await, Status::Ok);
+
+        let start: AuthenticationStart = server.start_passkey_login().await.into_json().await.unwrap();
+        sqlx::query("UPDATE webauthn_ceremonies SET created_at = now() - interval '10 minutes' WHERE id = $1")
+            .bind(&start.ceremony_id).execute(&server.db).await.unwrap();
+
+        let finish = server.sign(start).await;
+        let response = server.post("/webauthn/login/finish", &finish, vec![]).await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        server.remove().await;
+    }
+}
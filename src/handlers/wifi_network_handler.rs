@@ -0,0 +1,61 @@
+use rocket::{delete, get, post, put, State};
+use rocket::serde::json::Json;
+
+use crate::APIError;
+use crate::handlers::item_access::require_permission;
+use crate::handlers::item_handler::{create_item, delete_item, get_item, get_items, update_item};
+use crate::models::event_model::ClientInfo;
+use crate::models::item_model::ItemType;
+use crate::models::revision_model::{IfMatch, Tagged};
+use crate::models::share_model::Permission;
+use crate::models::user_model::User;
+use crate::models::validation_model::{field_error, Validated};
+use crate::models::wifi_network_model::{validate_wifi_password, WifiNetwork, WifiNetworkDto, WifiSecurity};
+use crate::persistence::attachment_dao::AttachmentDao;
+use crate::persistence::event_logger::EventLogger;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::push_hub::PushHub;
+use crate::persistence::share_dao::ShareDao;
+
+#[post("/wifi_networks", data = "<wifi_network>")]
+pub async fn create_wifi_network(user: User, wifi_network: Validated<WifiNetworkDto>, wifi_network_dao: &State<Box<dyn ItemDao<WifiNetwork> + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<WifiNetwork>>, APIError> {
+    require_wifi_password(wifi_network.security_type.unwrap_or_default(), wifi_network.password.as_deref().unwrap_or_default())?;
+
+    create_item(user, wifi_network.0, wifi_network_dao, client, events, push).await
+}
+
+#[get("/wifi_networks/<id>")]
+pub async fn get_wifi_network(user: User, id: i32, wifi_network_dao: &State<Box<dyn ItemDao<WifiNetwork> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>) -> Result<Tagged<Json<WifiNetwork>>, APIError> {
+    get_item(user, id, wifi_network_dao, share_dao, client, events).await
+}
+
+#[get("/wifi_networks")]
+pub async fn get_wifi_networks(user: User, wifi_network_dao: &State<Box<dyn ItemDao<WifiNetwork> + Sync + Send>>) -> Result<Json<Vec<WifiNetwork>>, APIError> {
+    get_items(user, wifi_network_dao).await
+}
+
+#[put("/wifi_networks/<id>", data = "<wifi_network>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_wifi_network(user: User, id: i32, wifi_network: Validated<WifiNetworkDto>, if_match: IfMatch, wifi_network_dao: &State<Box<dyn ItemDao<WifiNetwork> + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<Tagged<Json<WifiNetwork>>, APIError> {
+    if wifi_network.security_type.is_some() || wifi_network.password.is_some() {
+        require_permission(user.id, ItemType::WifiNetwork, id, Permission::Write, share_dao).await?;
+        let current = wifi_network_dao.get_item(id).await?;
+        let security_type = wifi_network.security_type.unwrap_or(current.security_type);
+        require_wifi_password(security_type, wifi_network.password.as_deref().unwrap_or(&current.password))?;
+    }
+
+    update_item(user, id, wifi_network.0, if_match, wifi_network_dao, share_dao, client, events, push).await
+}
+
+#[delete("/wifi_networks/<id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_wifi_network(user: User, id: i32, wifi_network_dao: &State<Box<dyn ItemDao<WifiNetwork> + Sync + Send>>, attachment_dao: &State<Box<dyn AttachmentDao + Sync + Send>>, share_dao: &State<Box<dyn ShareDao + Sync + Send>>, client: ClientInfo, events: &State<EventLogger>, push: &State<PushHub>) -> Result<(), APIError> {
+    delete_item(user, id, wifi_network_dao, attachment_dao, share_dao, client, events, push).await
+}
+
+/// The password has to fit the security type: the one sent along with it, or the stored one
+/// when only the password changes, and the other way round.
+fn require_wifi_password(security_type: WifiSecurity, password: &str) -> Result<(), APIError> {
+    validate_wifi_password(security_type, password)
+        .map_err(|err| APIError::Validation(vec![field_error("password", &err)]))
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use lettre::{AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use log::info;
+use thiserror::Error;
+
+pub use templates::EmailTemplate;
+
+mod templates;
+
+#[derive(Debug, Error)]
+pub enum MailError {
+    #[error("Invalid email address: {0}")]
+    InvalidAddress(String),
+    #[error("Failed to send email: {0}")]
+    Transport(String),
+}
+
+/// Where mail goes. SMTP for production and local mail catchers, `.eml` files in a directory
+/// or the log for development.
+enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    File(AsyncFileTransport<Tokio1Executor>),
+    Log,
+}
+
+/// Cheap to clone, clones share the transport and its connection pool.
+#[derive(Clone)]
+pub struct Mailer {
+    from: Mailbox,
+    transport: Arc<Transport>,
+    app_url: String,
+}
+
+impl Mailer {
+    /// Reads MAIL_TRANSPORT (`smtp`, `file` or `log`, the default) and its settings:
+    /// SMTP_HOST, SMTP_PORT, SMTP_TLS (`tls`, `starttls` or `none`), SMTP_USERNAME and
+    /// SMTP_PASSWORD for SMTP, MAIL_DIR for files. MAIL_FROM is the sender and APP_URL the
+    /// web vault links in emails point to.
+    pub fn from_env() -> Self {
+        let from = std::env::var("MAIL_FROM").unwrap_or(String::from("Lockdown <no-reply@localhost>"));
+        let from = from.parse().expect("MAIL_FROM must be a valid mailbox.");
+        let app_url = std::env::var("APP_URL").unwrap_or(String::from("https://localhost:4100"));
+
+        let transport = match std::env::var("MAIL_TRANSPORT").unwrap_or(String::from("log")).as_str() {
+            "smtp" => Transport::Smtp(smtp_from_env()),
+            "file" => {
+                let dir = std::env::var("MAIL_DIR").unwrap_or(String::from("mail"));
+                std::fs::create_dir_all(&dir).expect("Failed to create MAIL_DIR.");
+                Transport::File(AsyncFileTransport::<Tokio1Executor>::new(dir))
+            }
+            "log" => Transport::Log,
+            other => panic!("Unknown MAIL_TRANSPORT: {}", other),
+        };
+
+        Mailer { from, transport: Arc::new(transport), app_url: app_url.trim_end_matches('/').to_string() }
+    }
+
+    /// Absolute link into the web vault, e.g. `link("/reset-password", token)`.
+    pub fn link(&self, path: &str, token: &str) -> String {
+        format!("{}?token={}", self.url(path), token)
+    }
+
+    /// Absolute URL of a web vault page without a token.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.app_url, path)
+    }
+
+    pub async fn send(&self, to: &str, template: EmailTemplate, values: &[(&str, &str)]) -> Result<(), MailError> {
+        let (subject, body) = template.render(values);
+
+        if let Transport::Log = *self.transport {
+            info!("Email to {}: {}\n{}", to, subject, body);
+            return Ok(());
+        }
+
+        let to: Mailbox = to.parse().map_err(|_| MailError::InvalidAddress(to.to_string()))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|err| MailError::Transport(err.to_string()))?;
+
+        match &*self.transport {
+            Transport::Smtp(smtp) => smtp.send(message).await
+                .map(|_| ())
+                .map_err(|err| MailError::Transport(err.to_string())),
+            Transport::File(file) => file.send(message).await
+                .map(|_| ())
+                .map_err(|err| MailError::Transport(err.to_string())),
+            Transport::Log => Ok(()),
+        }
+    }
+}
+
+fn smtp_from_env() -> AsyncSmtpTransport<Tokio1Executor> {
+    let host = std::env::var("SMTP_HOST").expect("SMTP_HOST must be set for MAIL_TRANSPORT=smtp.");
+    let tls = std::env::var("SMTP_TLS").unwrap_or(String::from("starttls"));
+
+    let builder = match tls.as_str() {
+        "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host),
+        "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host),
+        // Plain connections, for mail catchers on the local machine.
+        "none" => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host)),
+        other => panic!("Unknown SMTP_TLS: {}", other),
+    }.expect("Invalid SMTP_HOST.");
+
+    let builder = match std::env::var("SMTP_PORT").ok() {
+        Some(port) => builder.port(port.parse().expect("SMTP_PORT must be a port number.")),
+        None => builder,
+    };
+
+    match (std::env::var("SMTP_USERNAME").ok(), std::env::var("SMTP_PASSWORD").ok()) {
+        (Some(username), Some(password)) => builder.credentials(Credentials::new(username, password)).build(),
+        _ => builder.build(),
+    }
+}
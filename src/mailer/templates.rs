@@ -0,0 +1,37 @@
+/// Plain text email templates. The first line of a template is the subject, the rest after the
+/// blank line the body. `{{name}}` placeholders are replaced when rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    VerifyEmail,
+    ChangeEmail,
+    EmailChanged,
+    PasswordReset,
+    Notification,
+    EmergencyAccessRequested,
+}
+
+impl EmailTemplate {
+    fn source(&self) -> &'static str {
+        match self {
+            EmailTemplate::VerifyEmail => include_str!("templates/verify_email.txt"),
+            EmailTemplate::ChangeEmail => include_str!("templates/change_email.txt"),
+            EmailTemplate::EmailChanged => include_str!("templates/email_changed.txt"),
+            EmailTemplate::PasswordReset => include_str!("templates/password_reset.txt"),
+            EmailTemplate::Notification => include_str!("templates/notification.txt"),
+            EmailTemplate::EmergencyAccessRequested => include_str!("templates/emergency_access_requested.txt"),
+        }
+    }
+
+    /// Returns the subject and body with `values` filled in.
+    pub fn render(&self, values: &[(&str, &str)]) -> (String, String) {
+        let mut text = self.source().to_string();
+        for (name, value) in values {
+            text = text.replace(&format!("{{{{{}}}}}", name), value);
+        }
+
+        match text.split_once("\n\n") {
+            Some((subject, body)) => (subject.trim().to_string(), body.to_string()),
+            None => (text.trim().to_string(), String::new()),
+        }
+    }
+}
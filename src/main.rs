@@ -4,20 +4,58 @@ use dotenvy::dotenv;
 use jsonwebtoken::{DecodingKey, EncodingKey};
 use rocket::launch;
 use sqlx::postgres::PgPoolOptions;
+use webauthn_rs::prelude::Url;
+use webauthn_rs::WebauthnBuilder;
 
 pub use handlers::*;
 
 use crate::cors::CORS;
+use crate::mailer::Mailer;
+use crate::models::api_credential_model::ApiCredential;
+use crate::models::identity_model::Identity;
+use crate::models::password_policy_model::PasswordPolicy;
+use crate::models::software_license_model::SoftwareLicense;
+use crate::models::ssh_key_model::SshKey;
+use crate::models::storage_model::AttachmentPolicy;
+use crate::models::wifi_network_model::WifiNetwork;
+use crate::persistence::attachment_dao::{AttachmentDao, AttachmentDaoImpl};
 use crate::persistence::auth_dao::{AuthDao, AuthDaoImpl};
+use crate::persistence::emergency_access_dao::{EmergencyAccessDao, EmergencyAccessDaoImpl};
+use crate::persistence::event_chain::CheckpointSigner;
+use crate::persistence::event_dao::{EventDao, EventDaoImpl};
+use crate::persistence::event_logger::EventLogger;
 use crate::persistence::login_dao::{LoginDao, LoginDaoImpl};
+use crate::persistence::login_passkey_dao::{LoginPasskeyDao, LoginPasskeyDaoImpl};
+use crate::persistence::custom_field_dao::{CustomFieldDao, CustomFieldDaoImpl};
+use crate::persistence::identity_dao::IdentityDaoImpl;
+use crate::persistence::item_dao::ItemDao;
+use crate::persistence::ssh_key_dao::SshKeyDaoImpl;
+use crate::persistence::api_credential_dao::ApiCredentialDaoImpl;
+use crate::persistence::wifi_network_dao::WifiNetworkDaoImpl;
+use crate::persistence::software_license_dao::SoftwareLicenseDaoImpl;
+use crate::persistence::equivalent_domains_dao::{EquivalentDomainsDao, EquivalentDomainsDaoImpl};
+use crate::persistence::notification_dao::{NotificationDao, NotificationDaoImpl};
+use crate::persistence::organization_dao::{OrganizationDao, OrganizationDaoImpl};
 use crate::persistence::payment_dao::{PaymentDao, PaymentDaoImpl};
+use crate::persistence::push_hub::PushHub;
 use crate::persistence::secured_note_dao::{SecuredNoteDao, SecuredNoteDaoImpl};
+use crate::persistence::send_dao::{SendDao, SendDaoImpl};
+use crate::persistence::share_dao::{ShareDao, ShareDaoImpl};
+use crate::persistence::storage_dao::{StorageDao, StorageDaoImpl};
+use crate::persistence::sync_dao::{SyncDao, SyncDaoImpl};
+use crate::persistence::user_token_dao::{UserTokenDao, UserTokenDaoImpl};
 use crate::persistence::users_dao::{UsersDao, UsersDaoImpl};
+use crate::persistence::webauthn_dao::{WebauthnDao, WebauthnDaoImpl};
+use crate::scheduler::Scheduler;
+use crate::tasks::{EmergencyAccessJob, EventCheckpointJob, EventRetentionJob, ReminderJob, SendCleanupJob};
 
 mod cors;
+mod mailer;
 mod models;
 mod handlers;
 mod persistence;
+mod scheduler;
+mod tasks;
 
 #[launch]
 async fn rocket() -> _ {
@@ -36,22 +74,115 @@ async fn rocket() -> _ {
         .expect("Failed to create Postgres connection pool!");
 
     let users_dao = UsersDaoImpl::new(pool.clone());
+    let user_token_dao = UserTokenDaoImpl::new(pool.clone());
     let auth_dao = AuthDaoImpl::new(pool.clone());
     let login_dao = LoginDaoImpl::new(pool.clone());
+    let login_passkey_dao = LoginPasskeyDaoImpl::new(pool.clone());
     let payment_dao = PaymentDaoImpl::new(pool.clone());
     let secured_note = SecuredNoteDaoImpl::new(pool.clone());
+    let storage_dao = StorageDaoImpl::new(pool.clone());
+    let attachment_dao = AttachmentDaoImpl::new(pool.clone());
+    let send_dao = SendDaoImpl::new(pool.clone());
+    let share_dao = ShareDaoImpl::new(pool.clone());
+    let organization_dao = OrganizationDaoImpl::new(pool.clone());
+    let emergency_access_dao = EmergencyAccessDaoImpl::new(pool.clone());
+    let webauthn_dao = WebauthnDaoImpl::new(pool.clone());
+    let notification_dao = NotificationDaoImpl::new(pool.clone());
+    let sync_dao = SyncDaoImpl::new(pool.clone());
+    let equivalent_domains_dao = EquivalentDomainsDaoImpl::new(pool.clone());
+    let custom_field_dao = CustomFieldDaoImpl::new(pool.clone());
+    let identity_dao = IdentityDaoImpl::new(pool.clone());
+    let ssh_key_dao = SshKeyDaoImpl::new(pool.clone());
+    let api_credential_dao = ApiCredentialDaoImpl::new(pool.clone());
+    let wifi_network_dao = WifiNetworkDaoImpl::new(pool.clone());
+    let software_license_dao = SoftwareLicenseDaoImpl::new(pool.clone());
+
+    // The relying party id is the domain the web vault is served from, the origin its full URL.
+    let rp_id = std::env::var("WEBAUTHN_RP_ID").unwrap_or(String::from("localhost"));
+    let rp_origin = std::env::var("WEBAUTHN_ORIGIN").unwrap_or(String::from("https://localhost:4100"));
+    let rp_origin = Url::parse(&rp_origin).expect("WEBAUTHN_ORIGIN must be a valid URL.");
+    let webauthn = WebauthnBuilder::new(&rp_id, &rp_origin)
+        .and_then(|builder| builder.rp_name("Lockdown").build())
+        .expect("Invalid WebAuthn relying party configuration.");
+
+    let event_dao = EventDaoImpl::new(pool.clone());
+    let event_logger = EventLogger::spawn(Box::new(EventDaoImpl::new(pool.clone())));
+    let push_hub = PushHub::from_env(pool.clone()).await;
+
+    let daily_at = scheduler::daily_at_from_env();
+    let mut scheduler = Scheduler::default()
+        .every(tasks::EMERGENCY_ACCESS_CHECK_INTERVAL, EmergencyAccessJob {
+            emergency_access_dao: Box::new(EmergencyAccessDaoImpl::new(pool.clone())),
+        })
+        .every(tasks::SEND_CLEANUP_INTERVAL, SendCleanupJob {
+            send_dao: Box::new(SendDaoImpl::new(pool.clone())),
+        })
+        .daily(daily_at, ReminderJob {
+            notification_dao: Box::new(NotificationDaoImpl::new(pool.clone())),
+            mailer: Mailer::from_env(),
+        });
+
+    // Audit events are kept for a year unless EVENT_RETENTION_DAYS says otherwise; 0 keeps them forever.
+    let event_retention_days = std::env::var("EVENT_RETENTION_DAYS").ok()
+        .map(|days| days.parse::<i32>().expect("EVENT_RETENTION_DAYS must be a number of days."))
+        .unwrap_or(365);
+    if event_retention_days > 0 {
+        scheduler = scheduler.daily(daily_at, EventRetentionJob {
+            event_dao: Box::new(EventDaoImpl::new(pool.clone())),
+            retention_days: event_retention_days,
+        });
+    }
+
+    let checkpoint_signer = CheckpointSigner::from_env();
+    if checkpoint_signer.enabled() {
+        scheduler = scheduler.every(
+            tasks::EVENT_CHECKPOINT_INTERVAL,
+            EventCheckpointJob::new(Box::new(EventDaoImpl::new(pool.clone())), checkpoint_signer.clone()),
+        );
+    } else {
+        log::warn!("EVENT_SIGNING_KEY not set, audit log checkpoints are disabled.");
+    }
+
+    scheduler.start();
 
     rocket::build()
         .mount(
             "/api",
             app_routes(),
         )
+        .register("/", app_catchers())
         .attach(CORS)
         .manage(Box::new(users_dao) as Box<dyn UsersDao + Send + Sync>)
+        .manage(Box::new(user_token_dao) as Box<dyn UserTokenDao + Send + Sync>)
         .manage(Box::new(auth_dao) as Box<dyn AuthDao + Send + Sync>)
         .manage(Box::new(login_dao) as Box<dyn LoginDao + Send + Sync>)
+        .manage(Box::new(login_passkey_dao) as Box<dyn LoginPasskeyDao + Send + Sync>)
         .manage(Box::new(payment_dao) as Box<dyn PaymentDao + Send + Sync>)
         .manage(Box::new(secured_note) as Box<dyn SecuredNoteDao + Send + Sync>)
+        .manage(Box::new(storage_dao) as Box<dyn StorageDao + Send + Sync>)
+        .manage(Box::new(attachment_dao) as Box<dyn AttachmentDao + Send + Sync>)
+        .manage(Box::new(send_dao) as Box<dyn SendDao + Send + Sync>)
+        .manage(Box::new(share_dao) as Box<dyn ShareDao + Send + Sync>)
+        .manage(Box::new(organization_dao) as Box<dyn OrganizationDao + Send + Sync>)
+        .manage(Box::new(emergency_access_dao) as Box<dyn EmergencyAccessDao + Send + Sync>)
+        .manage(Box::new(event_dao) as Box<dyn EventDao + Send + Sync>)
+        .manage(Box::new(webauthn_dao) as Box<dyn WebauthnDao + Send + Sync>)
+        .manage(Box::new(notification_dao) as Box<dyn NotificationDao + Send + Sync>)
+        .manage(Box::new(sync_dao) as Box<dyn SyncDao + Send + Sync>)
+        .manage(Box::new(equivalent_domains_dao) as Box<dyn EquivalentDomainsDao + Send + Sync>)
+        .manage(Box::new(custom_field_dao) as Box<dyn CustomFieldDao + Send + Sync>)
+        .manage(Box::new(identity_dao) as Box<dyn ItemDao<Identity> + Send + Sync>)
+        .manage(Box::new(ssh_key_dao) as Box<dyn ItemDao<SshKey> + Send + Sync>)
+        .manage(Box::new(api_credential_dao) as Box<dyn ItemDao<ApiCredential> + Send + Sync>)
+        .manage(Box::new(wifi_network_dao) as Box<dyn ItemDao<WifiNetwork> + Send + Sync>)
+        .manage(Box::new(software_license_dao) as Box<dyn ItemDao<SoftwareLicense> + Send + Sync>)
+        .manage(webauthn)
+        .manage(event_logger)
+        .manage(push_hub)
+        .manage(checkpoint_signer)
+        .manage(AttachmentPolicy::from_env())
+        .manage(PasswordPolicy::from_env())
+        .manage(Mailer::from_env())
         .manage(jwt_encoding_key)
         .manage(jwt_decoding_key)
 }
@@ -0,0 +1,112 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use chrono::NaiveDate;
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::{Validate, ValidationError};
+
+use crate::models::custom_field_model::{blank_hidden_fields, CustomField, CustomFieldDto, MAX_CUSTOM_FIELDS, validate_unlinked_fields};
+use crate::models::item_model::{ItemType, VaultItem};
+use crate::models::share_model::Permission;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// Longest API secret accepted. Some are whole JSON service account files.
+pub const MAX_API_SECRET_LENGTH: u64 = 16_384;
+
+/// A key and secret for a service's API, e.g. an access key pair or a bearer token.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct ApiCredential {
+    pub id: i32,
+    pub name: String,
+    pub api_key: String,
+    pub secret: String,
+    pub endpoint: String,
+    pub expires_on: Option<String>,
+    pub note: String,
+    pub color: String,
+    pub created_at: String,
+    pub modified_at: String,
+    pub custom_fields: Vec<CustomField>,
+    /// Bumped by every update, sent as the `ETag`.
+    pub revision: i64,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub permission: Permission,
+}
+
+#[derive(Debug, Clone, Error, Serialize, Deserialize, Validate)]
+pub struct ApiCredentialDto {
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub name: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub api_key: Option<String>,
+    #[validate(length(max = "MAX_API_SECRET_LENGTH"))]
+    pub secret: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"), custom = "validate_endpoint")]
+    pub endpoint: Option<String>,
+    /// `null` clears the date, leaving it out keeps it.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub expires_on: Option<Option<NaiveDate>>,
+    pub note: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub color: Option<String>,
+    /// Replaces all custom fields when sent.
+    #[validate(length(max = "MAX_CUSTOM_FIELDS"), custom = "validate_unlinked_fields")]
+    #[validate]
+    pub custom_fields: Option<Vec<CustomFieldDto>>,
+}
+
+/// The API's base URL, or nothing at all.
+fn validate_endpoint(endpoint: &str) -> Result<(), ValidationError> {
+    if !endpoint.is_empty() && !validator::validate_url(endpoint) {
+        return Err(ValidationError::new("url"));
+    }
+
+    Ok(())
+}
+
+impl VaultItem for ApiCredential {
+    type Dto = ApiCredentialDto;
+
+    const ITEM_TYPE: ItemType = ItemType::ApiCredential;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    fn permission(&self) -> Permission {
+        self.permission
+    }
+
+    fn set_permission(&mut self, permission: Permission) {
+        self.shared = permission != Permission::Owner;
+        self.permission = permission;
+    }
+
+    fn custom_fields_mut(&mut self) -> &mut Vec<CustomField> {
+        &mut self.custom_fields
+    }
+
+    /// Blanks the secret and hidden custom fields for members with hide-passwords access.
+    fn hide_secrets(&mut self) {
+        self.secret = String::new();
+        blank_hidden_fields(&mut self.custom_fields);
+    }
+}
+
+impl Display for ApiCredential {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for ApiCredentialDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::item_model::ItemType;
+
+#[derive(Debug, Error, Deserialize, Serialize)]
+pub struct File {
+    pub id: i32,
+    pub name: String,
+    pub owner_id: i32,
+    pub item_type: ItemType,
+    pub item_id: i32,
+    pub size: i32,
+    pub created_at: String,
+    pub file_type: String,
+    pub checksum: Option<String>,
+}
+
+#[derive(Debug, Error, Deserialize, Serialize)]
+pub struct FileDto {
+    pub name: String,
+    pub size: i32,
+    pub file_type: String,
+    pub checksum: String,
+}
+
+impl Display for File {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+
+impl Display for FileDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
@@ -13,4 +13,17 @@ pub struct Credentials {
 pub struct TokenClaims {
     pub sub: i32,
     pub exp: usize,
+    /// Issued at. Tokens from before it was added count as issued at 0. Informational only;
+    /// revocation goes by `gen`.
+    #[serde(default)]
+    pub iat: i64,
+    /// The user's token generation when issued. Tokens from before it was added count as 0.
+    #[serde(default)]
+    pub gen: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasswordChangeDto {
+    pub current_password: String,
+    pub new_password: String,
 }
@@ -0,0 +1,174 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::{Validate, ValidationError};
+
+use crate::models::item_model::ItemType;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// Most custom fields one item can have.
+pub const MAX_CUSTOM_FIELDS: u64 = 50;
+/// Longest value of a custom field.
+pub const MAX_CUSTOM_FIELD_VALUE_LENGTH: u64 = 10_000;
+/// What hidden values are replaced with in list responses. Hidden fields sent back with this
+/// value keep the value they have.
+pub const MASKED_VALUE: &str = "********";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldType {
+    Text,
+    /// A secret, e.g. a PIN or the answer to a security question. Masked in lists.
+    Hidden,
+    /// `true` or `false`.
+    Boolean,
+    /// Has no value of its own, it stands for the login's username or password, e.g. for
+    /// forms that name those fields differently.
+    Linked,
+}
+
+impl CustomFieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CustomFieldType::Text => "text",
+            CustomFieldType::Hidden => "hidden",
+            CustomFieldType::Boolean => "boolean",
+            CustomFieldType::Linked => "linked",
+        }
+    }
+}
+
+impl FromStr for CustomFieldType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(CustomFieldType::Text),
+            "hidden" => Ok(CustomFieldType::Hidden),
+            "boolean" => Ok(CustomFieldType::Boolean),
+            "linked" => Ok(CustomFieldType::Linked),
+            _ => Err(format!("Unknown custom field type: {}", s)),
+        }
+    }
+}
+
+/// The login field a linked custom field stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkedField {
+    Username,
+    Password,
+}
+
+impl LinkedField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkedField::Username => "username",
+            LinkedField::Password => "password",
+        }
+    }
+}
+
+impl FromStr for LinkedField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "username" => Ok(LinkedField::Username),
+            "password" => Ok(LinkedField::Password),
+            _ => Err(format!("Unknown linked field: {}", s)),
+        }
+    }
+}
+
+/// A user defined field of an item. Fields are listed by `position`.
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+    pub field_type: CustomFieldType,
+    pub linked_to: Option<LinkedField>,
+    pub position: i32,
+}
+
+/// A custom field as sent by clients. Items get their fields in the order they are sent.
+#[derive(Debug, Clone, Error, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_custom_field"))]
+pub struct CustomFieldDto {
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub name: String,
+    #[serde(default)]
+    #[validate(length(max = "MAX_CUSTOM_FIELD_VALUE_LENGTH"))]
+    pub value: String,
+    pub field_type: CustomFieldType,
+    pub linked_to: Option<LinkedField>,
+}
+
+/// A custom field whose name matched a search, with the item it belongs to.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct CustomFieldMatch {
+    pub item_type: ItemType,
+    pub item_id: i32,
+    pub name: String,
+    pub field_type: CustomFieldType,
+}
+
+/// Replaces the values of hidden fields with `MASKED_VALUE`.
+pub fn mask_hidden_fields(fields: &mut [CustomField]) {
+    fields.iter_mut()
+        .filter(|field| field.field_type == CustomFieldType::Hidden)
+        .for_each(|field| field.value = MASKED_VALUE.to_string());
+}
+
+/// Blanks the values of hidden fields, for members with hide-passwords access.
+pub fn blank_hidden_fields(fields: &mut [CustomField]) {
+    fields.iter_mut()
+        .filter(|field| field.field_type == CustomFieldType::Hidden)
+        .for_each(|field| field.value = String::new());
+}
+
+fn validate_custom_field(field: &CustomFieldDto) -> Result<(), ValidationError> {
+    let message = match field.field_type {
+        CustomFieldType::Linked if field.linked_to.is_none() => "Linked fields need the field they link to.",
+        CustomFieldType::Linked if !field.value.is_empty() => "Linked fields have no value of their own.",
+        CustomFieldType::Linked => return Ok(()),
+        _ if field.linked_to.is_some() => "Only linked fields link to another field.",
+        CustomFieldType::Boolean if field.value != "true" && field.value != "false" => "Boolean fields are either true or false.",
+        _ => return Ok(()),
+    };
+
+    let mut err = ValidationError::new("custom_field");
+    err.message = Some(message.into());
+    Err(err)
+}
+
+/// Linked fields stand for a username or password, only logins have those.
+pub fn validate_unlinked_fields(fields: &[CustomFieldDto]) -> Result<(), ValidationError> {
+    if fields.iter().any(|field| field.field_type == CustomFieldType::Linked) {
+        let mut err = ValidationError::new("custom_field");
+        err.message = Some("Only logins have linked fields.".into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+impl Display for CustomField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for CustomFieldDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for CustomFieldMatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
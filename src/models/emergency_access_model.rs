@@ -0,0 +1,217 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+use crate::models::api_credential_model::ApiCredential;
+use crate::models::identity_model::Identity;
+use crate::models::login_model::Login;
+use crate::models::payment_model::Payment;
+use crate::models::secured_note::SecuredNote;
+use crate::models::software_license_model::SoftwareLicense;
+use crate::models::ssh_key_model::SshKey;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+use crate::models::wifi_network_model::WifiNetwork;
+
+/// Longest waiting period a grantor can set before a recovery is approved automatically.
+pub const MAX_WAIT_DAYS: i32 = 90;
+
+/// What a trusted contact gets once a recovery is approved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmergencyAccessType {
+    View,
+    Takeover,
+}
+
+impl EmergencyAccessType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmergencyAccessType::View => "view",
+            EmergencyAccessType::Takeover => "takeover",
+        }
+    }
+}
+
+impl FromStr for EmergencyAccessType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "view" => Ok(EmergencyAccessType::View),
+            "takeover" => Ok(EmergencyAccessType::Takeover),
+            _ => Err(format!("Unknown emergency access type: {}", s)),
+        }
+    }
+}
+
+/// States of an emergency access grant. See `EmergencyAccessStatus::apply` for the transitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmergencyAccessStatus {
+    Invited,
+    Accepted,
+    RecoveryInitiated,
+    RecoveryApproved,
+}
+
+/// Something that happens to a grant. `Approve` comes from the grantor or from the timer once
+/// the waiting period elapsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmergencyAccessEvent {
+    Accept,
+    Initiate,
+    Approve,
+    Reject,
+}
+
+impl EmergencyAccessStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmergencyAccessStatus::Invited => "invited",
+            EmergencyAccessStatus::Accepted => "accepted",
+            EmergencyAccessStatus::RecoveryInitiated => "recovery_initiated",
+            EmergencyAccessStatus::RecoveryApproved => "recovery_approved",
+        }
+    }
+
+    /// The state after `event`, or `None` if the event isn't allowed in this state.
+    pub fn apply(&self, event: EmergencyAccessEvent) -> Option<EmergencyAccessStatus> {
+        use EmergencyAccessEvent::*;
+        use EmergencyAccessStatus::*;
+
+        match (self, event) {
+            (Invited, Accept) => Some(Accepted),
+            (Accepted, Initiate) => Some(RecoveryInitiated),
+            (RecoveryInitiated, Approve) => Some(RecoveryApproved),
+            (RecoveryInitiated | RecoveryApproved, Reject) => Some(Accepted),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for EmergencyAccessStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "invited" => Ok(EmergencyAccessStatus::Invited),
+            "accepted" => Ok(EmergencyAccessStatus::Accepted),
+            "recovery_initiated" => Ok(EmergencyAccessStatus::RecoveryInitiated),
+            "recovery_approved" => Ok(EmergencyAccessStatus::RecoveryApproved),
+            _ => Err(format!("Unknown emergency access status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct EmergencyAccessDto {
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub username: String,
+    pub access_type: EmergencyAccessType,
+    #[validate(range(min = 1, max = "MAX_WAIT_DAYS"))]
+    pub wait_days: i32,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct EmergencyAccess {
+    pub id: i32,
+    pub grantor_id: i32,
+    pub grantor_username: String,
+    pub grantee_id: i32,
+    pub grantee_username: String,
+    pub access_type: EmergencyAccessType,
+    pub wait_days: i32,
+    pub status: EmergencyAccessStatus,
+    pub recovery_initiated_at: Option<String>,
+    pub created_at: String,
+}
+
+/// The grantor's own items, as seen by a contact with approved access.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct EmergencyVault {
+    pub logins: Vec<Login>,
+    pub payments: Vec<Payment>,
+    pub secured_notes: Vec<SecuredNote>,
+    pub identities: Vec<Identity>,
+    pub ssh_keys: Vec<SshKey>,
+    pub api_credentials: Vec<ApiCredential>,
+    pub wifi_networks: Vec<WifiNetwork>,
+    pub software_licenses: Vec<SoftwareLicense>,
+}
+
+#[derive(Error, Serialize, Deserialize)]
+pub struct TakeoverDto {
+    pub new_password: String,
+}
+
+impl Display for EmergencyAccessDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for EmergencyAccess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for EmergencyVault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+/// Keeps the new password out of logs.
+impl Debug for TakeoverDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TakeoverDto").field("new_password", &"<redacted>").finish()
+    }
+}
+
+impl Display for TakeoverDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EmergencyAccessEvent::*;
+    use super::EmergencyAccessStatus::*;
+
+    #[test]
+    fn recovery_runs_from_invitation_to_approval() {
+        assert_eq!(Invited.apply(Accept), Some(Accepted));
+        assert_eq!(Accepted.apply(Initiate), Some(RecoveryInitiated));
+        assert_eq!(RecoveryInitiated.apply(Approve), Some(RecoveryApproved));
+    }
+
+    #[test]
+    fn rejecting_a_recovery_returns_to_accepted() {
+        assert_eq!(RecoveryInitiated.apply(Reject), Some(Accepted));
+        assert_eq!(RecoveryApproved.apply(Reject), Some(Accepted));
+    }
+
+    #[test]
+    fn other_transitions_are_refused() {
+        let allowed = [
+            (Invited, Accept),
+            (Accepted, Initiate),
+            (RecoveryInitiated, Approve),
+            (RecoveryInitiated, Reject),
+            (RecoveryApproved, Reject),
+        ];
+
+        for status in [Invited, Accepted, RecoveryInitiated, RecoveryApproved] {
+            for event in [Accept, Initiate, Approve, Reject] {
+                if !allowed.contains(&(status, event)) {
+                    assert_eq!(status.apply(event), None, "{:?} + {:?}", status, event);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use rocket::http::Status;
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::revision_model::EditConflict;
+
+/// Machine readable error codes. Clients branch on these rather than on messages, so existing
+/// codes must not change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    PasswordPolicy,
+    Unauthorized,
+    InvalidCredentials,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PreconditionFailed,
+    ValidationFailed,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    TooManyRequests,
+    InternalError,
+}
+
+impl ErrorCode {
+    /// The code for errors Rocket raises itself, e.g. for unknown routes or failing guards.
+    pub fn from_status(status: Status) -> Self {
+        match status.code {
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            409 => ErrorCode::Conflict,
+            412 => ErrorCode::PreconditionFailed,
+            413 => ErrorCode::PayloadTooLarge,
+            415 => ErrorCode::UnsupportedMediaType,
+            422 => ErrorCode::ValidationFailed,
+            429 => ErrorCode::TooManyRequests,
+            code if code >= 500 => ErrorCode::InternalError,
+            _ => ErrorCode::BadRequest,
+        }
+    }
+}
+
+/// A problem with one field of the request body.
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<EditConflict>,
+}
+
+/// The body of every error response: `{"error": {"code": ..., "message": ..., "fields": [...]}}`.
+/// Refused updates of items that changed in the meantime carry a `conflict` as well.
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ErrorResponse { error: ErrorBody { code, message: message.into(), fields: vec![], conflict: None } }
+    }
+
+    pub fn fields(mut self, fields: Vec<FieldError>) -> Self {
+        self.error.fields = fields;
+        self
+    }
+
+    pub fn conflict(mut self, conflict: EditConflict) -> Self {
+        self.error.conflict = Some(conflict);
+        self
+    }
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for ErrorBody {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for ErrorResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
@@ -0,0 +1,327 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::{FromForm, Request};
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::item_model::ItemType;
+
+pub const DEFAULT_EVENTS_PER_PAGE: i64 = 50;
+pub const MAX_EVENTS_PER_PAGE: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    LoginSucceeded,
+    LoginFailed,
+    Logout,
+    ItemCreated,
+    ItemViewed,
+    ItemUpdated,
+    ItemDeleted,
+    AttachmentUploaded,
+    AttachmentDownloaded,
+    AttachmentDeleted,
+    ShareCreated,
+    ShareDeleted,
+    EmergencyVaultViewed,
+    EmergencyTakeover,
+    EmailChanged,
+    PasswordReset,
+    PasswordChanged,
+    PaymentRevealed,
+    SendCreated,
+    SendAccessed,
+    SendDeleted,
+    OrganizationCreated,
+    OrganizationDeleted,
+    MemberInvited,
+    MemberJoined,
+    MemberRoleChanged,
+    MemberRemoved,
+    CollectionCreated,
+    CollectionDeleted,
+    CollectionRotationChanged,
+    CollectionAccessChanged,
+    CollectionItemAdded,
+    CollectionItemRemoved,
+    PasswordPolicyChanged,
+    ProfileUpdated,
+    AccountDeleted,
+    AttachmentsRepaired,
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::LoginSucceeded => "login_succeeded",
+            EventType::LoginFailed => "login_failed",
+            EventType::Logout => "logout",
+            EventType::ItemCreated => "item_created",
+            EventType::ItemViewed => "item_viewed",
+            EventType::ItemUpdated => "item_updated",
+            EventType::ItemDeleted => "item_deleted",
+            EventType::AttachmentUploaded => "attachment_uploaded",
+            EventType::AttachmentDownloaded => "attachment_downloaded",
+            EventType::AttachmentDeleted => "attachment_deleted",
+            EventType::ShareCreated => "share_created",
+            EventType::ShareDeleted => "share_deleted",
+            EventType::EmergencyVaultViewed => "emergency_vault_viewed",
+            EventType::EmergencyTakeover => "emergency_takeover",
+            EventType::EmailChanged => "email_changed",
+            EventType::PasswordReset => "password_reset",
+            EventType::PasswordChanged => "password_changed",
+            EventType::PaymentRevealed => "payment_revealed",
+            EventType::SendCreated => "send_created",
+            EventType::SendAccessed => "send_accessed",
+            EventType::SendDeleted => "send_deleted",
+            EventType::OrganizationCreated => "organization_created",
+            EventType::OrganizationDeleted => "organization_deleted",
+            EventType::MemberInvited => "member_invited",
+            EventType::MemberJoined => "member_joined",
+            EventType::MemberRoleChanged => "member_role_changed",
+            EventType::MemberRemoved => "member_removed",
+            EventType::CollectionCreated => "collection_created",
+            EventType::CollectionDeleted => "collection_deleted",
+            EventType::CollectionRotationChanged => "collection_rotation_changed",
+            EventType::CollectionAccessChanged => "collection_access_changed",
+            EventType::CollectionItemAdded => "collection_item_added",
+            EventType::CollectionItemRemoved => "collection_item_removed",
+            EventType::PasswordPolicyChanged => "password_policy_changed",
+            EventType::ProfileUpdated => "profile_updated",
+            EventType::AccountDeleted => "account_deleted",
+            EventType::AttachmentsRepaired => "attachments_repaired",
+        }
+    }
+}
+
+impl FromStr for EventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "login_succeeded" => Ok(EventType::LoginSucceeded),
+            "login_failed" => Ok(EventType::LoginFailed),
+            "logout" => Ok(EventType::Logout),
+            "item_created" => Ok(EventType::ItemCreated),
+            "item_viewed" => Ok(EventType::ItemViewed),
+            "item_updated" => Ok(EventType::ItemUpdated),
+            "item_deleted" => Ok(EventType::ItemDeleted),
+            "attachment_uploaded" => Ok(EventType::AttachmentUploaded),
+            "attachment_downloaded" => Ok(EventType::AttachmentDownloaded),
+            "attachment_deleted" => Ok(EventType::AttachmentDeleted),
+            "share_created" => Ok(EventType::ShareCreated),
+            "share_deleted" => Ok(EventType::ShareDeleted),
+            "emergency_vault_viewed" => Ok(EventType::EmergencyVaultViewed),
+            "emergency_takeover" => Ok(EventType::EmergencyTakeover),
+            "email_changed" => Ok(EventType::EmailChanged),
+            "password_reset" => Ok(EventType::PasswordReset),
+            "password_changed" => Ok(EventType::PasswordChanged),
+            "payment_revealed" => Ok(EventType::PaymentRevealed),
+            "send_created" => Ok(EventType::SendCreated),
+            "send_accessed" => Ok(EventType::SendAccessed),
+            "send_deleted" => Ok(EventType::SendDeleted),
+            "organization_created" => Ok(EventType::OrganizationCreated),
+            "organization_deleted" => Ok(EventType::OrganizationDeleted),
+            "member_invited" => Ok(EventType::MemberInvited),
+            "member_joined" => Ok(EventType::MemberJoined),
+            "member_role_changed" => Ok(EventType::MemberRoleChanged),
+            "member_removed" => Ok(EventType::MemberRemoved),
+            "collection_created" => Ok(EventType::CollectionCreated),
+            "collection_deleted" => Ok(EventType::CollectionDeleted),
+            "collection_rotation_changed" => Ok(EventType::CollectionRotationChanged),
+            "collection_access_changed" => Ok(EventType::CollectionAccessChanged),
+            "collection_item_added" => Ok(EventType::CollectionItemAdded),
+            "collection_item_removed" => Ok(EventType::CollectionItemRemoved),
+            "password_policy_changed" => Ok(EventType::PasswordPolicyChanged),
+            "profile_updated" => Ok(EventType::ProfileUpdated),
+            "account_deleted" => Ok(EventType::AccountDeleted),
+            "attachments_repaired" => Ok(EventType::AttachmentsRepaired),
+            _ => Err(format!("Unknown event type: {}", s)),
+        }
+    }
+}
+
+/// Where a request came from. Never fails; missing values are recorded as null.
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientInfo {
+    type Error = ();
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ClientInfo {
+            ip_address: request.client_ip().map(|ip| ip.to_string()),
+            user_agent: request.headers().get_one("User-Agent").map(String::from),
+        })
+    }
+}
+
+/// An event on its way to the `events` table.
+#[derive(Debug, Clone)]
+pub struct NewEvent {
+    pub event_type: EventType,
+    pub actor_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub item_type: Option<ItemType>,
+    pub item_id: Option<i32>,
+    pub details: Option<String>,
+}
+
+impl NewEvent {
+    pub fn new(event_type: EventType, actor_id: Option<i32>, client: &ClientInfo) -> Self {
+        NewEvent {
+            event_type,
+            actor_id,
+            ip_address: client.ip_address.clone(),
+            user_agent: client.user_agent.clone(),
+            item_type: None,
+            item_id: None,
+            details: None,
+        }
+    }
+
+    pub fn item(mut self, item_type: ItemType, item_id: i32) -> Self {
+        self.item_type = Some(item_type);
+        self.item_id = Some(item_id);
+        self
+    }
+
+    pub fn details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct Event {
+    pub id: i64,
+    pub event_type: EventType,
+    pub actor_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub item_type: Option<ItemType>,
+    pub item_id: Option<i32>,
+    pub details: Option<String>,
+    pub created_at: String,
+}
+
+/// Query string of `GET /events`. `since` and `until` are dates (`YYYY-MM-DD`), `until` inclusive.
+#[derive(Debug, FromForm)]
+pub struct EventFilter {
+    pub event_type: Option<String>,
+    pub item_type: Option<String>,
+    pub item_id: Option<i32>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// `EventFilter` after validation. A missing `actor_id` selects the events of all users.
+#[derive(Debug)]
+pub struct EventQuery {
+    pub actor_id: Option<i32>,
+    pub event_type: Option<EventType>,
+    pub item_type: Option<ItemType>,
+    pub item_id: Option<i32>,
+    pub since: Option<chrono::NaiveDate>,
+    pub until: Option<chrono::NaiveDate>,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl EventFilter {
+    pub fn into_query(self, actor_id: Option<i32>) -> Result<EventQuery, String> {
+        let date = |value: Option<String>| value
+            .map(|v| chrono::NaiveDate::from_str(&v).map_err(|_| format!("Invalid date: {}", v)))
+            .transpose();
+
+        Ok(EventQuery {
+            actor_id,
+            event_type: self.event_type.map(|t| t.parse()).transpose()?,
+            item_type: self.item_type.map(|t| t.parse()).transpose()?,
+            item_id: self.item_id,
+            since: date(self.since)?,
+            until: date(self.until)?,
+            page: self.page.unwrap_or(1).max(1),
+            per_page: self.per_page.unwrap_or(DEFAULT_EVENTS_PER_PAGE).clamp(1, MAX_EVENTS_PER_PAGE),
+        })
+    }
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct EventPage {
+    pub events: Vec<Event>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct EventCheckpoint {
+    pub id: i32,
+    pub event_id: i64,
+    pub hash: String,
+    pub signature: String,
+    pub created_at: String,
+}
+
+/// Where verification stopped. `chain` is `global`, `actor` or `content`, the latter meaning
+/// the record no longer matches its own hash.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct BrokenLink {
+    pub event_id: i64,
+    pub chain: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct ChainVerification {
+    pub events_checked: i64,
+    pub unchained_events: i64,
+    pub broken_link: Option<BrokenLink>,
+    pub checkpoints_checked: i64,
+    /// Checkpoints with a bad signature, a hash that differs from their event's, or an event
+    /// that is gone without a retention cut covering it.
+    pub invalid_checkpoints: Vec<i32>,
+    pub checkpoints_verifiable: bool,
+    pub public_key: Option<String>,
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for EventPage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for EventCheckpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for BrokenLink {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for ChainVerification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
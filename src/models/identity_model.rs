@@ -0,0 +1,130 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use chrono::NaiveDate;
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::{Validate, ValidationError};
+
+use crate::models::custom_field_model::{blank_hidden_fields, CustomField, CustomFieldDto, MAX_CUSTOM_FIELDS, validate_unlinked_fields};
+use crate::models::item_model::{ItemType, VaultItem};
+use crate::models::share_model::Permission;
+use crate::models::validation_model::{MAX_VARCHAR_LENGTH, validate_optional_email};
+
+/// A person's contact and passport details, for filling in forms.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct Identity {
+    pub id: i32,
+    pub name: String,
+    pub full_name: String,
+    pub email: String,
+    pub phone: String,
+    pub address: String,
+    pub city: String,
+    pub postal_code: String,
+    pub country: String,
+    pub passport_number: String,
+    pub passport_expiry: Option<String>,
+    pub note: String,
+    pub color: String,
+    pub created_at: String,
+    pub modified_at: String,
+    pub custom_fields: Vec<CustomField>,
+    /// Bumped by every update, sent as the `ETag`.
+    pub revision: i64,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub permission: Permission,
+}
+
+#[derive(Debug, Clone, Error, Serialize, Deserialize, Validate)]
+pub struct IdentityDto {
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub name: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub full_name: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"), custom = "validate_optional_email")]
+    pub email: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"), custom = "validate_phone")]
+    pub phone: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub address: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub city: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub postal_code: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub country: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub passport_number: Option<String>,
+    /// `null` clears the date, leaving it out keeps it.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub passport_expiry: Option<Option<NaiveDate>>,
+    pub note: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub color: Option<String>,
+    /// Replaces all custom fields when sent.
+    #[validate(length(max = "MAX_CUSTOM_FIELDS"), custom = "validate_unlinked_fields")]
+    #[validate]
+    pub custom_fields: Option<Vec<CustomFieldDto>>,
+}
+
+/// A phone number as people write it: digits, optionally with a leading `+`, spaces, dashes,
+/// dots and parentheses. Empty is fine.
+fn validate_phone(phone: &str) -> Result<(), ValidationError> {
+    let digits = phone.chars().filter(|c| c.is_ascii_digit()).count();
+    let allowed = phone.trim_start_matches('+').chars().all(|c| c.is_ascii_digit() || " -.()".contains(c));
+    if !phone.is_empty() && (!allowed || digits < 3) {
+        let mut err = ValidationError::new("phone");
+        err.message = Some("Must be a phone number.".into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+impl VaultItem for Identity {
+    type Dto = IdentityDto;
+
+    const ITEM_TYPE: ItemType = ItemType::Identity;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    fn permission(&self) -> Permission {
+        self.permission
+    }
+
+    fn set_permission(&mut self, permission: Permission) {
+        self.shared = permission != Permission::Owner;
+        self.permission = permission;
+    }
+
+    fn custom_fields_mut(&mut self) -> &mut Vec<CustomField> {
+        &mut self.custom_fields
+    }
+
+    /// Blanks the passport number and hidden custom fields for members with hide-passwords
+    /// access.
+    fn hide_secrets(&mut self) {
+        self.passport_number = String::new();
+        blank_hidden_fields(&mut self.custom_fields);
+    }
+}
+
+impl Display for Identity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for IdentityDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
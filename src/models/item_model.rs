@@ -0,0 +1,103 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use rocket::request::FromParam;
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::models::custom_field_model::{CustomField, mask_hidden_fields};
+use crate::models::share_model::Permission;
+
+/// Kinds of vault items. The string form is what `item_type` columns store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemType {
+    Login,
+    Payment,
+    SecuredNote,
+    Identity,
+    SshKey,
+    ApiCredential,
+    WifiNetwork,
+    SoftwareLicense,
+}
+
+impl ItemType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemType::Login => "login",
+            ItemType::Payment => "payment",
+            ItemType::SecuredNote => "secured_note",
+            ItemType::Identity => "identity",
+            ItemType::SshKey => "ssh_key",
+            ItemType::ApiCredential => "api_credential",
+            ItemType::WifiNetwork => "wifi_network",
+            ItemType::SoftwareLicense => "software_license",
+        }
+    }
+}
+
+impl FromStr for ItemType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "login" => Ok(ItemType::Login),
+            "payment" => Ok(ItemType::Payment),
+            "secured_note" => Ok(ItemType::SecuredNote),
+            "identity" => Ok(ItemType::Identity),
+            "ssh_key" => Ok(ItemType::SshKey),
+            "api_credential" => Ok(ItemType::ApiCredential),
+            "wifi_network" => Ok(ItemType::WifiNetwork),
+            "software_license" => Ok(ItemType::SoftwareLicense),
+            _ => Err(format!("Unknown item type: {}", s)),
+        }
+    }
+}
+
+/// Routes shared by all item types take the type from their first segment, e.g. `identities`.
+impl<'a> FromParam<'a> for ItemType {
+    type Error = &'a str;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        match param {
+            "logins" => Ok(ItemType::Login),
+            "payments" => Ok(ItemType::Payment),
+            "secured_notes" => Ok(ItemType::SecuredNote),
+            "identities" => Ok(ItemType::Identity),
+            "ssh_keys" => Ok(ItemType::SshKey),
+            "api_credentials" => Ok(ItemType::ApiCredential),
+            "wifi_networks" => Ok(ItemType::WifiNetwork),
+            "software_licenses" => Ok(ItemType::SoftwareLicense),
+            _ => Err(param),
+        }
+    }
+}
+
+impl Display for ItemType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Items served by the generic item handlers and DAOs, which only need to know their
+/// `ItemType` and the parts of them every item has.
+pub trait VaultItem: Serialize + Send + Sync + 'static {
+    /// What clients send to create or update the item.
+    type Dto: Serialize + Clone + Send + Sync;
+
+    const ITEM_TYPE: ItemType;
+
+    fn id(&self) -> i32;
+    fn revision(&self) -> i64;
+    fn permission(&self) -> Permission;
+    /// Records what the requesting user may do with the item.
+    fn set_permission(&mut self, permission: Permission);
+    fn custom_fields_mut(&mut self) -> &mut Vec<CustomField>;
+    /// Blanks secrets and hidden custom fields for members with hide-passwords access.
+    fn hide_secrets(&mut self);
+
+    /// Masks hidden custom fields, for list responses.
+    fn mask_custom_fields(&mut self) {
+        mask_hidden_fields(self.custom_fields_mut());
+    }
+}
@@ -2,27 +2,70 @@ use std::fmt::{Display, Formatter};
 
 use rocket::serde::{Deserialize, Serialize};
 use thiserror::Error;
+use validator::Validate;
 
-#[derive(Error, Debug, Serialize, Deserialize)]
+use crate::models::custom_field_model::{blank_hidden_fields, CustomField, CustomFieldDto, mask_hidden_fields, MAX_CUSTOM_FIELDS};
+use crate::models::share_model::Permission;
+use crate::models::url_match_model::MatchStrategy;
+use crate::models::validation_model::{MAX_VARCHAR_LENGTH, validate_list, validate_optional_email};
+
+/// How many logins the recently and most used views list, unless the client asks otherwise.
+pub const USAGE_VIEW_LIMIT: usize = 10;
+
+#[derive(Error, Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct LoginDto {
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
     pub username: Option<String>,
     pub note: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
     pub password: Option<String>,
+    #[validate(custom = "validate_optional_email", length(max = "MAX_VARCHAR_LENGTH"))]
     pub email: Option<String>,
+    /// URLs, or regexes for logins matched by regex. Checked against the strategy by the handlers.
+    #[validate(custom = "validate_list")]
     pub linked_websites: Option<Vec<String>>,
+    #[validate(custom = "validate_list")]
     pub collections: Option<Vec<String>>,
+    pub match_strategy: Option<MatchStrategy>,
+    /// Replaces all custom fields when sent.
+    #[validate(length(max = "MAX_CUSTOM_FIELDS"))]
+    #[validate]
+    pub custom_fields: Option<Vec<CustomFieldDto>>,
 }
 
 #[derive(Error, Debug, Serialize, Deserialize)]
 pub struct Login {
     pub id: i32,
+    /// When the login was last filled in or copied, or created if never.
     pub used_at: String,
+    pub use_count: i32,
     pub username: String,
     pub password: String,
     pub note: String,
     pub email: String,
     pub linked_websites: Vec<String>,
     pub collections: Vec<String>,
+    pub match_strategy: MatchStrategy,
+    pub custom_fields: Vec<CustomField>,
+    /// Bumped by every update, sent as the `ETag`.
+    pub revision: i64,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub permission: Permission,
+}
+
+impl Login {
+    /// Blanks the password and hidden custom fields for members with hide-passwords access.
+    pub fn hide_secrets(&mut self) {
+        self.password = String::new();
+        blank_hidden_fields(&mut self.custom_fields);
+    }
+
+    /// Masks hidden custom fields, for list responses.
+    pub fn mask_custom_fields(&mut self) {
+        mask_hidden_fields(&mut self.custom_fields);
+    }
 }
 
 impl Display for LoginDto {
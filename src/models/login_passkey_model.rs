@@ -0,0 +1,69 @@
+use std::fmt::{Display, Formatter};
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+use crate::models::share_model::Permission;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// A passkey for a website, kept on the login for that site. `credential_id` and `user_handle`
+/// are base64url as in WebAuthn; `private_key` is ciphertext, encrypted by the client.
+#[derive(Error, Debug, Serialize, Deserialize, Validate)]
+pub struct LoginPasskeyDto {
+    pub credential_id: String,
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub rp_id: String,
+    pub user_handle: String,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub user_name: Option<String>,
+    pub private_key: String,
+    pub sign_count: Option<i64>,
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub struct LoginPasskey {
+    pub id: i32,
+    pub login_id: i32,
+    pub credential_id: String,
+    pub rp_id: String,
+    pub user_handle: String,
+    pub user_name: Option<String>,
+    pub private_key: String,
+    pub sign_count: i64,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    #[serde(default)]
+    pub permission: Permission,
+}
+
+impl LoginPasskey {
+    /// Blanks the private key for members with hide-passwords access.
+    pub fn hide_secrets(&mut self) {
+        self.private_key = String::new();
+    }
+}
+
+/// The counter an authenticator reported in its last assertion.
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub struct SignCountDto {
+    pub sign_count: i64,
+}
+
+impl Display for LoginPasskeyDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for LoginPasskey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for SignCountDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
@@ -8,17 +8,76 @@ pub mod auth_model;
 pub mod login_model;
 pub mod payment_model;
 pub mod secured_note;
+pub mod storage_model;
+pub mod item_model;
+pub mod attachment_model;
+pub mod send_model;
+pub mod share_model;
+pub mod organization_model;
+pub mod emergency_access_model;
+pub mod event_model;
+pub mod webauthn_model;
+pub mod login_passkey_model;
+pub mod user_token_model;
+pub mod password_policy_model;
+pub mod error_model;
+pub mod validation_model;
+pub mod notification_model;
+pub mod push_model;
+pub mod sync_model;
+pub mod revision_model;
+pub mod url_match_model;
+pub mod report_model;
+pub mod custom_field_model;
+pub mod identity_model;
+pub mod ssh_key_model;
+pub mod api_credential_model;
+pub mod wifi_network_model;
+pub mod software_license_model;
 
 
 #[derive(Error, Debug)]
 pub enum DBError {
     #[error("Invalid UUID provided: {0}")]
     InvalidUUID(String),
+    #[error("Invalid item type: {0}")]
+    InvalidItemType(String),
+    #[error("Invalid permission: {0}")]
+    InvalidPermission(String),
+    #[error("Invalid organization role: {0}")]
+    InvalidRole(String),
+    #[error("Invalid collection access: {0}")]
+    InvalidAccess(String),
+    #[error("Invalid emergency access: {0}")]
+    InvalidEmergencyAccess(String),
+    #[error("Invalid event type: {0}")]
+    InvalidEventType(String),
+    #[error("Invalid credential: {0}")]
+    InvalidCredential(String),
+    #[error("Invalid card brand: {0}")]
+    InvalidCardBrand(String),
+    #[error("Invalid notification kind: {0}")]
+    InvalidNotificationKind(String),
+    #[error("Invalid custom field: {0}")]
+    InvalidCustomField(String),
+    #[error("Invalid match strategy: {0}")]
+    InvalidMatchStrategy(String),
+    #[error("Invalid Wi-Fi security type: {0}")]
+    InvalidWifiSecurity(String),
+    #[error("The item was changed since the revision the update was based on")]
+    StaleRevision,
+    /// A storage quota or attachment limit, with the message for the client.
+    #[error("{0}")]
+    LimitExceeded(String),
     #[error("Database error occurred")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
 
 // source: https://www.postgresql.org/docs/current/errcodes-appendix.html
 pub mod postgres_error_codes {
+    pub const NOT_NULL_VIOLATION: &str = "23502";
     pub const FOREIGN_KEY_VIOLATION: &str = "23503";
+    pub const UNIQUE_VIOLATION: &str = "23505";
+    pub const CHECK_VIOLATION: &str = "23514";
+    pub const INVALID_TEXT_REPRESENTATION: &str = "22P02";
 }
@@ -0,0 +1,125 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+use crate::models::item_model::ItemType;
+
+/// Longest warning before a card expires users can ask for.
+pub const MAX_CARD_EXPIRY_DAYS: i32 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    CardExpiring,
+    PasswordRotation,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::CardExpiring => "card_expiring",
+            NotificationKind::PasswordRotation => "password_rotation",
+        }
+    }
+}
+
+impl FromStr for NotificationKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "card_expiring" => Ok(NotificationKind::CardExpiring),
+            "password_rotation" => Ok(NotificationKind::PasswordRotation),
+            _ => Err(format!("Unknown notification kind: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: i32,
+    pub user_id: i32,
+    pub kind: NotificationKind,
+    pub item_type: Option<ItemType>,
+    pub item_id: Option<i32>,
+    pub message: String,
+    pub created_at: String,
+    pub dismissed_at: Option<String>,
+}
+
+/// A notification on its way to the `notifications` table. `dedup_key` identifies what it is
+/// about, e.g. the card's expiry, so the same reminder is only created once.
+#[derive(Debug, Clone)]
+pub struct NewNotification {
+    pub user_id: i32,
+    pub kind: NotificationKind,
+    pub item_type: ItemType,
+    pub item_id: i32,
+    pub dedup_key: String,
+    pub message: String,
+}
+
+/// Which reminders a user gets, and whether they are emailed as well.
+#[derive(Debug, Clone, Error, Serialize, Deserialize, Validate)]
+pub struct NotificationPreferences {
+    pub card_expiry: bool,
+    #[validate(range(min = 1, max = "MAX_CARD_EXPIRY_DAYS"))]
+    pub card_expiry_days: i32,
+    pub password_rotation: bool,
+    pub email: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        NotificationPreferences {
+            card_expiry: true,
+            card_expiry_days: 30,
+            password_rotation: true,
+            email: false,
+        }
+    }
+}
+
+/// A card that expires within its owner's warning period.
+#[derive(Debug)]
+pub struct ExpiringCard {
+    pub user_id: i32,
+    pub payment_id: i32,
+    pub name: String,
+    pub last_four: String,
+    pub expiration_month: i16,
+    pub expiration_year: i16,
+}
+
+/// A login in a collection with a rotation interval whose password is older than that.
+#[derive(Debug)]
+pub struct StalePassword {
+    pub user_id: i32,
+    pub login_id: i32,
+    pub username: String,
+    pub password_changed_at: String,
+    pub rotation_days: i32,
+}
+
+/// A notification still to be emailed to its user.
+#[derive(Debug)]
+pub struct PendingEmail {
+    pub notification: Notification,
+    pub email: String,
+    pub first_name: String,
+}
+
+impl Display for Notification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for NotificationPreferences {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+use crate::models::item_model::ItemType;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// Organization roles, in increasing order. Owners and admins manage members and every
+/// collection; managers manage the collections they have access to; users only get the
+/// access they were given per collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+    User,
+    Manager,
+    Admin,
+    Owner,
+}
+
+impl OrgRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrgRole::User => "user",
+            OrgRole::Manager => "manager",
+            OrgRole::Admin => "admin",
+            OrgRole::Owner => "owner",
+        }
+    }
+}
+
+impl FromStr for OrgRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(OrgRole::User),
+            "manager" => Ok(OrgRole::Manager),
+            "admin" => Ok(OrgRole::Admin),
+            "owner" => Ok(OrgRole::Owner),
+            _ => Err(format!("Unknown organization role: {}", s)),
+        }
+    }
+}
+
+/// Access of a member to a collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionAccess {
+    ReadOnly,
+    HidePasswords,
+    Manage,
+}
+
+impl CollectionAccess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollectionAccess::ReadOnly => "read_only",
+            CollectionAccess::HidePasswords => "hide_passwords",
+            CollectionAccess::Manage => "manage",
+        }
+    }
+}
+
+impl FromStr for CollectionAccess {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read_only" => Ok(CollectionAccess::ReadOnly),
+            "hide_passwords" => Ok(CollectionAccess::HidePasswords),
+            "manage" => Ok(CollectionAccess::Manage),
+            _ => Err(format!("Unknown collection access: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct OrganizationDto {
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub name: String,
+}
+
+/// An organization as seen by one of its members.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct Organization {
+    pub id: i32,
+    pub name: String,
+    pub role: OrgRole,
+    pub accepted: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct MemberDto {
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub username: String,
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct MemberRoleDto {
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub struct Member {
+    pub id: i32,
+    pub organization_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub role: OrgRole,
+    pub accepted: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct OrgCollectionDto {
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub name: String,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct OrgCollection {
+    pub id: i32,
+    pub organization_id: i32,
+    pub name: String,
+    /// Members are reminded to change passwords of logins in the collection after this many days.
+    pub rotation_days: Option<i32>,
+    pub created_at: String,
+}
+
+/// Longest password rotation interval, ten years.
+pub const MAX_ROTATION_DAYS: i32 = 3650;
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct CollectionRotationDto {
+    #[validate(range(min = 1, max = "MAX_ROTATION_DAYS"))]
+    pub rotation_days: Option<i32>,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct CollectionAccessDto {
+    pub member_id: i32,
+    pub access: CollectionAccess,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct CollectionItem {
+    pub item_type: ItemType,
+    pub item_id: i32,
+}
+
+impl Display for OrganizationDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for Organization {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for MemberDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for MemberRoleDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for OrgCollectionDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for OrgCollection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for CollectionRotationDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for CollectionAccessDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for CollectionItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
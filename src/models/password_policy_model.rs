@@ -0,0 +1,186 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+/// Highest strength score zxcvbn hands out.
+pub const MAX_PASSWORD_SCORE: i32 = 4;
+pub const MAX_PASSWORD_LENGTH: i32 = 128;
+
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+/// Parts of the user's identity shorter than this aren't banned from passwords.
+const MIN_BANNED_FRAGMENT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    MinLength,
+    MaxLength,
+    MinScore,
+    Banned,
+}
+
+impl PolicyRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyRule::MinLength => "min_length",
+            PolicyRule::MaxLength => "max_length",
+            PolicyRule::MinScore => "min_score",
+            PolicyRule::Banned => "banned",
+        }
+    }
+}
+
+/// One rule a password broke, with a message for the user.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    pub rule: PolicyRule,
+    pub message: String,
+}
+
+/// Requirements for account passwords. The server wide policy comes from the environment;
+/// organizations can only tighten it for their members.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    pub min_length: i32,
+    pub min_score: i32,
+    #[serde(skip)]
+    pub banned: Vec<String>,
+}
+
+/// An organization's overrides. Unset values leave the server policy in place.
+#[derive(Debug, Clone, Default, Error, Serialize, Deserialize, Validate)]
+pub struct OrgPasswordPolicy {
+    #[validate(range(min = 1, max = "MAX_PASSWORD_LENGTH"))]
+    pub min_length: Option<i32>,
+    #[validate(range(min = 0, max = "MAX_PASSWORD_SCORE"))]
+    pub min_score: Option<i32>,
+}
+
+impl PasswordPolicy {
+    /// Reads PASSWORD_MIN_LENGTH (default 8), PASSWORD_MIN_SCORE (0 to 4, default 2) and
+    /// PASSWORD_BANNED_FILE, a file with one banned password per line on top of the built in
+    /// list of common passwords.
+    pub fn from_env() -> Self {
+        let min_length = std::env::var("PASSWORD_MIN_LENGTH").ok()
+            .map(|l| l.parse().expect("PASSWORD_MIN_LENGTH must be a number."))
+            .unwrap_or(8);
+        let min_score = std::env::var("PASSWORD_MIN_SCORE").ok()
+            .map(|s| s.parse().expect("PASSWORD_MIN_SCORE must be a number."))
+            .unwrap_or(2);
+
+        let mut banned: Vec<String> = COMMON_PASSWORDS.lines().map(|p| p.trim().to_lowercase()).collect();
+        if let Ok(path) = std::env::var("PASSWORD_BANNED_FILE") {
+            let extra = std::fs::read_to_string(&path).expect("Failed to read PASSWORD_BANNED_FILE.");
+            banned.extend(extra.lines().map(|p| p.trim().to_lowercase()));
+        }
+        banned.retain(|p| !p.is_empty());
+
+        PasswordPolicy { min_length, min_score: min_score.clamp(0, MAX_PASSWORD_SCORE), banned }
+    }
+
+    /// This policy, tightened by whatever `org` requires.
+    pub fn stricter(&self, org: &OrgPasswordPolicy) -> Self {
+        PasswordPolicy {
+            min_length: self.min_length.max(org.min_length.unwrap_or(0)),
+            min_score: self.min_score.max(org.min_score.unwrap_or(0)),
+            banned: self.banned.clone(),
+        }
+    }
+
+    /// Every rule `password` breaks. `identity` holds the username, email and other personal
+    /// details, which may neither be the password nor part of it.
+    pub fn check(&self, password: &str, identity: &[&str]) -> Vec<PolicyViolation> {
+        let mut violations = vec![];
+        let length = password.chars().count() as i32;
+
+        if length < self.min_length {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::MinLength,
+                message: format!("Password must be at least {} characters long.", self.min_length),
+            });
+        }
+        if length > MAX_PASSWORD_LENGTH {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::MaxLength,
+                message: format!("Password can be at most {} characters long.", MAX_PASSWORD_LENGTH),
+            });
+        }
+
+        let lowercase = password.to_lowercase();
+        let fragments = identity.iter()
+            .flat_map(|value| [value.to_lowercase(), value.split('@').next().unwrap_or("").to_lowercase()])
+            .filter(|fragment| fragment.chars().count() >= MIN_BANNED_FRAGMENT);
+        if self.banned.contains(&lowercase) {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::Banned,
+                message: String::from("Password is too common."),
+            });
+        } else if fragments.into_iter().any(|fragment| lowercase.contains(&fragment)) {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::Banned,
+                message: String::from("Password can't contain your username or email."),
+            });
+        }
+
+        let score = zxcvbn::zxcvbn(password, identity).map(|entropy| entropy.score() as i32).unwrap_or(0);
+        if score < self.min_score {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::MinScore,
+                message: format!("Password is too easy to guess: strength {} of {}, at least {} required.", score, MAX_PASSWORD_SCORE, self.min_score),
+            });
+        }
+
+        violations
+    }
+}
+
+impl Display for PolicyViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for OrgPasswordPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_policy() -> PasswordPolicy {
+        PasswordPolicy { min_length: 10, min_score: 2, banned: vec![String::from("password")] }
+    }
+
+    #[test]
+    fn stricter_takes_the_tighter_requirements() {
+        let org = OrgPasswordPolicy { min_length: Some(16), min_score: Some(4) };
+        let policy = server_policy().stricter(&org);
+
+        assert_eq!(policy.min_length, 16);
+        assert_eq!(policy.min_score, 4);
+        assert_eq!(policy.banned, vec![String::from("password")]);
+    }
+
+    #[test]
+    fn stricter_never_loosens_the_server_policy() {
+        let org = OrgPasswordPolicy { min_length: Some(6), min_score: Some(0) };
+        let policy = server_policy().stricter(&org);
+
+        assert_eq!(policy.min_length, 10);
+        assert_eq!(policy.min_score, 2);
+    }
+
+    #[test]
+    fn stricter_keeps_the_server_policy_for_unset_values() {
+        let policy = server_policy().stricter(&OrgPasswordPolicy::default());
+
+        assert_eq!(policy.min_length, 10);
+        assert_eq!(policy.min_score, 2);
+    }
+}
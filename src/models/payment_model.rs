@@ -1,18 +1,235 @@
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 use rocket::serde::{Deserialize, Serialize};
+use chrono::{Datelike, Utc};
 use thiserror::Error;
+use validator::{Validate, ValidationError};
 
-#[derive(Error, Debug, Serialize, Deserialize)]
+use crate::models::custom_field_model::{blank_hidden_fields, CustomField, CustomFieldDto, mask_hidden_fields, MAX_CUSTOM_FIELDS, validate_unlinked_fields};
+use crate::models::share_model::Permission;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// Card networks, told apart by the number's prefix. Each has its own number lengths and
+/// security code length.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardBrand {
+    Visa,
+    Mastercard,
+    Amex,
+    Discover,
+    DinersClub,
+    Jcb,
+    UnionPay,
+    Maestro,
+    #[default]
+    Unknown,
+}
+
+impl CardBrand {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CardBrand::Visa => "visa",
+            CardBrand::Mastercard => "mastercard",
+            CardBrand::Amex => "amex",
+            CardBrand::Discover => "discover",
+            CardBrand::DinersClub => "diners_club",
+            CardBrand::Jcb => "jcb",
+            CardBrand::UnionPay => "unionpay",
+            CardBrand::Maestro => "maestro",
+            CardBrand::Unknown => "unknown",
+        }
+    }
+
+    /// The brand of a card number of digits only. Order matters where ranges overlap, e.g.
+    /// Discover's co-branded 622126-622925 inside UnionPay's 62.
+    pub fn detect(number: &str) -> Self {
+        let prefix = |digits: usize| number.get(..digits).and_then(|p| p.parse::<u32>().ok()).unwrap_or(0);
+
+        if matches!(prefix(2), 34 | 37) {
+            CardBrand::Amex
+        } else if prefix(4) == 6011 || (644..=649).contains(&prefix(3)) || prefix(2) == 65 || (622126..=622925).contains(&prefix(6)) {
+            CardBrand::Discover
+        } else if (51..=55).contains(&prefix(2)) || (2221..=2720).contains(&prefix(4)) {
+            CardBrand::Mastercard
+        } else if prefix(1) == 4 {
+            CardBrand::Visa
+        } else if (3528..=3589).contains(&prefix(4)) {
+            CardBrand::Jcb
+        } else if (300..=305).contains(&prefix(3)) || matches!(prefix(2), 36 | 38 | 39) {
+            CardBrand::DinersClub
+        } else if prefix(2) == 62 {
+            CardBrand::UnionPay
+        } else if prefix(2) == 50 || (56..=69).contains(&prefix(2)) {
+            CardBrand::Maestro
+        } else {
+            CardBrand::Unknown
+        }
+    }
+
+    /// Number lengths the brand issues.
+    pub fn lengths(&self) -> &'static [usize] {
+        match self {
+            CardBrand::Visa => &[13, 16, 19],
+            CardBrand::Mastercard => &[16],
+            CardBrand::Amex => &[15],
+            CardBrand::Discover | CardBrand::Jcb | CardBrand::UnionPay => &[16, 17, 18, 19],
+            CardBrand::DinersClub => &[14, 15, 16, 17, 18, 19],
+            CardBrand::Maestro | CardBrand::Unknown => &[12, 13, 14, 15, 16, 17, 18, 19],
+        }
+    }
+
+    /// Digits in the security code: four on the front of Amex cards, three on the back of the
+    /// others.
+    pub fn security_code_length(&self) -> u32 {
+        match self {
+            CardBrand::Amex => 4,
+            _ => 3,
+        }
+    }
+}
+
+impl FromStr for CardBrand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "visa" => Ok(CardBrand::Visa),
+            "mastercard" => Ok(CardBrand::Mastercard),
+            "amex" => Ok(CardBrand::Amex),
+            "discover" => Ok(CardBrand::Discover),
+            "diners_club" => Ok(CardBrand::DinersClub),
+            "jcb" => Ok(CardBrand::Jcb),
+            "unionpay" => Ok(CardBrand::UnionPay),
+            "maestro" => Ok(CardBrand::Maestro),
+            "unknown" => Ok(CardBrand::Unknown),
+            _ => Err(format!("Unknown card brand: {}", s)),
+        }
+    }
+}
+
+/// The card number without the spaces and dashes people type into it.
+pub fn normalize_card_number(number: &str) -> String {
+    number.chars().filter(|c| !c.is_whitespace() && *c != '-').collect()
+}
+
+/// Whether `number`, digits only, passes the Luhn checksum.
+pub fn luhn_valid(number: &str) -> bool {
+    let mut sum = 0;
+    for (i, c) in number.chars().rev().enumerate() {
+        let Some(mut digit) = c.to_digit(10) else {
+            return false;
+        };
+        if i % 2 == 1 {
+            digit *= 2;
+            if digit > 9 {
+                digit -= 9;
+            }
+        }
+        sum += digit;
+    }
+
+    !number.is_empty() && sum % 10 == 0
+}
+
+/// Security codes are stored as numbers, so leading zeros are gone; only the upper bound
+/// can be checked.
+pub fn validate_security_code(brand: CardBrand, code: i16) -> Result<(), ValidationError> {
+    let length = brand.security_code_length();
+    if code < 0 || code as i32 >= 10_i32.pow(length) {
+        let mut err = ValidationError::new("security_code");
+        err.message = Some(format!("{} security codes have {} digits.", brand_name(brand), length).into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+fn brand_name(brand: CardBrand) -> &'static str {
+    match brand {
+        CardBrand::Unknown => "Card",
+        CardBrand::Amex => "American Express",
+        CardBrand::DinersClub => "Diners Club",
+        CardBrand::Jcb => "JCB",
+        CardBrand::UnionPay => "UnionPay",
+        CardBrand::Visa => "Visa",
+        CardBrand::Mastercard => "Mastercard",
+        CardBrand::Discover => "Discover",
+        CardBrand::Maestro => "Maestro",
+    }
+}
+
+#[derive(Error, Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_expiry"))]
 pub struct PaymentDto {
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
     pub card_holder: Option<String>,
+    #[validate(custom = "validate_card_number")]
     pub card_number: Option<String>,
+    #[validate(range(min = 0, max = 9999))]
     pub security_code: Option<i16>,
+    #[validate(range(min = 1, max = 12))]
     pub expiration_month: Option<i16>,
+    #[validate(custom = "validate_expiration_year")]
     pub expiration_year: Option<i16>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
     pub name: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
     pub color: Option<String>,
     pub note: Option<String>,
+    /// Replaces all custom fields when sent.
+    #[validate(length(max = "MAX_CUSTOM_FIELDS"), custom = "validate_unlinked_fields")]
+    #[validate]
+    pub custom_fields: Option<Vec<CustomFieldDto>>,
+}
+
+/// Cards that already expired can't be saved. On updates only the fields sent are checked.
+fn validate_expiration_year(year: i16) -> Result<(), ValidationError> {
+    if (year as i32) < Utc::now().year() {
+        let mut err = ValidationError::new("expired");
+        err.message = Some("The card has expired.".into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+fn validate_card_number(number: &str) -> Result<(), ValidationError> {
+    let number = normalize_card_number(number);
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        let mut err = ValidationError::new("card_number");
+        err.message = Some("Card numbers only contain digits.".into());
+        return Err(err);
+    }
+
+    let brand = CardBrand::detect(&number);
+    if !brand.lengths().contains(&number.len()) {
+        let mut err = ValidationError::new("length");
+        err.message = Some(format!("{} numbers don't have {} digits.", brand_name(brand), number.len()).into());
+        return Err(err);
+    }
+    if !luhn_valid(&number) {
+        let mut err = ValidationError::new("luhn");
+        err.message = Some("The card number has a typo: its check digit is wrong.".into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+fn validate_expiry(payment: &PaymentDto) -> Result<(), ValidationError> {
+    if let (Some(month), Some(year)) = (payment.expiration_month, payment.expiration_year) {
+        // Earlier years are caught by `validate_expiration_year`.
+        let now = Utc::now();
+        if year as i32 == now.year() && (month as u32) < now.month() {
+            let mut err = ValidationError::new("expired");
+            err.message = Some("The card has expired.".into());
+            return Err(err);
+        }
+    }
+
+    Ok(())
 }
 
 #[derive(Error, Debug, Serialize, Deserialize)]
@@ -21,11 +238,42 @@ pub struct Payment {
     pub card_holder: String,
     pub card_number: String,
     pub security_code: i16,
+    #[serde(default)]
+    pub brand: CardBrand,
     pub expiration_month: i16,
     pub expiration_year: i16,
     pub name: String,
     pub color: String,
     pub note: String,
+    pub custom_fields: Vec<CustomField>,
+    /// Bumped by every update, sent as the `ETag`.
+    pub revision: i64,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub permission: Permission,
+}
+
+impl Payment {
+    /// Blanks the card number, security code and hidden custom fields for members with
+    /// hide-passwords access.
+    pub fn hide_secrets(&mut self) {
+        self.card_number = String::new();
+        self.security_code = 0;
+        blank_hidden_fields(&mut self.custom_fields);
+    }
+
+    /// Leaves only the last four digits of the card number and masks hidden custom fields, for
+    /// lists and previews. All of them come from `GET /payments/<id>/reveal`.
+    pub fn mask(&mut self) {
+        mask_hidden_fields(&mut self.custom_fields);
+        let digits = self.card_number.chars().count();
+        if digits > 4 {
+            let last_four: String = self.card_number.chars().skip(digits - 4).collect();
+            self.card_number = format!("{}{}", "*".repeat(digits - 4), last_four);
+        }
+        self.security_code = 0;
+    }
 }
 
 impl Display for PaymentDto {
@@ -39,3 +287,75 @@ impl Display for Payment {
         f.write_str(format!("{:?}", self).as_str())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luhn_accepts_valid_check_digits() {
+        assert!(luhn_valid("4111111111111111"));
+        assert!(luhn_valid("79927398713"));
+        assert!(luhn_valid("0"));
+    }
+
+    #[test]
+    fn luhn_rejects_typos_and_non_digits() {
+        assert!(!luhn_valid("4111111111111112"));
+        assert!(!luhn_valid("79927398710"));
+        assert!(!luhn_valid("4111 1111 1111 1111"));
+        assert!(!luhn_valid(""));
+    }
+
+    #[test]
+    fn detects_brands_by_prefix() {
+        assert_eq!(CardBrand::detect("4111111111111111"), CardBrand::Visa);
+        assert_eq!(CardBrand::detect("5555555555554444"), CardBrand::Mastercard);
+        assert_eq!(CardBrand::detect("2221000000000009"), CardBrand::Mastercard);
+        assert_eq!(CardBrand::detect("2720990000000007"), CardBrand::Mastercard);
+        assert_eq!(CardBrand::detect("378282246310005"), CardBrand::Amex);
+        assert_eq!(CardBrand::detect("341111111111111"), CardBrand::Amex);
+        assert_eq!(CardBrand::detect("6011111111111117"), CardBrand::Discover);
+        assert_eq!(CardBrand::detect("6445644564456445"), CardBrand::Discover);
+        assert_eq!(CardBrand::detect("3530111333300000"), CardBrand::Jcb);
+        assert_eq!(CardBrand::detect("30569309025904"), CardBrand::DinersClub);
+        assert_eq!(CardBrand::detect("36227206271667"), CardBrand::DinersClub);
+        assert_eq!(CardBrand::detect("6200000000000005"), CardBrand::UnionPay);
+        assert_eq!(CardBrand::detect("5018000000000009"), CardBrand::Maestro);
+        assert_eq!(CardBrand::detect("5900000000000009"), CardBrand::Maestro);
+        assert_eq!(CardBrand::detect("6761000000000006"), CardBrand::Maestro);
+    }
+
+    #[test]
+    fn detects_overlapping_ranges_in_order() {
+        assert_eq!(CardBrand::detect("6221260000000000"), CardBrand::Discover);
+        assert_eq!(CardBrand::detect("6229250000000000"), CardBrand::Discover);
+        assert_eq!(CardBrand::detect("6221250000000000"), CardBrand::UnionPay);
+        assert_eq!(CardBrand::detect("6229260000000000"), CardBrand::UnionPay);
+        assert_eq!(CardBrand::detect("6500000000000002"), CardBrand::Discover);
+    }
+
+    #[test]
+    fn unknown_prefixes_have_no_brand() {
+        assert_eq!(CardBrand::detect("1234567812345670"), CardBrand::Unknown);
+        assert_eq!(CardBrand::detect("3100000000000000"), CardBrand::Unknown);
+        assert_eq!(CardBrand::detect("2220990000000000"), CardBrand::Unknown);
+        assert_eq!(CardBrand::detect(""), CardBrand::Unknown);
+    }
+
+    #[test]
+    fn security_code_fits_brand() {
+        assert!(validate_security_code(CardBrand::Amex, 1234).is_ok());
+        assert!(validate_security_code(CardBrand::Amex, 123).is_ok());
+        assert!(validate_security_code(CardBrand::Visa, 999).is_ok());
+        assert!(validate_security_code(CardBrand::Unknown, 0).is_ok());
+    }
+
+    #[test]
+    fn security_code_too_long_for_brand() {
+        let err = validate_security_code(CardBrand::Visa, 1234).unwrap_err();
+        assert_eq!(err.message.as_deref(), Some("Visa security codes have 3 digits."));
+        assert!(validate_security_code(CardBrand::Amex, 10000).is_err());
+        assert!(validate_security_code(CardBrand::Mastercard, -1).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::models::item_model::ItemType;
+
+/// What connected devices are told. Item events only say which item changed; clients fetch
+/// the item themselves, with the permissions they have on it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PushEvent {
+    ItemCreated { item_type: ItemType, item_id: i32 },
+    ItemUpdated { item_type: ItemType, item_id: i32 },
+    ItemDeleted { item_type: ItemType, item_id: i32 },
+    /// Every session of the user ended, e.g. after a password change. Streams close after it.
+    Logout,
+    /// The device fell behind and missed events; it should refetch everything.
+    Resync,
+}
+
+impl PushEvent {
+    /// The SSE event name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PushEvent::ItemCreated { .. } => "item_created",
+            PushEvent::ItemUpdated { .. } => "item_updated",
+            PushEvent::ItemDeleted { .. } => "item_deleted",
+            PushEvent::Logout => "logout",
+            PushEvent::Resync => "resync",
+        }
+    }
+}
+
+/// An event and the users it is for, as passed between instances.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushMessage {
+    pub user_ids: Vec<i32>,
+    pub event: PushEvent,
+}
@@ -0,0 +1,35 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Logins not used for this long show up in the security report.
+pub const UNUSED_LOGIN_DAYS: i32 = 365;
+
+/// A login nobody filled in or copied for `UNUSED_LOGIN_DAYS`, likely an account to close.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct UnusedLogin {
+    pub login_id: i32,
+    pub username: String,
+    pub linked_websites: Vec<String>,
+    pub used_at: String,
+    pub use_count: i32,
+}
+
+/// Findings about the user's own vault.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct SecurityReport {
+    pub unused_logins: Vec<UnusedLogin>,
+}
+
+impl Display for UnusedLogin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for SecurityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
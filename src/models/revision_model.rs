@@ -0,0 +1,89 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use rocket::{Request, response};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{Responder, Response};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::serde::json::serde_json::{self, Value};
+use thiserror::Error;
+
+use crate::models::error_model::{ErrorCode, ErrorResponse};
+use crate::models::validation_model::RejectedBody;
+
+/// The `ETag` of an item at `revision`.
+pub fn etag(revision: i64) -> String {
+    format!("\"{}\"", revision)
+}
+
+/// The revision named by the `If-Match` header. Without the header, or with `*`, updates
+/// overwrite whatever is stored.
+pub struct IfMatch(pub Option<i64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = ErrorResponse;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let value = match request.headers().get_one("If-Match").map(str::trim) {
+            None | Some("*") => return Outcome::Success(IfMatch(None)),
+            Some(value) => value,
+        };
+
+        let revision = value.strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .and_then(|value| value.parse().ok());
+        match revision {
+            Some(revision) => Outcome::Success(IfMatch(Some(revision))),
+            None => {
+                let response = ErrorResponse::new(ErrorCode::BadRequest, "If-Match must be a single ETag returned by the server.");
+                request.local_cache(|| RejectedBody(Some(response.clone())));
+                Outcome::Failure((Status::BadRequest, response))
+            }
+        }
+    }
+}
+
+/// A response for the item at `revision`, sent with its `ETag`.
+pub struct Tagged<R> {
+    pub revision: i64,
+    pub inner: R,
+}
+
+impl<R> Tagged<R> {
+    pub fn new(revision: i64, inner: R) -> Self {
+        Tagged { revision, inner }
+    }
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Tagged<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        Response::build_from(self.inner.respond_to(request)?)
+            .raw_header("ETag", etag(self.revision))
+            .ok()
+    }
+}
+
+/// Both sides of an update refused because the item changed in the meantime: the item as
+/// stored and the changes the client sent, so the client can merge them and retry with the
+/// stored revision.
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
+pub struct EditConflict {
+    pub current: Value,
+    pub submitted: Value,
+}
+
+impl EditConflict {
+    pub fn new(current: &impl Serialize, submitted: &impl Serialize) -> Self {
+        EditConflict {
+            current: serde_json::to_value(current).unwrap_or(Value::Null),
+            submitted: serde_json::to_value(submitted).unwrap_or(Value::Null),
+        }
+    }
+}
+
+impl Display for EditConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
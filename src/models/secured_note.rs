@@ -2,6 +2,11 @@ use std::fmt::{Debug, Display, Formatter};
 
 use rocket::serde::{Deserialize, Serialize};
 use thiserror::Error;
+use validator::Validate;
+
+use crate::models::custom_field_model::{CustomField, CustomFieldDto, mask_hidden_fields, MAX_CUSTOM_FIELDS, validate_unlinked_fields};
+use crate::models::share_model::Permission;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
 
 #[derive(Debug, Error, Serialize, Deserialize)]
 pub struct SecuredNote {
@@ -11,35 +16,37 @@ pub struct SecuredNote {
     pub created_at: String,
     pub modified_at: String,
     pub color: String,
+    pub custom_fields: Vec<CustomField>,
+    /// Bumped by every update, sent as the `ETag`.
+    pub revision: i64,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub permission: Permission,
 }
 
 
-#[derive(Debug, Error, Serialize, Deserialize)]
+#[derive(Debug, Clone, Error, Serialize, Deserialize, Validate)]
 pub struct SecuredNoteDto {
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
     pub name: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
     pub content: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
     pub color: Option<String>,
+    /// Replaces all custom fields when sent.
+    #[validate(length(max = "MAX_CUSTOM_FIELDS"), custom = "validate_unlinked_fields")]
+    #[validate]
+    pub custom_fields: Option<Vec<CustomFieldDto>>,
 }
 
-#[derive(Debug, Error, Deserialize, Serialize)]
-pub struct File {
-    pub id: i32,
-    pub name: String,
-    pub owner_id: i32,
-    pub note_id: i32,
-    pub size: i32,
-    pub created_at: String,
-    pub file_type: String,
-}
-
-#[derive(Debug, Error, Deserialize, Serialize)]
-pub struct FileDto {
-    pub name: String,
-    pub size: i32,
-    pub file_type: String,
+impl SecuredNote {
+    /// Masks hidden custom fields, for list responses.
+    pub fn mask_custom_fields(&mut self) {
+        mask_hidden_fields(&mut self.custom_fields);
+    }
 }
 
-
 impl Display for SecuredNote {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str(format!("{:?}", self).as_str())
@@ -52,16 +59,3 @@ impl Display for SecuredNoteDto {
         f.write_str(format!("{:?}", self).as_str())
     }
 }
-
-impl Display for File {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(format!("{:?}", self).as_str())
-    }
-}
-
-
-impl Display for FileDto {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(format!("{:?}", self).as_str())
-    }
-}
@@ -0,0 +1,142 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+pub const DEFAULT_SEND_EXPIRY_MINUTES: i32 = 7 * 24 * 60;
+pub const MAX_SEND_EXPIRY_MINUTES: i32 = 31 * 24 * 60;
+/// Wrong passwords a send takes before it is locked for `SEND_LOCKOUT_MINUTES`.
+pub const MAX_SEND_PASSWORD_ATTEMPTS: i32 = 5;
+pub const SEND_LOCKOUT_MINUTES: i32 = 15;
+
+/// A text share. `text` is ciphertext: the key only ever lives in the fragment of the share
+/// link, which browsers don't send to the server.
+#[derive(Error, Serialize, Deserialize, Validate)]
+pub struct SecureSendDto {
+    pub text: Option<String>,
+    #[validate(range(min = 1, max = "MAX_SEND_EXPIRY_MINUTES"))]
+    pub expires_in_minutes: Option<i32>,
+    #[validate(range(min = 1))]
+    pub max_views: Option<i32>,
+    pub password: Option<String>,
+    pub hide_email: Option<bool>,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct SecureSend {
+    pub id: i32,
+    pub token: String,
+    pub kind: String,
+    pub file_name: Option<String>,
+    pub file_size: Option<i32>,
+    pub has_password: bool,
+    pub hide_email: bool,
+    pub max_views: Option<i32>,
+    pub view_count: i32,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+#[derive(Error, Serialize, Deserialize)]
+pub struct SecureSendAccessDto {
+    pub password: Option<String>,
+}
+
+/// What an anonymous recipient gets back for a share token.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct SecureSendView {
+    pub kind: String,
+    pub text: Option<String>,
+    pub file_name: Option<String>,
+    pub file_size: Option<i32>,
+    pub creator_email: Option<String>,
+    pub expires_at: String,
+    pub views_left: Option<i32>,
+}
+
+/// Keeps the send password out of logs.
+impl Debug for SecureSendDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecureSendDto")
+            .field("text", &self.text)
+            .field("expires_in_minutes", &self.expires_in_minutes)
+            .field("max_views", &self.max_views)
+            .field("password", &"<redacted>")
+            .field("hide_email", &self.hide_email)
+            .finish()
+    }
+}
+
+impl Display for SecureSendDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for SecureSend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+/// Keeps the send password out of logs.
+impl Debug for SecureSendAccessDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecureSendAccessDto").field("password", &"<redacted>").finish()
+    }
+}
+
+impl Display for SecureSendAccessDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for SecureSendView {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+/// A share as stored, including the fields that must not leave the server.
+pub struct SecureSendRecord {
+    pub id: i32,
+    pub kind: String,
+    pub content: Option<String>,
+    pub file_name: Option<String>,
+    pub file_size: Option<i32>,
+    pub password: Option<String>,
+    pub max_views: Option<i32>,
+    pub view_count: i32,
+    pub expires_at: String,
+    pub creator_email: Option<String>,
+    pub available: bool,
+    /// Too many wrong passwords were tried recently.
+    pub locked: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send(expires_in_minutes: Option<i32>) -> SecureSendDto {
+        SecureSendDto { text: Some(String::from("ciphertext")), expires_in_minutes, max_views: None, password: Some(String::from("hunter2")), hide_email: None }
+    }
+
+    #[test]
+    fn expiry_must_be_within_range() {
+        assert!(send(None).validate().is_ok());
+        assert!(send(Some(1)).validate().is_ok());
+        assert!(send(Some(MAX_SEND_EXPIRY_MINUTES)).validate().is_ok());
+        assert!(send(Some(0)).validate().is_err());
+        assert!(send(Some(-5)).validate().is_err());
+        assert!(send(Some(MAX_SEND_EXPIRY_MINUTES + 1)).validate().is_err());
+    }
+
+    #[test]
+    fn passwords_are_redacted() {
+        assert!(!format!("{}", send(None)).contains("hunter2"));
+        assert!(!format!("{:?}", SecureSendAccessDto { password: Some(String::from("hunter2")) }).contains("hunter2"));
+    }
+}
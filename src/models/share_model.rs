@@ -0,0 +1,109 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+use crate::models::item_model::ItemType;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// What a user may do with an item, in increasing order. Shares grant `Read` or `Write`,
+/// organization collections any of the first three; only the owner may delete an item or
+/// share it further. `HidePasswords` sees an item without its secrets, and is what a
+/// permission missing from input falls back to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    #[default]
+    HidePasswords,
+    Read,
+    Write,
+    Owner,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::HidePasswords => "hide_passwords",
+            Permission::Read => "read",
+            Permission::Write => "write",
+            Permission::Owner => "owner",
+        }
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hide_passwords" => Ok(Permission::HidePasswords),
+            "read" => Ok(Permission::Read),
+            "write" => Ok(Permission::Write),
+            "owner" => Ok(Permission::Owner),
+            _ => Err(format!("Unknown permission: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct ShareDto {
+    pub item_type: ItemType,
+    pub item_id: i32,
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub username: String,
+    pub permission: Permission,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct Share {
+    pub id: i32,
+    pub item_type: ItemType,
+    pub item_id: i32,
+    pub owner_id: i32,
+    pub owner_username: String,
+    pub shared_with_id: i32,
+    pub shared_with_username: String,
+    pub permission: Permission,
+    pub created_at: String,
+}
+
+impl Display for ShareDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for Share {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permissions_rank_from_hide_passwords_to_owner() {
+        assert!(Permission::HidePasswords < Permission::Read);
+        assert!(Permission::Read < Permission::Write);
+        assert!(Permission::Write < Permission::Owner);
+        assert_eq!(Permission::default(), Permission::HidePasswords);
+    }
+
+    #[test]
+    fn strongest_grant_wins() {
+        let grants = [Permission::Read, Permission::Write, Permission::HidePasswords];
+        assert_eq!(grants.iter().max(), Some(&Permission::Write));
+    }
+
+    #[test]
+    fn permissions_round_trip_through_strings() {
+        for permission in [Permission::HidePasswords, Permission::Read, Permission::Write, Permission::Owner] {
+            assert_eq!(permission.as_str().parse::<Permission>(), Ok(permission));
+        }
+        assert!("admin".parse::<Permission>().is_err());
+    }
+}
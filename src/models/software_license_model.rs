@@ -0,0 +1,119 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use chrono::{NaiveDate, Utc};
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::{Validate, ValidationError};
+
+use crate::models::custom_field_model::{blank_hidden_fields, CustomField, CustomFieldDto, MAX_CUSTOM_FIELDS, validate_unlinked_fields};
+use crate::models::item_model::{ItemType, VaultItem};
+use crate::models::share_model::Permission;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// Longest license key accepted. Some licenses are whole signed files.
+pub const MAX_LICENSE_KEY_LENGTH: u64 = 16_384;
+/// Most seats one license can have.
+pub const MAX_LICENSE_SEATS: i32 = 1_000_000;
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct SoftwareLicense {
+    pub id: i32,
+    pub name: String,
+    pub product: String,
+    pub license_key: String,
+    pub seats: i32,
+    pub purchase_date: Option<String>,
+    /// The person or company the license was sold to.
+    pub licensed_to: String,
+    pub note: String,
+    pub color: String,
+    pub created_at: String,
+    pub modified_at: String,
+    pub custom_fields: Vec<CustomField>,
+    /// Bumped by every update, sent as the `ETag`.
+    pub revision: i64,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub permission: Permission,
+}
+
+#[derive(Debug, Clone, Error, Serialize, Deserialize, Validate)]
+pub struct SoftwareLicenseDto {
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub name: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub product: Option<String>,
+    #[validate(length(max = "MAX_LICENSE_KEY_LENGTH"))]
+    pub license_key: Option<String>,
+    #[validate(range(min = 1, max = "MAX_LICENSE_SEATS"))]
+    pub seats: Option<i32>,
+    /// `null` clears the date, leaving it out keeps it.
+    #[validate(custom = "validate_purchase_date")]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub purchase_date: Option<Option<NaiveDate>>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub licensed_to: Option<String>,
+    pub note: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub color: Option<String>,
+    /// Replaces all custom fields when sent.
+    #[validate(length(max = "MAX_CUSTOM_FIELDS"), custom = "validate_unlinked_fields")]
+    #[validate]
+    pub custom_fields: Option<Vec<CustomFieldDto>>,
+}
+
+fn validate_purchase_date(date: &NaiveDate) -> Result<(), ValidationError> {
+    if *date > Utc::now().date_naive() {
+        let mut err = ValidationError::new("purchase_date");
+        err.message = Some("Can't be in the future.".into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+impl VaultItem for SoftwareLicense {
+    type Dto = SoftwareLicenseDto;
+
+    const ITEM_TYPE: ItemType = ItemType::SoftwareLicense;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    fn permission(&self) -> Permission {
+        self.permission
+    }
+
+    fn set_permission(&mut self, permission: Permission) {
+        self.shared = permission != Permission::Owner;
+        self.permission = permission;
+    }
+
+    fn custom_fields_mut(&mut self) -> &mut Vec<CustomField> {
+        &mut self.custom_fields
+    }
+
+    /// Blanks the license key and hidden custom fields for members with hide-passwords access.
+    fn hide_secrets(&mut self) {
+        self.license_key = String::new();
+        blank_hidden_fields(&mut self.custom_fields);
+    }
+}
+
+impl Display for SoftwareLicense {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for SoftwareLicenseDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use base64::{Engine as _, engine::general_purpose};
+use rocket::serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use validator::{Validate, ValidationError};
+
+use crate::models::custom_field_model::{blank_hidden_fields, CustomField, CustomFieldDto, MAX_CUSTOM_FIELDS, validate_unlinked_fields};
+use crate::models::item_model::{ItemType, VaultItem};
+use crate::models::share_model::Permission;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// Longest private key accepted, enough for 16384 bit RSA keys.
+pub const MAX_SSH_KEY_LENGTH: u64 = 16_384;
+
+/// Public key algorithms of OpenSSH.
+const SSH_KEY_TYPES: [&str; 8] = [
+    "ssh-ed25519",
+    "ssh-rsa",
+    "ssh-dss",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "sk-ssh-ed25519@openssh.com",
+    "sk-ecdsa-sha2-nistp256@openssh.com",
+];
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct SshKey {
+    pub id: i32,
+    pub name: String,
+    pub private_key: String,
+    pub public_key: String,
+    /// SHA256 fingerprint of the public key, as `ssh-keygen -l` prints it. Computed, not sent.
+    pub fingerprint: String,
+    pub passphrase: String,
+    pub note: String,
+    pub color: String,
+    pub created_at: String,
+    pub modified_at: String,
+    pub custom_fields: Vec<CustomField>,
+    /// Bumped by every update, sent as the `ETag`.
+    pub revision: i64,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub permission: Permission,
+}
+
+#[derive(Debug, Clone, Error, Serialize, Deserialize, Validate)]
+pub struct SshKeyDto {
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub name: Option<String>,
+    #[validate(length(max = "MAX_SSH_KEY_LENGTH"), custom = "validate_private_key")]
+    pub private_key: Option<String>,
+    #[validate(length(max = "MAX_SSH_KEY_LENGTH"), custom = "validate_public_key")]
+    pub public_key: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub passphrase: Option<String>,
+    pub note: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub color: Option<String>,
+    /// Replaces all custom fields when sent.
+    #[validate(length(max = "MAX_CUSTOM_FIELDS"), custom = "validate_unlinked_fields")]
+    #[validate]
+    pub custom_fields: Option<Vec<CustomFieldDto>>,
+}
+
+/// The key blob of an OpenSSH public key line, `<type> <base64 blob> [comment]`. The blob
+/// starts with the key type again, which has to match.
+fn public_key_blob(public_key: &str) -> Option<Vec<u8>> {
+    let mut parts = public_key.split_whitespace();
+    let key_type = parts.next()?;
+    if !SSH_KEY_TYPES.contains(&key_type) {
+        return None;
+    }
+
+    let blob = general_purpose::STANDARD.decode(parts.next()?).ok()?;
+    let length = u32::from_be_bytes(blob.get(..4)?.try_into().ok()?) as usize;
+    if blob.get(4..4 + length)? != key_type.as_bytes() {
+        return None;
+    }
+
+    Some(blob)
+}
+
+/// `SHA256:` and the unpadded base64 digest of the key blob. Empty for an empty key.
+pub fn ssh_fingerprint(public_key: &str) -> String {
+    match public_key_blob(public_key) {
+        Some(blob) => format!("SHA256:{}", general_purpose::STANDARD_NO_PAD.encode(Sha256::digest(blob))),
+        None => String::new(),
+    }
+}
+
+/// An OpenSSH public key line, or nothing at all.
+fn validate_public_key(public_key: &str) -> Result<(), ValidationError> {
+    if !public_key.trim().is_empty() && public_key_blob(public_key).is_none() {
+        let mut err = ValidationError::new("public_key");
+        err.message = Some("Must be an OpenSSH public key, like the contents of id_ed25519.pub.".into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// A PEM encoded private key in OpenSSH, PKCS#1 or PKCS#8 format, or nothing at all.
+fn validate_private_key(private_key: &str) -> Result<(), ValidationError> {
+    let private_key = private_key.trim();
+    let framed = private_key.starts_with("-----BEGIN ") && private_key.ends_with("PRIVATE KEY-----");
+    if !private_key.is_empty() && !framed {
+        let mut err = ValidationError::new("private_key");
+        err.message = Some("Must be a PEM encoded private key, starting with -----BEGIN.".into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+impl VaultItem for SshKey {
+    type Dto = SshKeyDto;
+
+    const ITEM_TYPE: ItemType = ItemType::SshKey;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    fn permission(&self) -> Permission {
+        self.permission
+    }
+
+    fn set_permission(&mut self, permission: Permission) {
+        self.shared = permission != Permission::Owner;
+        self.permission = permission;
+    }
+
+    fn custom_fields_mut(&mut self) -> &mut Vec<CustomField> {
+        &mut self.custom_fields
+    }
+
+    /// Blanks the private key, passphrase and hidden custom fields for members with
+    /// hide-passwords access. The public key isn't a secret.
+    fn hide_secrets(&mut self) {
+        self.private_key = String::new();
+        self.passphrase = String::new();
+        blank_hidden_fields(&mut self.custom_fields);
+    }
+}
+
+impl Display for SshKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for SshKeyDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
@@ -0,0 +1,118 @@
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::models::attachment_model::File;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub used: i64,
+    pub quota: i64,
+    pub files: i64,
+    pub max_files_per_item: i32,
+    pub plan: String,
+}
+
+impl StorageUsage {
+    pub fn remaining(&self) -> i64 {
+        (self.quota - self.used).max(0)
+    }
+}
+
+/// Result of comparing `attachments` with the contents of `upload/`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FsckReport {
+    pub missing_files: Vec<File>,
+    pub orphaned_files: Vec<String>,
+    pub corrupted_files: Vec<File>,
+    /// Files whose checksum couldn't be computed, with the I/O error.
+    pub unreadable_files: Vec<UnreadableFile>,
+    /// Staged uploads left behind in `upload/tmp`.
+    pub staged_files: Vec<String>,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnreadableFile {
+    pub attachment: File,
+    pub error: String,
+}
+
+/// Content types accepted for attachments, read from `ATTACHMENT_ALLOWED_TYPES` and
+/// `ATTACHMENT_DENIED_TYPES` (comma separated, `image/*` style wildcards allowed).
+/// An empty allow list accepts everything that isn't denied.
+pub struct AttachmentPolicy {
+    pub allowed_types: Vec<String>,
+    pub denied_types: Vec<String>,
+}
+
+impl AttachmentPolicy {
+    pub fn from_env() -> Self {
+        AttachmentPolicy {
+            allowed_types: Self::types_from_env("ATTACHMENT_ALLOWED_TYPES"),
+            denied_types: Self::types_from_env("ATTACHMENT_DENIED_TYPES"),
+        }
+    }
+
+    pub fn permits(&self, content_type: &str) -> bool {
+        let content_type = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+
+        if self.denied_types.iter().any(|t| Self::matches(t, &content_type)) {
+            return false;
+        }
+
+        self.allowed_types.is_empty() || self.allowed_types.iter().any(|t| Self::matches(t, &content_type))
+    }
+
+    fn matches(pattern: &str, content_type: &str) -> bool {
+        match pattern.strip_suffix("/*") {
+            Some(prefix) => content_type.split('/').next() == Some(prefix),
+            None => pattern == content_type,
+        }
+    }
+
+    fn types_from_env(key: &str) -> Vec<String> {
+        std::env::var(key).unwrap_or_default()
+            .split(',')
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect()
+    }
+}
+
+/// Value of the `Content-Length` header, used to reject uploads before their body is read.
+pub struct ContentLength(pub u64);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ContentLength {
+    type Error = ();
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.headers().get_one("Content-Length").and_then(|l| l.parse().ok()) {
+            Some(length) => Outcome::Success(ContentLength(length)),
+            None => Outcome::Forward(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(used: i64, quota: i64) -> StorageUsage {
+        StorageUsage { used, quota, files: 0, max_files_per_item: 10, plan: String::from("free") }
+    }
+
+    #[test]
+    fn remaining_is_what_the_quota_leaves() {
+        assert_eq!(usage(0, 1000).remaining(), 1000);
+        assert_eq!(usage(400, 1000).remaining(), 600);
+        assert_eq!(usage(1000, 1000).remaining(), 0);
+    }
+
+    #[test]
+    fn remaining_is_never_negative() {
+        // Quotas can be lowered below what a user already stores.
+        assert_eq!(usage(1500, 1000).remaining(), 0);
+        assert_eq!(usage(10, 0).remaining(), 0);
+    }
+}
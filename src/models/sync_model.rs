@@ -0,0 +1,45 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::api_credential_model::ApiCredential;
+use crate::models::identity_model::Identity;
+use crate::models::item_model::ItemType;
+use crate::models::login_model::Login;
+use crate::models::payment_model::Payment;
+use crate::models::secured_note::SecuredNote;
+use crate::models::software_license_model::SoftwareLicense;
+use crate::models::ssh_key_model::SshKey;
+use crate::models::wifi_network_model::WifiNetwork;
+
+/// An item that was deleted, or that the user can no longer see.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub item_type: ItemType,
+    pub item_id: i32,
+}
+
+/// Everything that changed since the revision the client asked for. `revision` is what to
+/// ask for next time. With `full` set, the lists hold the whole vault and the client should
+/// replace its cache rather than apply them.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub revision: i64,
+    pub full: bool,
+    pub logins: Vec<Login>,
+    pub payments: Vec<Payment>,
+    pub secured_notes: Vec<SecuredNote>,
+    pub identities: Vec<Identity>,
+    pub ssh_keys: Vec<SshKey>,
+    pub api_credentials: Vec<ApiCredential>,
+    pub wifi_networks: Vec<WifiNetwork>,
+    pub software_licenses: Vec<SoftwareLicense>,
+    pub deleted: Vec<Tombstone>,
+}
+
+impl Display for SyncResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
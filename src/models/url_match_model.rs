@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use regex::Regex;
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::{Host, Url};
+use validator::{Validate, ValidationError};
+
+use crate::models::login_model::Login;
+use crate::models::validation_model::validate_list;
+
+/// How the linked websites of a login are compared with the page being filled in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchStrategy {
+    /// Same registrable domain, e.g. `login.example.co.uk` and `www.example.co.uk`, or
+    /// domains of one equivalent domain group.
+    #[default]
+    BaseDomain,
+    /// Same host, any port.
+    Host,
+    /// Same host and port.
+    HostAndPort,
+    /// The page URL starts with the linked website.
+    StartsWith,
+    /// The linked website is a regex the whole page URL has to match; it's anchored at both
+    /// ends.
+    Regex,
+    /// Never suggested for autofill.
+    Never,
+}
+
+impl MatchStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchStrategy::BaseDomain => "base_domain",
+            MatchStrategy::Host => "host",
+            MatchStrategy::HostAndPort => "host_and_port",
+            MatchStrategy::StartsWith => "starts_with",
+            MatchStrategy::Regex => "regex",
+            MatchStrategy::Never => "never",
+        }
+    }
+
+    /// How well `website` matches `page`, if at all. Websites that don't parse never match,
+    /// neither do regexes missing from `patterns`.
+    pub fn matches(&self, website: &str, page: &PageUrl, equivalent_domains: &[EquivalentDomains], patterns: &CompiledPatterns) -> Option<MatchStrength> {
+        match self {
+            MatchStrategy::Never => None,
+            MatchStrategy::Regex => patterns.get(website)
+                .filter(|regex| regex.is_match(page.url.as_str()))
+                .map(|_| MatchStrength::Exact),
+            MatchStrategy::StartsWith => {
+                let prefix = Url::parse(website).map(String::from).unwrap_or_else(|_| website.to_string());
+                page.url.as_str().starts_with(&prefix).then_some(MatchStrength::Exact)
+            }
+            strategy => {
+                let website = PageUrl::parse(website)?;
+                let strength = if website.host == page.host && website.url.port_or_known_default() == page.url.port_or_known_default() {
+                    MatchStrength::HostAndPort
+                } else if website.host == page.host {
+                    MatchStrength::Host
+                } else if website.base_domain == page.base_domain {
+                    MatchStrength::BaseDomain
+                } else if equivalent_domains.iter().any(|group| group.contains(&website.base_domain) && group.contains(&page.base_domain)) {
+                    MatchStrength::EquivalentDomain
+                } else {
+                    return None;
+                };
+
+                let required = match strategy {
+                    MatchStrategy::HostAndPort => MatchStrength::HostAndPort,
+                    MatchStrategy::Host => MatchStrength::Host,
+                    _ => MatchStrength::EquivalentDomain,
+                };
+                (strength >= required).then_some(strength)
+            }
+        }
+    }
+}
+
+impl FromStr for MatchStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base_domain" => Ok(MatchStrategy::BaseDomain),
+            "host" => Ok(MatchStrategy::Host),
+            "host_and_port" => Ok(MatchStrategy::HostAndPort),
+            "starts_with" => Ok(MatchStrategy::StartsWith),
+            "regex" => Ok(MatchStrategy::Regex),
+            "never" => Ok(MatchStrategy::Never),
+            _ => Err(format!("Unknown match strategy: {}", s)),
+        }
+    }
+}
+
+/// Compiles a linked website of a regex login so it has to match the whole page URL.
+pub fn anchored_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+/// The regexes of the logins being matched, each compiled once.
+#[derive(Default)]
+pub struct CompiledPatterns(HashMap<String, Regex>);
+
+impl CompiledPatterns {
+    /// Compiles the linked websites of the logins matched by regex. Invalid ones are left out.
+    pub fn of(logins: &[Login]) -> Self {
+        let patterns = logins.iter()
+            .filter(|login| login.match_strategy == MatchStrategy::Regex)
+            .flat_map(|login| &login.linked_websites)
+            .filter_map(|pattern| anchored_regex(pattern).ok().map(|regex| (pattern.clone(), regex)))
+            .collect();
+
+        CompiledPatterns(patterns)
+    }
+
+    pub fn get(&self, pattern: &str) -> Option<&Regex> {
+        self.0.get(pattern)
+    }
+}
+
+/// How closely a login matched, weakest first. Better matches are listed first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchStrength {
+    EquivalentDomain,
+    BaseDomain,
+    Host,
+    HostAndPort,
+    /// Matched by a prefix or regex written for this very page.
+    Exact,
+}
+
+/// A URL with its host and registrable domain, per the public suffix list. Hosts without a
+/// registrable domain, e.g. IP addresses or `localhost`, are their own base domain.
+pub struct PageUrl {
+    pub url: Url,
+    pub host: String,
+    pub base_domain: String,
+}
+
+impl PageUrl {
+    /// Parses `url`, taking `https` for URLs without a scheme. None for URLs without a host.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) if !url.contains("://") => Url::parse(&format!("https://{}", url)).ok()?,
+            Err(_) => return None,
+        };
+
+        let (host, base_domain) = match url.host()? {
+            Host::Domain(domain) => {
+                let domain = domain.trim_end_matches('.').to_string();
+                let base_domain = psl::domain_str(&domain).unwrap_or(&domain).to_string();
+                (domain, base_domain)
+            }
+            host => (host.to_string(), host.to_string()),
+        };
+
+        Some(PageUrl { url, host, base_domain })
+    }
+}
+
+/// A login suggested for a page.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct LoginMatch {
+    pub strength: MatchStrength,
+    pub login: Login,
+}
+
+/// Domains a user treats as one site, stored as their base domains.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct EquivalentDomains {
+    pub id: i32,
+    pub domains: Vec<String>,
+}
+
+impl EquivalentDomains {
+    pub fn contains(&self, base_domain: &str) -> bool {
+        self.domains.iter().any(|domain| domain == base_domain)
+    }
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct EquivalentDomainsDto {
+    #[validate(length(min = 2), custom = "validate_domain_list")]
+    pub domains: Vec<String>,
+}
+
+impl EquivalentDomainsDto {
+    /// The distinct base domains of the group, e.g. `google.com` for `www.google.com`.
+    pub fn base_domains(&self) -> Vec<String> {
+        let mut base_domains: Vec<String> = vec![];
+        for domain in &self.domains {
+            let base_domain = PageUrl::parse(domain).map(|url| url.base_domain).unwrap_or_else(|| domain.to_lowercase());
+            if !base_domains.contains(&base_domain) {
+                base_domains.push(base_domain);
+            }
+        }
+        base_domains
+    }
+}
+
+/// Like `validate_list`, where every entry is a domain name.
+fn validate_domain_list(domains: &[String]) -> Result<(), ValidationError> {
+    if let Some(domain) = domains.iter().find(|domain| !matches!(Host::parse(domain), Ok(Host::Domain(_)))) {
+        let mut err = ValidationError::new("domain");
+        err.message = Some(format!("{} is not a valid domain name.", domain).into());
+        return Err(err);
+    }
+
+    validate_list(domains)
+}
+
+/// Like `validate_list`, where every entry is a regex.
+pub fn validate_regex_list(patterns: &[String]) -> Result<(), ValidationError> {
+    if let Some((pattern, err)) = patterns.iter().find_map(|pattern| anchored_regex(pattern).err().map(|err| (pattern, err))) {
+        let mut error = ValidationError::new("regex");
+        error.message = Some(format!("{} is not a valid regex: {}", pattern, err).into());
+        return Err(error);
+    }
+
+    validate_list(patterns)
+}
+
+impl Display for LoginMatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for EquivalentDomains {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for EquivalentDomainsDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
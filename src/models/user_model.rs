@@ -2,12 +2,16 @@ use jsonwebtoken::{DecodingKey, Validation};
 use rocket::http::Status;
 use rocket::Request;
 use rocket::request::{FromRequest, Outcome};
+use validator::Validate;
 
 use crate::{models::*, persistence::users_dao::UsersDao};
 use crate::handlers::auth_handler::{Token, TokenError};
 use crate::models::auth_model::TokenClaims;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
 use crate::persistence::auth_dao::AuthDao;
 
+pub const MIN_USERNAME_LENGTH: usize = 3;
+
 #[derive(Serialize, Deserialize)]
 pub struct User {
     pub id: i32,
@@ -16,22 +20,32 @@ pub struct User {
     pub last_name: String,
     pub email: String,
     pub created_at: String,
+    #[serde(default)]
+    pub email_verified: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Validate)]
 pub struct UserDto {
+    #[validate(length(min = "MIN_USERNAME_LENGTH", max = "MAX_VARCHAR_LENGTH"))]
     pub username: String,
     pub password: String,
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
     pub first_name: String,
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
     pub last_name: String,
+    #[validate(email, length(max = "MAX_VARCHAR_LENGTH"))]
     pub email: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Validate)]
 pub struct UserUpdateDto {
+    #[validate(length(min = "MIN_USERNAME_LENGTH", max = "MAX_VARCHAR_LENGTH"))]
     pub username: Option<String>,
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
     pub first_name: Option<String>,
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
     pub last_name: Option<String>,
+    #[validate(email, length(max = "MAX_VARCHAR_LENGTH"))]
     pub email: Option<String>,
 }
 
@@ -61,7 +75,10 @@ impl<'r> FromRequest<'r> for User {
         let decoded_claims = jsonwebtoken::decode::<TokenClaims>(authorization, decoding_key, &Validation::default());
 
         let user_id = match decoded_claims {
-            Ok(token_claims) => token_claims.claims.sub,
+            Ok(token_claims) => match auth_dao.token_revoked(&token_claims.claims).await {
+                Ok(false) => token_claims.claims.sub,
+                _ => return Outcome::Failure((Status::Unauthorized, TokenError::Invalid))
+            },
             Err(e) => return Outcome::Failure((Status::Unauthorized, TokenError::Invalid))
         };
 
@@ -72,3 +89,23 @@ impl<'r> FromRequest<'r> for User {
         };
     }
 }
+
+pub struct Admin(pub User);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = TokenError;
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let user = match request.guard::<User>().await {
+            Outcome::Success(user) => user,
+            Outcome::Failure(failure) => return Outcome::Failure(failure),
+            Outcome::Forward(f) => return Outcome::Forward(f),
+        };
+        let user_dao = request.rocket().state::<Box<dyn UsersDao + Sync + Send>>().unwrap();
+
+        match user_dao.is_admin(user.id).await {
+            Ok(true) => Outcome::Success(Admin(user)),
+            _ => Outcome::Failure((Status::Forbidden, TokenError::Forbidden)),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+/// What an emailed token may be used for, and how long it is valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenPurpose {
+    VerifyEmail,
+    ChangeEmail,
+    ResetPassword,
+}
+
+impl TokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::VerifyEmail => "verify_email",
+            TokenPurpose::ChangeEmail => "change_email",
+            TokenPurpose::ResetPassword => "reset_password",
+        }
+    }
+
+    pub fn valid_minutes(&self) -> i32 {
+        match self {
+            TokenPurpose::VerifyEmail | TokenPurpose::ChangeEmail => 48 * 60,
+            TokenPurpose::ResetPassword => 60,
+        }
+    }
+}
+
+impl FromStr for TokenPurpose {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "verify_email" => Ok(TokenPurpose::VerifyEmail),
+            "change_email" => Ok(TokenPurpose::ChangeEmail),
+            "reset_password" => Ok(TokenPurpose::ResetPassword),
+            _ => Err(format!("Unknown token purpose: {}", s)),
+        }
+    }
+}
+
+/// A redeemed token: whose it was and the address it was sent to.
+#[derive(Debug)]
+pub struct UserToken {
+    pub user_id: i32,
+    pub email: String,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct TokenDto {
+    pub token: String,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct PasswordForgotDto {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Error, Serialize, Deserialize)]
+pub struct PasswordResetDto {
+    pub token: String,
+    pub password: String,
+}
+
+impl Display for TokenDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for PasswordForgotDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Debug for PasswordResetDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordResetDto").field("token", &self.token).field("password", &"<redacted>").finish()
+    }
+}
+
+impl Display for PasswordResetDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
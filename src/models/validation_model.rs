@@ -0,0 +1,166 @@
+use std::ops::{Deref, DerefMut};
+
+use rocket::{Data, Request};
+use rocket::data::{FromData, Outcome};
+use rocket::http::Status;
+use rocket::serde::Deserialize;
+use rocket::serde::json::{self, Json};
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::models::error_model::{ErrorCode, ErrorResponse, FieldError};
+
+/// Longest value a `varchar(255)` column takes.
+pub const MAX_VARCHAR_LENGTH: u64 = 255;
+
+/// A JSON body that passed its `Validate` rules. Use it in place of `Json<T>`; invalid bodies
+/// are answered with a 422 listing every failing field before the handler runs.
+#[derive(Debug)]
+pub struct Validated<T>(pub T);
+
+/// Why a body was rejected, left in the request's local cache for the error catchers.
+pub struct RejectedBody(pub Option<ErrorResponse>);
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Validated<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: Deserialize<'r> + Validate> FromData<'r> for Validated<T> {
+    type Error = ErrorResponse;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let value = match Json::<T>::from_data(request, data).await {
+            Outcome::Success(Json(value)) => value,
+            Outcome::Failure((status, err)) => {
+                let message = match err {
+                    json::Error::Parse(_, err) => err.to_string(),
+                    json::Error::Io(err) => err.to_string(),
+                };
+                let response = ErrorResponse::new(ErrorCode::from_status(status), message);
+                request.local_cache(|| RejectedBody(Some(response.clone())));
+                return Outcome::Failure((status, response));
+            }
+            Outcome::Forward(data) => return Outcome::Forward(data),
+        };
+
+        match value.validate() {
+            Ok(()) => Outcome::Success(Validated(value)),
+            Err(errors) => {
+                let response = ErrorResponse::new(ErrorCode::ValidationFailed, "Validation failed.")
+                    .fields(field_errors(&errors));
+                request.local_cache(|| RejectedBody(Some(response.clone())));
+                Outcome::Failure((Status::UnprocessableEntity, response))
+            }
+        }
+    }
+}
+
+/// Flattens `errors` into one entry per failing rule, sorted by field. Errors from `schema`
+/// validators that span several fields have the field `__all__`.
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields = vec![];
+    collect_field_errors("", errors, &mut fields);
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+fn collect_field_errors(prefix: &str, errors: &ValidationErrors, fields: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let field = format!("{}{}", prefix, field);
+        match kind {
+            ValidationErrorsKind::Field(errors) => fields.extend(errors.iter().map(|error| field_error(&field, error))),
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(&format!("{}.", field), errors, fields),
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    collect_field_errors(&format!("{}[{}].", field, index), errors, fields);
+                }
+            }
+        }
+    }
+}
+
+/// A failed check a handler ran itself, reported like the declarative ones.
+pub fn field_error(field: &str, error: &ValidationError) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        code: error.code.to_string(),
+        message: describe(error),
+    }
+}
+
+/// The rule's own message, or one built from the rule and its parameters.
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    // Range bounds are stored as floats; show whole numbers without the fraction.
+    let param = |name: &str| error.params.get(name).map(|value| match value.as_f64() {
+        Some(number) if number.fract() == 0.0 => format!("{}", number as i64),
+        _ => value.to_string(),
+    });
+    match error.code.as_ref() {
+        "length" => match (param("min"), param("max")) {
+            (Some(min), Some(max)) => format!("Must be between {} and {} characters long.", min, max),
+            (Some(min), None) => format!("Must be at least {} characters long.", min),
+            (None, Some(max)) => format!("Must be at most {} characters long.", max),
+            (None, None) => String::from("Has the wrong length."),
+        },
+        "range" => match (param("min"), param("max")) {
+            (Some(min), Some(max)) => format!("Must be between {} and {}.", min, max),
+            (Some(min), None) => format!("Must be at least {}.", min),
+            (None, Some(max)) => format!("Must be at most {}.", max),
+            (None, None) => String::from("Is out of range."),
+        },
+        "email" => String::from("Must be a valid email address."),
+        "url" => String::from("Must be a valid URL."),
+        code => format!("Failed the {} check.", code),
+    }
+}
+
+/// An email address, or nothing at all.
+pub fn validate_optional_email(email: &str) -> Result<(), ValidationError> {
+    if email.is_empty() || validator::validate_email(email) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("email"))
+    }
+}
+
+/// Values stored comma separated in one `varchar(255)` column.
+pub fn validate_list(values: &[String]) -> Result<(), ValidationError> {
+    if values.iter().any(|value| value.contains(',')) {
+        let mut err = ValidationError::new("list");
+        err.message = Some("Entries can't contain commas.".into());
+        return Err(err);
+    }
+
+    if values.join(",").chars().count() as u64 > MAX_VARCHAR_LENGTH {
+        let mut err = ValidationError::new("length");
+        err.message = Some(format!("Must be at most {} characters long in total.", MAX_VARCHAR_LENGTH).into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Like `validate_list`, where every entry is a URL.
+pub fn validate_url_list(urls: &[String]) -> Result<(), ValidationError> {
+    if let Some(url) = urls.iter().find(|url| !validator::validate_url(url.as_str())) {
+        let mut err = ValidationError::new("url");
+        err.message = Some(format!("{} is not a valid URL.", url).into());
+        return Err(err);
+    }
+
+    validate_list(urls)
+}
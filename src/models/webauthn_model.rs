@@ -0,0 +1,141 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+use webauthn_rs::prelude::{CreationChallengeResponse, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse};
+
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// Passkeys replace the master password; second factor keys are asked for after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialUsage {
+    Passkey,
+    SecondFactor,
+}
+
+impl CredentialUsage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CredentialUsage::Passkey => "passkey",
+            CredentialUsage::SecondFactor => "second_factor",
+        }
+    }
+}
+
+impl FromStr for CredentialUsage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "passkey" => Ok(CredentialUsage::Passkey),
+            "second_factor" => Ok(CredentialUsage::SecondFactor),
+            _ => Err(format!("Unknown credential usage: {}", s)),
+        }
+    }
+}
+
+/// A registered authenticator, without its key material.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct WebauthnCredential {
+    pub id: i32,
+    pub name: String,
+    pub usage: CredentialUsage,
+    pub sign_count: i64,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct CredentialNameDto {
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub name: String,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize, Validate)]
+pub struct RegistrationStartDto {
+    #[validate(length(min = 1, max = "MAX_VARCHAR_LENGTH"))]
+    pub name: String,
+    pub usage: CredentialUsage,
+}
+
+/// Options for `navigator.credentials.create`, and the id to finish the ceremony with.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct RegistrationStart {
+    pub ceremony_id: String,
+    pub options: CreationChallengeResponse,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct RegistrationFinishDto {
+    pub ceremony_id: String,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct PasskeyLoginDto {
+    pub username: String,
+}
+
+/// Options for `navigator.credentials.get`, and the id to finish the ceremony with.
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct AuthenticationStart {
+    pub ceremony_id: String,
+    pub options: RequestChallengeResponse,
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct AuthenticationFinishDto {
+    pub ceremony_id: String,
+    pub credential: PublicKeyCredential,
+}
+
+impl Display for WebauthnCredential {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for CredentialNameDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for RegistrationStartDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for RegistrationStart {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for RegistrationFinishDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for PasskeyLoginDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for AuthenticationStart {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for AuthenticationFinishDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::{Validate, ValidationError};
+
+use crate::models::custom_field_model::{blank_hidden_fields, CustomField, CustomFieldDto, MAX_CUSTOM_FIELDS, validate_unlinked_fields};
+use crate::models::item_model::{ItemType, VaultItem};
+use crate::models::share_model::Permission;
+use crate::models::validation_model::MAX_VARCHAR_LENGTH;
+
+/// SSIDs are at most 32 bytes long.
+pub const MAX_SSID_BYTES: usize = 32;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WifiSecurity {
+    /// No password.
+    Open,
+    Wep,
+    Wpa,
+    #[default]
+    Wpa2,
+    Wpa3,
+    /// 802.1X, users sign in with their own credentials, e.g. kept in custom fields.
+    Enterprise,
+}
+
+impl WifiSecurity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WifiSecurity::Open => "open",
+            WifiSecurity::Wep => "wep",
+            WifiSecurity::Wpa => "wpa",
+            WifiSecurity::Wpa2 => "wpa2",
+            WifiSecurity::Wpa3 => "wpa3",
+            WifiSecurity::Enterprise => "enterprise",
+        }
+    }
+}
+
+impl FromStr for WifiSecurity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(WifiSecurity::Open),
+            "wep" => Ok(WifiSecurity::Wep),
+            "wpa" => Ok(WifiSecurity::Wpa),
+            "wpa2" => Ok(WifiSecurity::Wpa2),
+            "wpa3" => Ok(WifiSecurity::Wpa3),
+            "enterprise" => Ok(WifiSecurity::Enterprise),
+            _ => Err(format!("Unknown Wi-Fi security type: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub struct WifiNetwork {
+    pub id: i32,
+    pub name: String,
+    pub ssid: String,
+    pub security_type: WifiSecurity,
+    pub password: String,
+    /// The network doesn't broadcast its SSID.
+    pub hidden: bool,
+    pub note: String,
+    pub color: String,
+    pub created_at: String,
+    pub modified_at: String,
+    pub custom_fields: Vec<CustomField>,
+    /// Bumped by every update, sent as the `ETag`.
+    pub revision: i64,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub permission: Permission,
+}
+
+/// The password is checked against the security type in the handlers, as on updates either
+/// of them may come from the stored network.
+#[derive(Debug, Clone, Error, Serialize, Deserialize, Validate)]
+pub struct WifiNetworkDto {
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub name: Option<String>,
+    #[validate(custom = "validate_ssid")]
+    pub ssid: Option<String>,
+    pub security_type: Option<WifiSecurity>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub password: Option<String>,
+    pub hidden: Option<bool>,
+    pub note: Option<String>,
+    #[validate(length(max = "MAX_VARCHAR_LENGTH"))]
+    pub color: Option<String>,
+    /// Replaces all custom fields when sent.
+    #[validate(length(max = "MAX_CUSTOM_FIELDS"), custom = "validate_unlinked_fields")]
+    #[validate]
+    pub custom_fields: Option<Vec<CustomFieldDto>>,
+}
+
+fn validate_ssid(ssid: &str) -> Result<(), ValidationError> {
+    if ssid.is_empty() || ssid.len() > MAX_SSID_BYTES {
+        let mut err = ValidationError::new("length");
+        err.message = Some(format!("Must be between 1 and {} bytes long.", MAX_SSID_BYTES).into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Whether `password` is a key the security type takes: none for open networks, 5 or 13
+/// characters or 10 or 26 hex digits for WEP, and a passphrase of 8 to 63 characters or 64
+/// hex digits for WPA. Enterprise networks take anything.
+pub fn validate_wifi_password(security_type: WifiSecurity, password: &str) -> Result<(), ValidationError> {
+    let hex = !password.is_empty() && password.chars().all(|c| c.is_ascii_hexdigit());
+    let length = password.chars().count();
+    let message = match security_type {
+        WifiSecurity::Open if !password.is_empty() => "Open networks have no password.",
+        WifiSecurity::Wep if !(matches!(length, 5 | 13) || (hex && matches!(length, 10 | 26))) => {
+            "WEP keys are 5 or 13 characters, or 10 or 26 hex digits."
+        }
+        WifiSecurity::Wpa | WifiSecurity::Wpa2 | WifiSecurity::Wpa3 if !((8..=63).contains(&length) || (hex && length == 64)) => {
+            "WPA passphrases are 8 to 63 characters, or 64 hex digits."
+        }
+        _ => return Ok(()),
+    };
+
+    let mut err = ValidationError::new("wifi_password");
+    err.message = Some(message.into());
+    Err(err)
+}
+
+impl VaultItem for WifiNetwork {
+    type Dto = WifiNetworkDto;
+
+    const ITEM_TYPE: ItemType = ItemType::WifiNetwork;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    fn permission(&self) -> Permission {
+        self.permission
+    }
+
+    fn set_permission(&mut self, permission: Permission) {
+        self.shared = permission != Permission::Owner;
+        self.permission = permission;
+    }
+
+    fn custom_fields_mut(&mut self) -> &mut Vec<CustomField> {
+        &mut self.custom_fields
+    }
+
+    /// Blanks the password and hidden custom fields for members with hide-passwords access.
+    fn hide_secrets(&mut self) {
+        self.password = String::new();
+        blank_hidden_fields(&mut self.custom_fields);
+    }
+}
+
+impl Display for WifiNetwork {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
+
+impl Display for WifiNetworkDto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(format!("{:?}", self).as_str())
+    }
+}
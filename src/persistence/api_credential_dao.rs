@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::api_credential_model::{ApiCredential, ApiCredentialDto};
+use crate::models::DBError;
+use crate::persistence::item_dao::{commit_item, fill_item, fill_items, ItemDao};
+
+pub struct ApiCredentialDaoImpl {
+    db: PgPool,
+}
+
+impl ApiCredentialDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        ApiCredentialDaoImpl { db }
+    }
+}
+
+struct ApiCredentialRecord {
+    id: i32,
+    name: String,
+    api_key: String,
+    secret: String,
+    endpoint: String,
+    expires_on: Option<sqlx::types::time::Date>,
+    note: String,
+    color: String,
+    revision: i64,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    modified_at: sqlx::types::time::PrimitiveDateTime,
+    permission: String,
+}
+
+impl TryFrom<ApiCredentialRecord> for ApiCredential {
+    type Error = DBError;
+
+    /// Custom fields are loaded separately.
+    fn try_from(record: ApiCredentialRecord) -> Result<Self, Self::Error> {
+        Ok(ApiCredential {
+            id: record.id,
+            name: record.name,
+            api_key: record.api_key,
+            secret: record.secret,
+            endpoint: record.endpoint,
+            expires_on: record.expires_on.map(|d| d.to_string()),
+            note: record.note,
+            color: record.color,
+            created_at: record.created_at.to_string(),
+            modified_at: record.modified_at.to_string(),
+            custom_fields: vec![],
+            revision: record.revision,
+            shared: record.permission != "owner",
+            permission: record.permission.parse().map_err(DBError::InvalidPermission)?,
+        })
+    }
+}
+
+#[async_trait]
+impl ItemDao<ApiCredential> for ApiCredentialDaoImpl {
+    async fn create_item(&self, api_credential: ApiCredentialDto, owner_id: i32) -> Result<ApiCredential, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(ApiCredentialRecord, r#"
+            INSERT INTO api_credentials (owner_id, name, api_key, secret, endpoint, expires_on, note, color)
+            VALUES ($1, COALESCE($2, ''), COALESCE($3, ''), COALESCE($4, ''), COALESCE($5, ''), $6::date,
+                    COALESCE($7, ''), COALESCE($8, 'red'))
+            RETURNING id, name, api_key, secret, endpoint, expires_on, note, color, revision, created_at, modified_at,
+                      'owner' as "permission!"
+        "#,
+            owner_id,
+            api_credential.name,
+            api_credential.api_key,
+            api_credential.secret,
+            api_credential.endpoint,
+            api_credential.expires_on.flatten().map(|d| d.to_string()) as _,
+            api_credential.note,
+            api_credential.color,
+        ).fetch_one(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        commit_item(tx, ApiCredential::try_from(record)?, api_credential.custom_fields).await
+    }
+
+    async fn get_item(&self, id: i32) -> Result<ApiCredential, DBError> {
+        let record = sqlx::query_as!(ApiCredentialRecord, r#"
+            SELECT id, name, api_key, secret, endpoint, expires_on, note, color, revision, created_at, modified_at,
+                   'owner' as "permission!"
+            FROM api_credentials WHERE id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        fill_item(&self.db, ApiCredential::try_from(record)?).await
+    }
+
+    async fn get_items(&self, user_id: i32, since: Option<i64>) -> Result<Vec<ApiCredential>, DBError> {
+        let records = sqlx::query_as!(ApiCredentialRecord, r#"
+            SELECT a.id, a.name, a.api_key, a.secret, a.endpoint, a.expires_on, a.note, a.color, a.revision,
+                   a.created_at, a.modified_at, COALESCE(s.permission, 'owner') as "permission!"
+            FROM api_credentials a
+            LEFT JOIN LATERAL (
+                SELECT permission FROM item_permissions
+                WHERE item_type = 'api_credential' AND item_id = a.id AND user_id = $1
+                ORDER BY rank DESC LIMIT 1
+            ) s ON a.owner_id IS DISTINCT FROM $1
+            WHERE (a.owner_id = $1 OR s.permission IS NOT NULL)
+              AND ($2::bigint IS NULL OR EXISTS(
+                  SELECT 1 FROM sync_changes c
+                  WHERE c.user_id = $1 AND c.item_type = 'api_credential' AND c.item_id = a.id AND c.revision > $2
+              ))
+            ORDER BY a.id
+        "#, user_id, since).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let api_credentials = records.into_iter().map(ApiCredential::try_from).collect::<Result<Vec<_>, _>>()?;
+        fill_items(&self.db, api_credentials).await
+    }
+
+    async fn update_item(&self, id: i32, api_credential: ApiCredentialDto, if_match: Option<i64>) -> Result<ApiCredential, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(ApiCredentialRecord, r#"
+            UPDATE api_credentials SET name = COALESCE($1, name), api_key = COALESCE($2, api_key),
+                                       secret = COALESCE($3, secret), endpoint = COALESCE($4, endpoint),
+                                       expires_on = CASE WHEN $5 THEN $6::date ELSE expires_on END,
+                                       note = COALESCE($7, note), color = COALESCE($8, color), revision = revision + 1
+            WHERE id = $9 AND ($10::bigint IS NULL OR revision = $10)
+            RETURNING id, name, api_key, secret, endpoint, expires_on, note, color, revision, created_at, modified_at,
+                      'owner' as "permission!"
+        "#,
+            api_credential.name,
+            api_credential.api_key,
+            api_credential.secret,
+            api_credential.endpoint,
+            api_credential.expires_on.is_some(),
+            api_credential.expires_on.flatten().map(|d| d.to_string()) as _,
+            api_credential.note,
+            api_credential.color,
+            id,
+            if_match
+        ).fetch_optional(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .ok_or(DBError::StaleRevision)?;
+
+        commit_item(tx, ApiCredential::try_from(record)?, api_credential.custom_fields).await
+    }
+
+    async fn delete_item(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM api_credentials WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::attachment_model::{File, FileDto};
+use crate::models::DBError;
+use crate::models::item_model::ItemType;
+use crate::models::storage_model::StorageUsage;
+
+/// Serializes uploads of one user, and uploads to one item, so concurrent uploads can't
+/// together exceed the storage quota or the attachments per item.
+const ATTACHMENT_QUOTA_LOCK: i32 = 0x61747471;
+
+#[async_trait]
+pub trait AttachmentDao {
+    /// Fails with `LimitExceeded` if the item has no attachment left or the file doesn't fit in
+    /// the owner's quota, counting what was stored since `limits` was read.
+    async fn save_file(&self, owner_id: i32, file: FileDto, item_type: ItemType, item_id: i32, limits: &StorageUsage) -> Result<File, DBError>;
+    async fn get_attachment(&self, id: i32) -> Result<File, DBError>;
+    async fn get_attachments(&self, item_type: ItemType, item_ids: &[i32]) -> Result<Vec<File>, DBError>;
+    async fn count_attachments(&self, item_type: ItemType, item_id: i32) -> Result<i64, DBError>;
+    async fn delete_attachment(&self, id: i32) -> Result<(), DBError>;
+    /// Every attachment, paired with whether it was created more than `settled_for` ago.
+    async fn get_all_attachments(&self, settled_for: Duration) -> Result<Vec<(File, bool)>, DBError>;
+    async fn delete_attachments(&self, ids: &[i32]) -> Result<(), DBError>;
+}
+
+pub struct AttachmentDaoImpl {
+    db: PgPool,
+}
+
+impl AttachmentDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        AttachmentDaoImpl { db }
+    }
+}
+
+struct FileRecord {
+    id: i32,
+    name: String,
+    owner_id: Option<i32>,
+    item_type: String,
+    item_id: i32,
+    size: i32,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    file_type: String,
+    checksum: Option<String>,
+}
+
+impl TryFrom<FileRecord> for File {
+    type Error = DBError;
+
+    fn try_from(record: FileRecord) -> Result<Self, Self::Error> {
+        Ok(File {
+            id: record.id,
+            name: record.name,
+            owner_id: record.owner_id.unwrap(),
+            item_type: record.item_type.parse().map_err(DBError::InvalidItemType)?,
+            item_id: record.item_id,
+            size: record.size,
+            created_at: record.created_at.to_string(),
+            file_type: record.file_type,
+            checksum: record.checksum,
+        })
+    }
+}
+
+#[async_trait]
+impl AttachmentDao for AttachmentDaoImpl {
+    async fn save_file(&self, owner_id: i32, file: FileDto, item_type: ItemType, item_id: i32, limits: &StorageUsage) -> Result<File, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!("SELECT pg_advisory_xact_lock($1, $2)", ATTACHMENT_QUOTA_LOCK, owner_id)
+            .execute(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+        sqlx::query!("SELECT pg_advisory_xact_lock(hashtext($1), $2)", item_type.as_str(), item_id)
+            .execute(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let stored = sqlx::query!(r#"
+            SELECT (SELECT COUNT(*) FROM attachments WHERE item_type = $1 AND item_id = $2) as "item_files!",
+                   (SELECT COALESCE(SUM(size), 0) FROM attachments WHERE owner_id = $3)
+                       + (SELECT COALESCE(SUM(file_size), 0) FROM sends WHERE owner_id = $3) as "used!"
+        "#, item_type.as_str(), item_id, owner_id).fetch_one(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        if stored.item_files >= limits.max_files_per_item as i64 {
+            return Err(DBError::LimitExceeded(format!("Item already has the maximum of {} attachments.", limits.max_files_per_item)));
+        }
+        if stored.used + file.size as i64 > limits.quota {
+            return Err(DBError::LimitExceeded(format!("Upload exceeds your storage quota ({} of {} bytes used).", stored.used, limits.quota)));
+        }
+
+        let record = sqlx::query_as!(FileRecord, r#"
+            INSERT INTO attachments (name, size, type, item_type, item_id, owner_id, checksum)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, name, owner_id, item_type, item_id, size, created_at, type as file_type, checksum
+        "#,
+        file.name, file.size, file.file_type, item_type.as_str(), item_id, owner_id, file.checksum
+        ).fetch_one(&mut tx).await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        File::try_from(record)
+    }
+
+    async fn get_attachment(&self, id: i32) -> Result<File, DBError> {
+        let record = sqlx::query_as!(FileRecord, r#"
+            SELECT id, name, owner_id, item_type, item_id, size, created_at, type as file_type, checksum
+            FROM attachments WHERE id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        File::try_from(record)
+    }
+
+    async fn get_attachments(&self, item_type: ItemType, item_ids: &[i32]) -> Result<Vec<File>, DBError> {
+        let records = sqlx::query_as!(FileRecord, r#"
+            SELECT id, name, owner_id, item_type, item_id, size, created_at, type as file_type, checksum
+            FROM attachments
+            WHERE item_type = $1 AND item_id = ANY($2)
+            ORDER BY id
+        "#, item_type.as_str(), item_ids).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(File::try_from).collect()
+    }
+
+    async fn count_attachments(&self, item_type: ItemType, item_id: i32) -> Result<i64, DBError> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "c!" FROM attachments WHERE item_type = $1 AND item_id = $2"#,
+            item_type.as_str(), item_id
+        ).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(count)
+    }
+
+    async fn delete_attachment(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!(r#"DELETE from attachments where id = $1"#, id)
+            .execute(&self.db).await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn get_all_attachments(&self, settled_for: Duration) -> Result<Vec<(File, bool)>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT id, name, owner_id, item_type, item_id, size, created_at, type as file_type, checksum,
+                   created_at < now() - make_interval(secs => $1) as "settled!"
+            FROM attachments ORDER BY id
+        "#, settled_for.as_secs_f64()).fetch_all(&self.db).await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        records.into_iter().map(|record| {
+            let file = File::try_from(FileRecord {
+                id: record.id,
+                name: record.name,
+                owner_id: record.owner_id,
+                item_type: record.item_type,
+                item_id: record.item_id,
+                size: record.size,
+                created_at: record.created_at,
+                file_type: record.file_type,
+                checksum: record.checksum,
+            })?;
+            Ok((file, record.settled))
+        }).collect()
+    }
+
+    async fn delete_attachments(&self, ids: &[i32]) -> Result<(), DBError> {
+        sqlx::query!(r#"DELETE from attachments where id = ANY($1)"#, ids)
+            .execute(&self.db).await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+}
@@ -17,6 +17,7 @@ pub trait AuthDao {
     async fn login(&self, credentials: Credentials, jwt_encoding_key: &EncodingKey) -> Result<(User, Token), DBError>;
     async fn logout(&self, token: Token) -> Result<(), DBError>;
     async fn token_blacklisted(&self, token: Token) -> Result<bool, DBError>;
+    async fn token_revoked(&self, claims: &TokenClaims) -> Result<bool, DBError>;
 }
 
 
@@ -47,6 +48,26 @@ impl Display for AuthError {
 }
 
 
+/// Signs a session token for `user_id`, valid for seven days or until the user's token
+/// generation moves past `generation`.
+pub fn issue_token(user_id: i32, generation: i32, jwt_encoding_key: &EncodingKey) -> Token {
+    let now = chrono::Utc::now();
+    let claims = TokenClaims {
+        sub: user_id,
+        exp: (now + chrono::Duration::days(7)).timestamp() as usize,
+        iat: now.timestamp(),
+        gen: generation,
+    };
+
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
+        &claims,
+        jwt_encoding_key,
+    ).unwrap();
+
+    Token(token)
+}
+
 #[async_trait]
 impl AuthDao for AuthDaoImpl {
     async fn login(&self, credentials: Credentials, jwt_encoding_key: &EncodingKey) -> Result<(User, Token), DBError> {
@@ -84,19 +105,11 @@ impl AuthDao for AuthDaoImpl {
             last_name: record.last_name,
             email: record.email,
             created_at: record.created_at.unwrap().to_string(),
+            email_verified: record.email_verified,
         };
-        let claims = TokenClaims {
-            sub: user.id,
-            exp: (chrono::Utc::now() + chrono::Duration::days(7)).timestamp() as usize,
-        };
-
-        let token = jsonwebtoken::encode(
-            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
-            &claims,
-            &jwt_encoding_key,
-        ).unwrap();
+        let token = issue_token(user.id, record.token_generation, jwt_encoding_key);
 
-        Ok((user, Token(token)))
+        Ok((user, token))
     }
 
     async fn logout(&self, token: Token) -> Result<(), DBError> {
@@ -121,4 +134,18 @@ impl AuthDao for AuthDaoImpl {
 
         Ok(record.c.unwrap() > 0)
     }
+
+    /// Whether the user signed out everywhere after the token was issued.
+    async fn token_revoked(&self, claims: &TokenClaims) -> Result<bool, DBError> {
+        let record = sqlx::query!(
+            r#"
+               SELECT token_generation FROM users WHERE id = $1
+            "#,
+            claims.sub
+        ).fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(claims.gen != record.token_generation)
+    }
 }
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+use crate::models::custom_field_model::{CustomField, CustomFieldDto, CustomFieldMatch, CustomFieldType, MASKED_VALUE};
+use crate::models::DBError;
+use crate::models::item_model::ItemType;
+
+#[async_trait]
+pub trait CustomFieldDao {
+    /// Fields whose name contains `name`, ignoring case, of items the user owns or was granted.
+    async fn search_custom_fields(&self, user_id: i32, name: &str) -> Result<Vec<CustomFieldMatch>, DBError>;
+}
+
+pub struct CustomFieldDaoImpl {
+    db: PgPool,
+}
+
+impl CustomFieldDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        CustomFieldDaoImpl { db }
+    }
+}
+
+#[async_trait]
+impl CustomFieldDao for CustomFieldDaoImpl {
+    async fn search_custom_fields(&self, user_id: i32, name: &str) -> Result<Vec<CustomFieldMatch>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT cf.item_type, cf.item_id, cf.name, cf.field_type
+            FROM custom_fields cf
+            WHERE strpos(lower(cf.name), lower($2)) > 0
+              AND EXISTS(
+                  SELECT 1 FROM item_users(cf.item_type, cf.item_id, item_owner(cf.item_type, cf.item_id)) u
+                  WHERE u.user_id = $1
+              )
+            ORDER BY cf.item_type, cf.item_id, cf.position
+        "#, user_id, name).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(|r| Ok(CustomFieldMatch {
+            item_type: r.item_type.parse().map_err(DBError::InvalidItemType)?,
+            item_id: r.item_id,
+            name: r.name,
+            field_type: r.field_type.parse().map_err(DBError::InvalidCustomField)?,
+        })).collect()
+    }
+}
+
+// The item DAOs read and write the custom fields of their items with these.
+
+/// The custom fields of `item_ids`, by item. Items without any are left out.
+pub async fn load_custom_fields<'c>(db: impl Executor<'c, Database = Postgres>, item_type: ItemType, item_ids: &[i32]) -> Result<HashMap<i32, Vec<CustomField>>, DBError> {
+    let records = sqlx::query!(r#"
+        SELECT item_id, name, value, field_type, linked_to, position FROM custom_fields
+        WHERE item_type = $1 AND item_id = ANY($2)
+        ORDER BY item_id, position
+    "#, item_type.as_str(), item_ids).fetch_all(db).await
+        .map_err(|e| DBError::Other(Box::new(e)))?;
+
+    let mut fields: HashMap<i32, Vec<CustomField>> = HashMap::new();
+    for r in records {
+        fields.entry(r.item_id).or_default().push(CustomField {
+            name: r.name,
+            value: r.value,
+            field_type: r.field_type.parse().map_err(DBError::InvalidCustomField)?,
+            linked_to: r.linked_to.map(|l| l.parse().map_err(DBError::InvalidCustomField)).transpose()?,
+            position: r.position,
+        });
+    }
+
+    Ok(fields)
+}
+
+/// Replaces the custom fields of the item. Hidden fields sent back masked keep the value of
+/// the stored hidden field with the same name. Runs in the transaction that saves the item,
+/// so items aren't left with half their fields.
+pub async fn save_custom_fields(tx: &mut Transaction<'_, Postgres>, item_type: ItemType, item_id: i32, fields: Vec<CustomFieldDto>) -> Result<Vec<CustomField>, DBError> {
+    let stored = sqlx::query!(
+        "DELETE FROM custom_fields WHERE item_type = $1 AND item_id = $2 RETURNING name, value, field_type",
+        item_type.as_str(), item_id
+    ).fetch_all(&mut *tx).await
+        .map_err(|e| DBError::Other(Box::new(e)))?;
+
+    let mut saved = Vec::with_capacity(fields.len());
+    for (position, field) in fields.into_iter().enumerate() {
+        let value = match field.field_type {
+            CustomFieldType::Hidden if field.value == MASKED_VALUE => stored.iter()
+                .find(|s| s.name == field.name && s.field_type == CustomFieldType::Hidden.as_str())
+                .map(|s| s.value.clone())
+                .unwrap_or(field.value),
+            _ => field.value,
+        };
+        let field = CustomField {
+            name: field.name,
+            value,
+            field_type: field.field_type,
+            linked_to: field.linked_to,
+            position: position as i32,
+        };
+
+        sqlx::query!(r#"
+            INSERT INTO custom_fields (item_type, item_id, name, value, field_type, linked_to, position)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#, item_type.as_str(), item_id, field.name, field.value, field.field_type.as_str(),
+            field.linked_to.map(|l| l.as_str()), field.position
+        ).execute(&mut *tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+        saved.push(field);
+    }
+
+    Ok(saved)
+}
@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::emergency_access_model::{EmergencyAccess, EmergencyAccessStatus, EmergencyAccessType};
+
+#[async_trait]
+pub trait EmergencyAccessDao {
+    async fn create_emergency_access(&self, grantor_id: i32, grantee_id: i32, access_type: EmergencyAccessType, wait_days: i32) -> Result<EmergencyAccess, DBError>;
+    async fn get_emergency_access(&self, id: i32) -> Result<EmergencyAccess, DBError>;
+    async fn get_granted_by(&self, grantor_id: i32) -> Result<Vec<EmergencyAccess>, DBError>;
+    async fn get_granted_to(&self, grantee_id: i32) -> Result<Vec<EmergencyAccess>, DBError>;
+    async fn transition(&self, id: i32, from: EmergencyAccessStatus, to: EmergencyAccessStatus) -> Result<bool, DBError>;
+    async fn delete_emergency_access(&self, id: i32) -> Result<(), DBError>;
+    async fn approve_elapsed_recoveries(&self) -> Result<Vec<i32>, DBError>;
+}
+
+pub struct EmergencyAccessDaoImpl {
+    db: PgPool,
+}
+
+impl EmergencyAccessDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        EmergencyAccessDaoImpl { db }
+    }
+}
+
+struct EmergencyAccessRecord {
+    id: i32,
+    grantor_id: i32,
+    grantor_username: String,
+    grantee_id: i32,
+    grantee_username: String,
+    access_type: String,
+    wait_days: i32,
+    status: String,
+    recovery_initiated_at: Option<sqlx::types::time::PrimitiveDateTime>,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+}
+
+impl TryFrom<EmergencyAccessRecord> for EmergencyAccess {
+    type Error = DBError;
+
+    fn try_from(record: EmergencyAccessRecord) -> Result<Self, Self::Error> {
+        Ok(EmergencyAccess {
+            id: record.id,
+            grantor_id: record.grantor_id,
+            grantor_username: record.grantor_username,
+            grantee_id: record.grantee_id,
+            grantee_username: record.grantee_username,
+            access_type: record.access_type.parse().map_err(DBError::InvalidEmergencyAccess)?,
+            wait_days: record.wait_days,
+            status: record.status.parse().map_err(DBError::InvalidEmergencyAccess)?,
+            recovery_initiated_at: record.recovery_initiated_at.map(|t| t.to_string()),
+            created_at: record.created_at.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmergencyAccessDao for EmergencyAccessDaoImpl {
+    async fn create_emergency_access(&self, grantor_id: i32, grantee_id: i32, access_type: EmergencyAccessType, wait_days: i32) -> Result<EmergencyAccess, DBError> {
+        let id = sqlx::query_scalar!(r#"
+            INSERT INTO emergency_access (grantor_id, grantee_id, access_type, wait_days)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+        "#, grantor_id, grantee_id, access_type.as_str(), wait_days).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        self.get_emergency_access(id).await
+    }
+
+    async fn get_emergency_access(&self, id: i32) -> Result<EmergencyAccess, DBError> {
+        let record = sqlx::query_as!(EmergencyAccessRecord, r#"
+            SELECT e.id, e.grantor_id, o.username as grantor_username, e.grantee_id, c.username as grantee_username,
+                   e.access_type, e.wait_days, e.status, e.recovery_initiated_at, e.created_at
+            FROM emergency_access e
+            JOIN users o ON o.id = e.grantor_id
+            JOIN users c ON c.id = e.grantee_id
+            WHERE e.id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        EmergencyAccess::try_from(record)
+    }
+
+    async fn get_granted_by(&self, grantor_id: i32) -> Result<Vec<EmergencyAccess>, DBError> {
+        let records = sqlx::query_as!(EmergencyAccessRecord, r#"
+            SELECT e.id, e.grantor_id, o.username as grantor_username, e.grantee_id, c.username as grantee_username,
+                   e.access_type, e.wait_days, e.status, e.recovery_initiated_at, e.created_at
+            FROM emergency_access e
+            JOIN users o ON o.id = e.grantor_id
+            JOIN users c ON c.id = e.grantee_id
+            WHERE e.grantor_id = $1
+            ORDER BY e.id
+        "#, grantor_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(EmergencyAccess::try_from).collect()
+    }
+
+    async fn get_granted_to(&self, grantee_id: i32) -> Result<Vec<EmergencyAccess>, DBError> {
+        let records = sqlx::query_as!(EmergencyAccessRecord, r#"
+            SELECT e.id, e.grantor_id, o.username as grantor_username, e.grantee_id, c.username as grantee_username,
+                   e.access_type, e.wait_days, e.status, e.recovery_initiated_at, e.created_at
+            FROM emergency_access e
+            JOIN users o ON o.id = e.grantor_id
+            JOIN users c ON c.id = e.grantee_id
+            WHERE e.grantee_id = $1
+            ORDER BY e.id
+        "#, grantee_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(EmergencyAccess::try_from).collect()
+    }
+
+    /// Moves a grant from `from` to `to`, unless another request or the timer moved it first.
+    /// Entering `recovery_initiated` starts the waiting period.
+    async fn transition(&self, id: i32, from: EmergencyAccessStatus, to: EmergencyAccessStatus) -> Result<bool, DBError> {
+        let result = sqlx::query!(r#"
+            UPDATE emergency_access
+            SET status = $3::varchar,
+                recovery_initiated_at = CASE WHEN $3::varchar = 'recovery_initiated' THEN now() ELSE recovery_initiated_at END
+            WHERE id = $1 AND status = $2
+        "#, id, from.as_str(), to.as_str()).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_emergency_access(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM emergency_access WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn approve_elapsed_recoveries(&self) -> Result<Vec<i32>, DBError> {
+        sqlx::query_scalar!(r#"
+            UPDATE emergency_access
+            SET status = 'recovery_approved'
+            WHERE status = 'recovery_initiated'
+              AND recovery_initiated_at + make_interval(days => wait_days) <= now()
+            RETURNING id
+        "#).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))
+    }
+}
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::url_match_model::EquivalentDomains;
+
+#[async_trait]
+pub trait EquivalentDomainsDao {
+    async fn get_equivalent_domains(&self, user_id: i32) -> Result<Vec<EquivalentDomains>, DBError>;
+    async fn create_equivalent_domains(&self, user_id: i32, domains: &[String]) -> Result<EquivalentDomains, DBError>;
+    async fn delete_equivalent_domains(&self, id: i32, user_id: i32) -> Result<bool, DBError>;
+}
+
+pub struct EquivalentDomainsDaoImpl {
+    db: PgPool,
+}
+
+impl EquivalentDomainsDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        EquivalentDomainsDaoImpl { db }
+    }
+}
+
+#[async_trait]
+impl EquivalentDomainsDao for EquivalentDomainsDaoImpl {
+    async fn get_equivalent_domains(&self, user_id: i32) -> Result<Vec<EquivalentDomains>, DBError> {
+        let records = sqlx::query!("SELECT id, domains FROM equivalent_domains WHERE user_id = $1 ORDER BY id", user_id)
+            .fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|r| EquivalentDomains {
+            id: r.id,
+            domains: r.domains.split(',').map(String::from).collect(),
+        }).collect())
+    }
+
+    async fn create_equivalent_domains(&self, user_id: i32, domains: &[String]) -> Result<EquivalentDomains, DBError> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO equivalent_domains (user_id, domains) VALUES ($1, $2) RETURNING id",
+            user_id, domains.join(",")
+        ).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(EquivalentDomains { id, domains: domains.to_vec() })
+    }
+
+    /// Returns false if the user has no such group.
+    async fn delete_equivalent_domains(&self, id: i32, user_id: i32) -> Result<bool, DBError> {
+        let result = sqlx::query!("DELETE FROM equivalent_domains WHERE id = $1 AND user_id = $2", id, user_id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
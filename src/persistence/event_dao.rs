@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use sqlx::types::time::{OffsetDateTime, PrimitiveDateTime};
+
+use crate::models::DBError;
+use crate::models::event_model::{Event, EventCheckpoint, EventPage, EventQuery, NewEvent};
+use crate::persistence::event_chain::{ChainLink, StoredLink};
+
+/// Serializes writers so every event links to the one inserted right before it.
+const EVENT_CHAIN_LOCK: i64 = 0x6576656e7473;
+
+#[async_trait]
+pub trait EventDao {
+    async fn insert_events(&self, events: &[NewEvent]) -> Result<(), DBError>;
+    async fn get_events(&self, query: &EventQuery) -> Result<EventPage, DBError>;
+    /// Records the newest chained event it deletes as a retention cut.
+    async fn delete_events_older_than(&self, days: i32) -> Result<u64, DBError>;
+    /// Id and hash of the newest event deleted by retention.
+    async fn get_retention_cut(&self) -> Result<Option<(i64, String)>, DBError>;
+    async fn get_links(&self, after_id: i64, limit: i64) -> Result<Vec<StoredLink>, DBError>;
+    async fn get_last_link(&self) -> Result<Option<(i64, String)>, DBError>;
+    async fn create_checkpoint(&self, event_id: i64, hash: &str, signature: &str) -> Result<(), DBError>;
+    async fn get_checkpoints(&self) -> Result<Vec<EventCheckpoint>, DBError>;
+}
+
+pub struct EventDaoImpl {
+    db: PgPool,
+}
+
+impl EventDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        EventDaoImpl { db }
+    }
+}
+
+struct EventRecord {
+    id: i64,
+    event_type: String,
+    actor_id: Option<i32>,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    item_type: Option<String>,
+    item_id: Option<i32>,
+    details: Option<String>,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+}
+
+impl TryFrom<EventRecord> for Event {
+    type Error = DBError;
+
+    fn try_from(record: EventRecord) -> Result<Self, Self::Error> {
+        Ok(Event {
+            id: record.id,
+            event_type: record.event_type.parse().map_err(DBError::InvalidEventType)?,
+            actor_id: record.actor_id,
+            ip_address: record.ip_address,
+            user_agent: record.user_agent,
+            item_type: record.item_type.map(|t| t.parse().map_err(DBError::InvalidItemType)).transpose()?,
+            item_id: record.item_id,
+            details: record.details,
+            created_at: record.created_at.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl EventDao for EventDaoImpl {
+    async fn insert_events(&self, events: &[NewEvent]) -> Result<(), DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!("SELECT pg_advisory_xact_lock($1)", EVENT_CHAIN_LOCK)
+            .execute(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        // After retention deleted every event, the chain continues from the retention cut.
+        let mut prev_hash = sqlx::query_scalar!(r#"
+            SELECT COALESCE(
+                (SELECT hash FROM events WHERE hash IS NOT NULL ORDER BY id DESC LIMIT 1),
+                (SELECT hash FROM event_retention_cuts ORDER BY event_id DESC LIMIT 1)
+            ) as "hash"
+        "#).fetch_one(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .unwrap_or_default();
+        let mut actor_hashes: HashMap<i32, String> = HashMap::new();
+
+        // Postgres keeps microseconds, so the hashed timestamp has to be truncated to match.
+        let now = OffsetDateTime::now_utc();
+        let now = now.replace_nanosecond(now.nanosecond() / 1000 * 1000).unwrap();
+        let created_at = PrimitiveDateTime::new(now.date(), now.time());
+
+        for event in events {
+            let actor_prev_hash = match event.actor_id {
+                Some(actor_id) => match actor_hashes.get(&actor_id) {
+                    Some(hash) => hash.clone(),
+                    None => sqlx::query_scalar!(
+                        r#"SELECT hash as "hash!" FROM events WHERE actor_id = $1 AND hash IS NOT NULL ORDER BY id DESC LIMIT 1"#,
+                        actor_id
+                    ).fetch_optional(&mut tx).await
+                        .map_err(|e| DBError::Other(Box::new(e)))?
+                        .unwrap_or_default(),
+                },
+                None => String::new(),
+            };
+
+            let link = ChainLink {
+                prev_hash: prev_hash.clone(),
+                actor_prev_hash,
+                event_type: event.event_type.as_str().to_string(),
+                actor_id: event.actor_id,
+                ip_address: event.ip_address.clone(),
+                user_agent: event.user_agent.clone(),
+                item_type: event.item_type.map(|t| t.as_str().to_string()),
+                item_id: event.item_id,
+                details: event.details.clone(),
+                created_at: created_at.to_string(),
+            };
+            let hash = link.hash();
+
+            sqlx::query!(r#"
+                INSERT INTO events (event_type, actor_id, ip_address, user_agent, item_type, item_id, details,
+                                    created_at, prev_hash, actor_prev_hash, hash)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+                link.event_type,
+                link.actor_id,
+                link.ip_address,
+                link.user_agent,
+                link.item_type,
+                link.item_id,
+                link.details,
+                created_at,
+                link.prev_hash,
+                link.actor_prev_hash,
+                hash
+            ).execute(&mut tx).await
+                .map_err(|e| DBError::Other(Box::new(e)))?;
+
+            if let Some(actor_id) = event.actor_id {
+                actor_hashes.insert(actor_id, hash.clone());
+            }
+            prev_hash = hash;
+        }
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))
+    }
+
+    async fn get_events(&self, query: &EventQuery) -> Result<EventPage, DBError> {
+        let event_type = query.event_type.map(|t| t.as_str());
+        let item_type = query.item_type.map(|t| t.as_str());
+        let since = query.since.map(|d| d.to_string());
+        let until = query.until.map(|d| d.to_string());
+
+        let total = sqlx::query_scalar!(r#"
+            SELECT COUNT(*) as "c!" FROM events
+            WHERE ($1::integer IS NULL OR actor_id = $1)
+              AND ($2::varchar IS NULL OR event_type = $2)
+              AND ($3::varchar IS NULL OR item_type = $3)
+              AND ($4::integer IS NULL OR item_id = $4)
+              AND ($5::date IS NULL OR created_at >= $5::date)
+              AND ($6::date IS NULL OR created_at < $6::date + 1)
+        "#, query.actor_id, event_type, item_type, query.item_id, since as _, until as _)
+            .fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let records = sqlx::query_as!(EventRecord, r#"
+            SELECT id, event_type, actor_id, ip_address, user_agent, item_type, item_id, details, created_at
+            FROM events
+            WHERE ($1::integer IS NULL OR actor_id = $1)
+              AND ($2::varchar IS NULL OR event_type = $2)
+              AND ($3::varchar IS NULL OR item_type = $3)
+              AND ($4::integer IS NULL OR item_id = $4)
+              AND ($5::date IS NULL OR created_at >= $5::date)
+              AND ($6::date IS NULL OR created_at < $6::date + 1)
+            ORDER BY id DESC
+            LIMIT $7 OFFSET $8
+        "#, query.actor_id, event_type, item_type, query.item_id, since as _, until as _,
+            query.per_page, (query.page - 1) * query.per_page)
+            .fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(EventPage {
+            events: records.into_iter().map(Event::try_from).collect::<Result<_, _>>()?,
+            page: query.page,
+            per_page: query.per_page,
+            total,
+        })
+    }
+
+    async fn delete_events_older_than(&self, days: i32) -> Result<u64, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!("SELECT pg_advisory_xact_lock($1)", EVENT_CHAIN_LOCK)
+            .execute(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let deleted = sqlx::query!(r#"
+            DELETE FROM events WHERE created_at < now() - make_interval(days => $1)
+            RETURNING id, hash
+        "#, days).fetch_all(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let cut = deleted.iter()
+            .filter_map(|record| record.hash.as_ref().map(|hash| (record.id, hash)))
+            .max_by_key(|(id, _)| *id);
+        if let Some((event_id, hash)) = cut {
+            sqlx::query!("INSERT INTO event_retention_cuts (event_id, hash) VALUES ($1, $2)", event_id, hash)
+                .execute(&mut tx).await
+                .map_err(|e| DBError::Other(Box::new(e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(deleted.len() as u64)
+    }
+
+    async fn get_retention_cut(&self) -> Result<Option<(i64, String)>, DBError> {
+        let record = sqlx::query!("SELECT event_id, hash FROM event_retention_cuts ORDER BY event_id DESC LIMIT 1")
+            .fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(record.map(|record| (record.event_id, record.hash)))
+    }
+
+    async fn get_links(&self, after_id: i64, limit: i64) -> Result<Vec<StoredLink>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT id, event_type, actor_id, ip_address, user_agent, item_type, item_id, details, created_at,
+                   prev_hash, actor_prev_hash, hash
+            FROM events
+            WHERE id > $1
+            ORDER BY id
+            LIMIT $2
+        "#, after_id, limit).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|record| StoredLink {
+            id: record.id,
+            link: ChainLink {
+                prev_hash: record.prev_hash.unwrap_or_default(),
+                actor_prev_hash: record.actor_prev_hash.unwrap_or_default(),
+                event_type: record.event_type,
+                actor_id: record.actor_id,
+                ip_address: record.ip_address,
+                user_agent: record.user_agent,
+                item_type: record.item_type,
+                item_id: record.item_id,
+                details: record.details,
+                created_at: record.created_at.to_string(),
+            },
+            hash: record.hash,
+        }).collect())
+    }
+
+    async fn get_last_link(&self) -> Result<Option<(i64, String)>, DBError> {
+        let record = sqlx::query!(
+            r#"SELECT id, hash as "hash!" FROM events WHERE hash IS NOT NULL ORDER BY id DESC LIMIT 1"#
+        ).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(record.map(|record| (record.id, record.hash)))
+    }
+
+    async fn create_checkpoint(&self, event_id: i64, hash: &str, signature: &str) -> Result<(), DBError> {
+        sqlx::query!(
+            "INSERT INTO event_checkpoints (event_id, hash, signature) VALUES ($1, $2, $3)",
+            event_id, hash, signature
+        ).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_checkpoints(&self) -> Result<Vec<EventCheckpoint>, DBError> {
+        let records = sqlx::query!("SELECT id, event_id, hash, signature, created_at FROM event_checkpoints ORDER BY id")
+            .fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|record| EventCheckpoint {
+            id: record.id,
+            event_id: record.event_id,
+            hash: record.hash,
+            signature: record.signature,
+            created_at: record.created_at.to_string(),
+        }).collect())
+    }
+}
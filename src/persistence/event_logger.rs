@@ -0,0 +1,47 @@
+use log::error;
+use tokio::sync::mpsc;
+
+use crate::models::event_model::NewEvent;
+use crate::persistence::event_dao::EventDao;
+
+const EVENT_QUEUE_SIZE: usize = 10_000;
+const MAX_EVENT_BATCH: usize = 100;
+
+/// Queues audit events for a background writer so handlers never wait on the insert.
+/// When the queue is full, events are dropped and logged rather than blocking the request.
+pub struct EventLogger {
+    sender: mpsc::Sender<NewEvent>,
+}
+
+impl EventLogger {
+    pub fn spawn(event_dao: Box<dyn EventDao + Sync + Send>) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<NewEvent>(EVENT_QUEUE_SIZE);
+
+        tokio::spawn(async move {
+            let mut batch = Vec::with_capacity(MAX_EVENT_BATCH);
+
+            while let Some(event) = receiver.recv().await {
+                batch.push(event);
+                while batch.len() < MAX_EVENT_BATCH {
+                    match receiver.try_recv() {
+                        Ok(event) => batch.push(event),
+                        Err(_) => break,
+                    }
+                }
+
+                if let Err(err) = event_dao.insert_events(&batch).await {
+                    error!("Failed to write {} audit events: {:?}", batch.len(), err);
+                }
+                batch.clear();
+            }
+        });
+
+        EventLogger { sender }
+    }
+
+    pub fn log(&self, event: NewEvent) {
+        if let Err(err) = self.sender.try_send(event) {
+            error!("Dropped audit event: {:?}", err);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use rand::random;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub const UPLOAD_DIR: &str = "upload";
+pub const TMP_DIR: &str = "upload/tmp";
+const SENDS_DIR: &str = "upload/sends";
+
+/// An upload copied into `upload/tmp`, not yet visible under its attachment id.
+pub struct StagedFile {
+    pub path: PathBuf,
+    pub checksum: String,
+    pub size: u64,
+}
+
+pub fn attachment_path(id: i32) -> PathBuf {
+    Path::new(UPLOAD_DIR).join(id.to_string())
+}
+
+pub fn send_path(id: i32) -> PathBuf {
+    Path::new(SENDS_DIR).join(id.to_string())
+}
+
+/// Copies `source` into the temporary directory, hashing it on the way.
+pub async fn stage(source: &Path) -> io::Result<StagedFile> {
+    fs::create_dir_all(TMP_DIR).await?;
+    let path = Path::new(TMP_DIR).join(format!("{:016x}", random::<u64>()));
+
+    let mut input = fs::File::open(source).await?;
+    let mut output = fs::File::create(&path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0;
+
+    loop {
+        let read = input.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        output.write_all(&buffer[..read]).await?;
+        size += read as u64;
+    }
+    output.sync_all().await?;
+
+    Ok(StagedFile { path, checksum: format!("{:x}", hasher.finalize()), size })
+}
+
+/// Moves a staged file to its final location. Both live in `upload/`, so the rename is atomic.
+pub async fn commit(staged: &StagedFile, target: &Path) -> io::Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::rename(&staged.path, target).await
+}
+
+pub async fn discard(staged: &StagedFile) {
+    if let Err(err) = fs::remove_file(&staged.path).await {
+        log::error!("Failed to remove staged upload {:?}: {:?}", staged.path, err);
+    }
+}
+
+/// Removes a stored file. A file that is already gone is not an error.
+pub async fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+pub async fn checksum(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Names of all files stored directly in `upload/` that weren't modified within `min_age`,
+/// skipping the temporary directory.
+pub async fn stored_files(min_age: Duration) -> io::Result<Vec<String>> {
+    files_older_than(Path::new(UPLOAD_DIR), min_age).await
+}
+
+/// Names of staged uploads in `upload/tmp` that weren't modified within `min_age`. Staging and
+/// committing happen within one request, so anything older was left behind by a failed upload.
+pub async fn staged_files(min_age: Duration) -> io::Result<Vec<String>> {
+    files_older_than(Path::new(TMP_DIR), min_age).await
+}
+
+async fn files_older_than(dir: &Path, min_age: Duration) -> io::Result<Vec<String>> {
+    let mut names = vec![];
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(names),
+        Err(err) => return Err(err),
+    };
+
+    let now = SystemTime::now();
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        let age = now.duration_since(metadata.modified()?).unwrap_or_default();
+        if metadata.is_file() && age >= min_age {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+
+    Ok(names)
+}
@@ -0,0 +1,183 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::identity_model::{Identity, IdentityDto};
+use crate::persistence::item_dao::{commit_item, fill_item, fill_items, ItemDao};
+
+pub struct IdentityDaoImpl {
+    db: PgPool,
+}
+
+impl IdentityDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        IdentityDaoImpl { db }
+    }
+}
+
+struct IdentityRecord {
+    id: i32,
+    name: String,
+    full_name: String,
+    email: String,
+    phone: String,
+    address: String,
+    city: String,
+    postal_code: String,
+    country: String,
+    passport_number: String,
+    passport_expiry: Option<sqlx::types::time::Date>,
+    note: String,
+    color: String,
+    revision: i64,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    modified_at: sqlx::types::time::PrimitiveDateTime,
+    permission: String,
+}
+
+impl TryFrom<IdentityRecord> for Identity {
+    type Error = DBError;
+
+    /// Custom fields are loaded separately.
+    fn try_from(record: IdentityRecord) -> Result<Self, Self::Error> {
+        Ok(Identity {
+            id: record.id,
+            name: record.name,
+            full_name: record.full_name,
+            email: record.email,
+            phone: record.phone,
+            address: record.address,
+            city: record.city,
+            postal_code: record.postal_code,
+            country: record.country,
+            passport_number: record.passport_number,
+            passport_expiry: record.passport_expiry.map(|d| d.to_string()),
+            note: record.note,
+            color: record.color,
+            created_at: record.created_at.to_string(),
+            modified_at: record.modified_at.to_string(),
+            custom_fields: vec![],
+            revision: record.revision,
+            shared: record.permission != "owner",
+            permission: record.permission.parse().map_err(DBError::InvalidPermission)?,
+        })
+    }
+}
+
+#[async_trait]
+impl ItemDao<Identity> for IdentityDaoImpl {
+    async fn create_item(&self, identity: IdentityDto, owner_id: i32) -> Result<Identity, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(IdentityRecord, r#"
+            INSERT INTO identities (owner_id, name, full_name, email, phone, address, city, postal_code, country,
+                                    passport_number, passport_expiry, note, color)
+            VALUES ($1, COALESCE($2, ''), COALESCE($3, ''), COALESCE($4, ''), COALESCE($5, ''), COALESCE($6, ''),
+                    COALESCE($7, ''), COALESCE($8, ''), COALESCE($9, ''), COALESCE($10, ''), $11::date,
+                    COALESCE($12, ''), COALESCE($13, 'red'))
+            RETURNING id, name, full_name, email, phone, address, city, postal_code, country, passport_number,
+                      passport_expiry, note, color, revision, created_at, modified_at, 'owner' as "permission!"
+        "#,
+            owner_id,
+            identity.name,
+            identity.full_name,
+            identity.email,
+            identity.phone,
+            identity.address,
+            identity.city,
+            identity.postal_code,
+            identity.country,
+            identity.passport_number,
+            identity.passport_expiry.flatten().map(|d| d.to_string()) as _,
+            identity.note,
+            identity.color,
+        ).fetch_one(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        commit_item(tx, Identity::try_from(record)?, identity.custom_fields).await
+    }
+
+    async fn get_item(&self, id: i32) -> Result<Identity, DBError> {
+        let record = sqlx::query_as!(IdentityRecord, r#"
+            SELECT id, name, full_name, email, phone, address, city, postal_code, country, passport_number,
+                   passport_expiry, note, color, revision, created_at, modified_at, 'owner' as "permission!"
+            FROM identities WHERE id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        fill_item(&self.db, Identity::try_from(record)?).await
+    }
+
+    async fn get_items(&self, user_id: i32, since: Option<i64>) -> Result<Vec<Identity>, DBError> {
+        let records = sqlx::query_as!(IdentityRecord, r#"
+            SELECT i.id, i.name, i.full_name, i.email, i.phone, i.address, i.city, i.postal_code, i.country,
+                   i.passport_number, i.passport_expiry, i.note, i.color, i.revision, i.created_at, i.modified_at,
+                   COALESCE(s.permission, 'owner') as "permission!"
+            FROM identities i
+            LEFT JOIN LATERAL (
+                SELECT permission FROM item_permissions
+                WHERE item_type = 'identity' AND item_id = i.id AND user_id = $1
+                ORDER BY rank DESC LIMIT 1
+            ) s ON i.owner_id IS DISTINCT FROM $1
+            WHERE (i.owner_id = $1 OR s.permission IS NOT NULL)
+              AND ($2::bigint IS NULL OR EXISTS(
+                  SELECT 1 FROM sync_changes c
+                  WHERE c.user_id = $1 AND c.item_type = 'identity' AND c.item_id = i.id AND c.revision > $2
+              ))
+            ORDER BY i.id
+        "#, user_id, since).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let identities = records.into_iter().map(Identity::try_from).collect::<Result<Vec<_>, _>>()?;
+        fill_items(&self.db, identities).await
+    }
+
+    async fn update_item(&self, id: i32, identity: IdentityDto, if_match: Option<i64>) -> Result<Identity, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(IdentityRecord, r#"
+            UPDATE identities SET name = COALESCE($1, name), full_name = COALESCE($2, full_name),
+                                  email = COALESCE($3, email), phone = COALESCE($4, phone),
+                                  address = COALESCE($5, address), city = COALESCE($6, city),
+                                  postal_code = COALESCE($7, postal_code), country = COALESCE($8, country),
+                                  passport_number = COALESCE($9, passport_number),
+                                  passport_expiry = CASE WHEN $10 THEN $11::date ELSE passport_expiry END,
+                                  note = COALESCE($12, note), color = COALESCE($13, color), revision = revision + 1
+            WHERE id = $14 AND ($15::bigint IS NULL OR revision = $15)
+            RETURNING id, name, full_name, email, phone, address, city, postal_code, country, passport_number,
+                      passport_expiry, note, color, revision, created_at, modified_at, 'owner' as "permission!"
+        "#,
+            identity.name,
+            identity.full_name,
+            identity.email,
+            identity.phone,
+            identity.address,
+            identity.city,
+            identity.postal_code,
+            identity.country,
+            identity.passport_number,
+            identity.passport_expiry.is_some(),
+            identity.passport_expiry.flatten().map(|d| d.to_string()) as _,
+            identity.note,
+            identity.color,
+            id,
+            if_match
+        ).fetch_optional(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .ok_or(DBError::StaleRevision)?;
+
+        commit_item(tx, Identity::try_from(record)?, identity.custom_fields).await
+    }
+
+    async fn delete_item(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM identities WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::models::custom_field_model::CustomFieldDto;
+use crate::models::DBError;
+use crate::models::item_model::VaultItem;
+use crate::models::share_model::Permission;
+use crate::persistence::custom_field_dao::{load_custom_fields, save_custom_fields};
+
+/// Storage of one item type, behind the generic item handlers.
+#[async_trait]
+pub trait ItemDao<T: VaultItem> {
+    async fn create_item(&self, item: T::Dto, owner_id: i32) -> Result<T, DBError>;
+    async fn get_item(&self, id: i32) -> Result<T, DBError>;
+    /// Everything the user owns or was granted; with `since`, only what changed after that sync revision.
+    async fn get_items(&self, user_id: i32, since: Option<i64>) -> Result<Vec<T>, DBError>;
+    /// Bumps the revision. With `if_match`, fails with `StaleRevision` unless that is the stored revision.
+    async fn update_item(&self, id: i32, item: T::Dto, if_match: Option<i64>) -> Result<T, DBError>;
+    async fn delete_item(&self, id: i32) -> Result<(), DBError>;
+}
+
+// The item DAOs only write the queries of their own table and leave the rest to these.
+
+/// Loads the custom fields of `items` and blanks the secrets of those the user has
+/// hide-passwords access to.
+pub async fn fill_items<T: VaultItem>(db: &PgPool, mut items: Vec<T>) -> Result<Vec<T>, DBError> {
+    let ids: Vec<i32> = items.iter().map(T::id).collect();
+    let mut custom_fields = load_custom_fields(db, T::ITEM_TYPE, &ids).await?;
+
+    for item in items.iter_mut() {
+        *item.custom_fields_mut() = custom_fields.remove(&item.id()).unwrap_or_default();
+        if item.permission() == Permission::HidePasswords {
+            item.hide_secrets();
+        }
+    }
+
+    Ok(items)
+}
+
+/// `fill_items` for a single item.
+pub async fn fill_item<T: VaultItem>(db: &PgPool, item: T) -> Result<T, DBError> {
+    let mut items = fill_items(db, vec![item]).await?;
+    Ok(items.remove(0))
+}
+
+/// Saves the custom fields sent with the item, or reads the stored ones if none were sent, and
+/// commits the transaction the item was saved in.
+pub async fn commit_item<T: VaultItem>(mut tx: Transaction<'_, Postgres>, mut item: T, custom_fields: Option<Vec<CustomFieldDto>>) -> Result<T, DBError> {
+    *item.custom_fields_mut() = match custom_fields {
+        Some(custom_fields) => save_custom_fields(&mut tx, T::ITEM_TYPE, item.id(), custom_fields).await?,
+        None => load_custom_fields(&mut *tx, T::ITEM_TYPE, &[item.id()]).await?
+            .remove(&item.id())
+            .unwrap_or_default(),
+    };
+
+    tx.commit().await
+        .map_err(|e| DBError::Other(Box::new(e)))?;
+
+    Ok(item)
+}
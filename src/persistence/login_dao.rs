@@ -4,16 +4,25 @@ use rocket::http::hyper::body::HttpBody;
 use sqlx::PgPool;
 
 use crate::models::DBError;
+use crate::models::item_model::ItemType;
 use crate::models::login_model::{Login, LoginDto};
+use crate::models::report_model::UnusedLogin;
+use crate::models::share_model::Permission;
+use crate::persistence::custom_field_dao::{load_custom_fields, save_custom_fields};
 
 #[async_trait]
 pub trait LoginDao {
     async fn create_login(&self, login: LoginDto, owner_id: i32) -> Result<Login, DBError>;
-    async fn get_logins(&self, owner_id: i32) -> Result<Vec<Login>, DBError>;
+    /// Everything the user owns or was granted; with `since`, only what changed after that sync revision.
+    async fn get_logins(&self, user_id: i32, since: Option<i64>) -> Result<Vec<Login>, DBError>;
     async fn get_login(&self, id: i32) -> Result<Login, DBError>;
-    async fn get_login_owner(&self, id: i32) -> Result<i32, DBError>;
     async fn delete_logins(&self, ids: &Vec<i32>) -> Result<(), DBError>;
-    async fn update_login(&self, id: i32, login: LoginDto) -> Result<Login, DBError>;
+    /// Bumps the revision. With `if_match`, fails with `StaleRevision` unless that is the stored revision.
+    async fn update_login(&self, id: i32, login: LoginDto, if_match: Option<i64>) -> Result<Login, DBError>;
+    /// Sets `used_at` to now and counts the use. Leaves the revision alone, using a login is not an edit.
+    async fn record_login_use(&self, id: i32) -> Result<(), DBError>;
+    /// Logins the user owns that weren't used for `days`, least recently used first.
+    async fn find_unused_logins(&self, owner_id: i32, days: i32) -> Result<Vec<UnusedLogin>, DBError>;
 }
 
 #[derive(FromForm)]
@@ -35,11 +44,14 @@ impl LoginDaoImpl {
 #[async_trait]
 impl LoginDao for LoginDaoImpl {
     async fn create_login(&self, login: LoginDto, owner_id: i32) -> Result<Login, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
         let record = sqlx::query!(
             r#"
-                INSERT INTO logins (username, note, password, email, linked_websites, collections, owner_id)
-                VALUES ($1, $2, $3, $4, $5, $6, $7)
-                RETURNING id, used_at, username,note, password, email, linked_websites, collections
+                INSERT INTO logins (username, note, password, email, linked_websites, collections, owner_id, match_strategy)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id, used_at, use_count, username,note, password, email, linked_websites, collections, match_strategy, revision
             "#,
             login.username,
             login.note,
@@ -47,45 +59,81 @@ impl LoginDao for LoginDaoImpl {
             login.email,
             login.linked_websites.unwrap_or(vec![]).join(","),
             login.collections.unwrap_or(vec![]).join(","),
-            owner_id
-        ).fetch_one(&self.db)
+            owner_id,
+            login.match_strategy.unwrap_or_default().as_str()
+        ).fetch_one(&mut tx)
             .await
             .map_err(|e| DBError::Other(Box::new(e)))?;
 
+        let custom_fields = save_custom_fields(&mut tx, ItemType::Login, record.id, login.custom_fields.unwrap_or_default()).await?;
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
 
         Ok(Login {
             id: record.id,
             used_at: record.used_at.to_string(),
+            use_count: record.use_count,
             username: record.username,
             note: record.note,
             password: record.password,
             email: record.email.to_string(),
             linked_websites: record.linked_websites.split(",").map(|s| s.to_string()).collect(),
             collections: record.collections.split(",").map(|s| s.to_string()).collect(),
+            match_strategy: record.match_strategy.parse().map_err(DBError::InvalidMatchStrategy)?,
+            custom_fields,
+            revision: record.revision,
+            shared: false,
+            permission: Permission::Owner,
         })
     }
 
-    async fn get_logins(&self, owner_id: i32) -> Result<Vec<Login>, DBError> {
+    async fn get_logins(&self, user_id: i32, since: Option<i64>) -> Result<Vec<Login>, DBError> {
         let records = sqlx::query!(r#"
-            SELECT id, used_at, username, password,note, email, linked_websites, collections
-            FROM logins
-            WHERE owner_id = $1
+            SELECT l.id, l.used_at, l.use_count, l.username, l.password, l.note, l.email, l.linked_websites, l.collections, l.match_strategy, l.revision,
+                   COALESCE(s.permission, 'owner') as "permission!"
+            FROM logins l
+            LEFT JOIN LATERAL (
+                SELECT permission FROM item_permissions
+                WHERE item_type = 'login' AND item_id = l.id AND user_id = $1
+                ORDER BY rank DESC LIMIT 1
+            ) s ON l.owner_id IS DISTINCT FROM $1
+            WHERE (l.owner_id = $1 OR s.permission IS NOT NULL)
+              AND ($2::bigint IS NULL OR EXISTS(
+                  SELECT 1 FROM sync_changes c
+                  WHERE c.user_id = $1 AND c.item_type = 'login' AND c.item_id = l.id AND c.revision > $2
+              ))
+            ORDER BY l.id
         "#,
-        owner_id
+        user_id, since
         ).fetch_all(&self.db).await
             .map_err(|e| DBError::Other(Box::new(e)))?;
 
-
-        Ok(records.iter().map(|record| Login {
-            id: record.id,
-            used_at: record.used_at.to_string(),
-            username: record.username.to_string(),
-            note: record.note.to_string(),
-            password: record.password.to_string(),
-            email: record.email.to_string(),
-            linked_websites: record.linked_websites.split(",").map(|s| s.to_string()).collect(),
-            collections: record.collections.split(",").map(|s| s.to_string()).collect(),
-        }).collect())
+        let ids: Vec<i32> = records.iter().map(|record| record.id).collect();
+        let mut custom_fields = load_custom_fields(&self.db, ItemType::Login, &ids).await?;
+
+        records.iter().map(|record| {
+            let mut login = Login {
+                id: record.id,
+                used_at: record.used_at.to_string(),
+                use_count: record.use_count,
+                username: record.username.to_string(),
+                note: record.note.to_string(),
+                password: record.password.to_string(),
+                email: record.email.to_string(),
+                linked_websites: record.linked_websites.split(",").map(|s| s.to_string()).collect(),
+                collections: record.collections.split(",").map(|s| s.to_string()).collect(),
+                match_strategy: record.match_strategy.parse().map_err(DBError::InvalidMatchStrategy)?,
+                custom_fields: custom_fields.remove(&record.id).unwrap_or_default(),
+                revision: record.revision,
+                shared: record.permission != "owner",
+                permission: record.permission.parse().map_err(DBError::InvalidPermission)?,
+            };
+            if login.permission == Permission::HidePasswords {
+                login.hide_secrets();
+            }
+            Ok(login)
+        }).collect()
     }
 
     async fn get_login(&self, id: i32) -> Result<Login, DBError> {
@@ -96,23 +144,21 @@ impl LoginDao for LoginDaoImpl {
         return Ok(Login {
             id: record.id,
             used_at: record.used_at.to_string(),
+            use_count: record.use_count,
             username: record.username.to_string(),
             password: record.password.to_string(),
             note: record.note.to_string(),
             email: record.email.to_string(),
             linked_websites: record.linked_websites.split(",").map(|s| s.to_string()).collect(),
             collections: record.collections.split(",").map(|s| s.to_string()).collect(),
+            match_strategy: record.match_strategy.parse().map_err(DBError::InvalidMatchStrategy)?,
+            custom_fields: load_custom_fields(&self.db, ItemType::Login, &[id]).await?.remove(&id).unwrap_or_default(),
+            revision: record.revision,
+            shared: false,
+            permission: Permission::Owner,
         });
     }
 
-    async fn get_login_owner(&self, id: i32) -> Result<i32, DBError> {
-        let record = sqlx::query!(r#" SELECT owner_id FROM logins WHERE id = $1"#, id).fetch_one(&self.db).await.map_err(
-            |e| DBError::Other(Box::new(e))
-        )?;
-
-        return Ok(record.owner_id.unwrap());
-    }
-
     async fn delete_logins(&self, ids: &Vec<i32>) -> Result<(), DBError> {
         sqlx::query!(r#"DELETE FROM logins WHERE id = ANY($1)"#, ids).execute(&self.db).await.map_err(
             |e| DBError::Other(Box::new(e))
@@ -121,7 +167,7 @@ impl LoginDao for LoginDaoImpl {
         Ok(())
     }
 
-    async fn update_login(&self, id: i32, login_dao: LoginDto) -> Result<Login, DBError> {
+    async fn update_login(&self, id: i32, login_dao: LoginDto, if_match: Option<i64>) -> Result<Login, DBError> {
         let mut login = self.get_login(id).await?;
 
         if let Some(password) = login_dao.password {
@@ -142,11 +188,19 @@ impl LoginDao for LoginDaoImpl {
         if let Some(collections) = login_dao.collections {
             login.collections = collections;
         }
+        if let Some(match_strategy) = login_dao.match_strategy {
+            login.match_strategy = match_strategy;
+        }
 
-        sqlx::query!(r#"
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        login.revision = sqlx::query_scalar!(r#"
             Update logins
-            set username = $1, note = $2, password = $3, email = $4, linked_websites = $5, collections = $6
-            where id = $7
+            set username = $1, note = $2, password = $3, email = $4, linked_websites = $5, collections = $6,
+                match_strategy = $9, revision = revision + 1
+            where id = $7 AND ($8::bigint IS NULL OR revision = $8)
+            RETURNING revision
         "#,
             login.username,
             login.note,
@@ -154,11 +208,46 @@ impl LoginDao for LoginDaoImpl {
             login.email,
             login.linked_websites.join(","),
             login.collections.join(","),
-            id
-        ).execute(&self.db)
+            id,
+            if_match,
+            login.match_strategy.as_str()
+        ).fetch_optional(&mut tx)
             .await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .ok_or(DBError::StaleRevision)?;
+
+        if let Some(custom_fields) = login_dao.custom_fields {
+            login.custom_fields = save_custom_fields(&mut tx, ItemType::Login, id, custom_fields).await?;
+        }
+
+        tx.commit().await
             .map_err(|e| DBError::Other(Box::new(e)))?;
 
         return Ok(login);
     }
+
+    async fn record_login_use(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("UPDATE logins SET used_at = now(), use_count = use_count + 1 WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn find_unused_logins(&self, owner_id: i32, days: i32) -> Result<Vec<UnusedLogin>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT id, username, linked_websites, used_at, use_count FROM logins
+            WHERE owner_id = $1 AND used_at < now() - make_interval(days => $2)
+            ORDER BY used_at, id
+        "#, owner_id, days).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|r| UnusedLogin {
+            login_id: r.id,
+            username: r.username,
+            linked_websites: r.linked_websites.split(",").filter(|s| !s.is_empty()).map(|s| s.to_string()).collect(),
+            used_at: r.used_at.to_string(),
+            use_count: r.use_count,
+        }).collect())
+    }
 }
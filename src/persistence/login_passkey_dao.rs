@@ -0,0 +1,143 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::login_passkey_model::{LoginPasskey, LoginPasskeyDto};
+
+#[async_trait]
+pub trait LoginPasskeyDao {
+    async fn create_passkey(&self, login_id: i32, passkey: LoginPasskeyDto) -> Result<LoginPasskey, DBError>;
+    async fn get_passkey(&self, id: i32) -> Result<LoginPasskey, DBError>;
+    async fn get_passkeys(&self, login_id: i32) -> Result<Vec<LoginPasskey>, DBError>;
+    async fn find_passkeys(&self, user_id: i32, rp_id: &str) -> Result<Vec<LoginPasskey>, DBError>;
+    async fn update_sign_count(&self, id: i32, sign_count: i64) -> Result<Option<LoginPasskey>, DBError>;
+    async fn delete_passkey(&self, id: i32) -> Result<(), DBError>;
+}
+
+pub struct LoginPasskeyDaoImpl {
+    db: PgPool,
+}
+
+impl LoginPasskeyDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        LoginPasskeyDaoImpl { db }
+    }
+}
+
+struct PasskeyRecord {
+    id: i32,
+    login_id: i32,
+    credential_id: String,
+    rp_id: String,
+    user_handle: String,
+    user_name: Option<String>,
+    private_key: String,
+    sign_count: i64,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    last_used_at: Option<sqlx::types::time::PrimitiveDateTime>,
+    permission: String,
+}
+
+impl TryFrom<PasskeyRecord> for LoginPasskey {
+    type Error = DBError;
+
+    fn try_from(record: PasskeyRecord) -> Result<Self, Self::Error> {
+        Ok(LoginPasskey {
+            id: record.id,
+            login_id: record.login_id,
+            credential_id: record.credential_id,
+            rp_id: record.rp_id,
+            user_handle: record.user_handle,
+            user_name: record.user_name,
+            private_key: record.private_key,
+            sign_count: record.sign_count,
+            created_at: record.created_at.to_string(),
+            last_used_at: record.last_used_at.map(|t| t.to_string()),
+            permission: record.permission.parse().map_err(DBError::InvalidPermission)?,
+        })
+    }
+}
+
+#[async_trait]
+impl LoginPasskeyDao for LoginPasskeyDaoImpl {
+    async fn create_passkey(&self, login_id: i32, passkey: LoginPasskeyDto) -> Result<LoginPasskey, DBError> {
+        let record = sqlx::query_as!(PasskeyRecord, r#"
+            INSERT INTO login_passkeys (login_id, credential_id, rp_id, user_handle, user_name, private_key, sign_count)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, login_id, credential_id, rp_id, user_handle, user_name, private_key, sign_count,
+                      created_at, last_used_at, 'owner' as "permission!"
+        "#,
+        login_id, passkey.credential_id, passkey.rp_id.to_lowercase(), passkey.user_handle, passkey.user_name,
+        passkey.private_key, passkey.sign_count.unwrap_or(0)
+        ).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        LoginPasskey::try_from(record)
+    }
+
+    async fn get_passkey(&self, id: i32) -> Result<LoginPasskey, DBError> {
+        let record = sqlx::query_as!(PasskeyRecord, r#"
+            SELECT id, login_id, credential_id, rp_id, user_handle, user_name, private_key, sign_count,
+                   created_at, last_used_at, 'owner' as "permission!"
+            FROM login_passkeys WHERE id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        LoginPasskey::try_from(record)
+    }
+
+    async fn get_passkeys(&self, login_id: i32) -> Result<Vec<LoginPasskey>, DBError> {
+        let records = sqlx::query_as!(PasskeyRecord, r#"
+            SELECT id, login_id, credential_id, rp_id, user_handle, user_name, private_key, sign_count,
+                   created_at, last_used_at, 'owner' as "permission!"
+            FROM login_passkeys WHERE login_id = $1
+            ORDER BY id
+        "#, login_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(LoginPasskey::try_from).collect()
+    }
+
+    /// Passkeys for `rp_id` on every login the user can read. Logins the user may only see with
+    /// hidden passwords are left out, their keys would be blanked anyway.
+    async fn find_passkeys(&self, user_id: i32, rp_id: &str) -> Result<Vec<LoginPasskey>, DBError> {
+        let records = sqlx::query_as!(PasskeyRecord, r#"
+            SELECT p.id, p.login_id, p.credential_id, p.rp_id, p.user_handle, p.user_name, p.private_key,
+                   p.sign_count, p.created_at, p.last_used_at, COALESCE(s.permission, 'owner') as "permission!"
+            FROM login_passkeys p
+            JOIN logins l ON l.id = p.login_id
+            LEFT JOIN LATERAL (
+                SELECT permission FROM item_permissions
+                WHERE item_type = 'login' AND item_id = l.id AND user_id = $1
+                ORDER BY rank DESC LIMIT 1
+            ) s ON l.owner_id IS DISTINCT FROM $1
+            WHERE p.rp_id = $2 AND (l.owner_id = $1 OR s.permission IN ('read', 'write'))
+            ORDER BY p.last_used_at DESC NULLS LAST, p.id
+        "#, user_id, rp_id.to_lowercase()).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(LoginPasskey::try_from).collect()
+    }
+
+    /// Stores a new signature counter, unless it doesn't move forward, which can mean the
+    /// credential was cloned. Authenticators without a counter always report 0.
+    async fn update_sign_count(&self, id: i32, sign_count: i64) -> Result<Option<LoginPasskey>, DBError> {
+        let record = sqlx::query_as!(PasskeyRecord, r#"
+            UPDATE login_passkeys SET sign_count = $1, last_used_at = now()
+            WHERE id = $2 AND ($1 > sign_count OR ($1 = 0 AND sign_count = 0))
+            RETURNING id, login_id, credential_id, rp_id, user_handle, user_name, private_key, sign_count,
+                      created_at, last_used_at, 'owner' as "permission!"
+        "#, sign_count, id).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        record.map(LoginPasskey::try_from).transpose()
+    }
+
+    async fn delete_passkey(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM login_passkeys WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+}
@@ -3,3 +3,27 @@ pub mod auth_dao;
 pub mod login_dao;
 pub mod payment_dao;
 pub mod secured_note_dao;
+pub mod storage_dao;
+pub mod file_storage;
+pub mod attachment_dao;
+pub mod send_dao;
+pub mod share_dao;
+pub mod organization_dao;
+pub mod emergency_access_dao;
+pub mod event_dao;
+pub mod event_logger;
+pub mod event_chain;
+pub mod webauthn_dao;
+pub mod login_passkey_dao;
+pub mod user_token_dao;
+pub mod notification_dao;
+pub mod push_hub;
+pub mod sync_dao;
+pub mod equivalent_domains_dao;
+pub mod custom_field_dao;
+pub mod item_dao;
+pub mod identity_dao;
+pub mod ssh_key_dao;
+pub mod api_credential_dao;
+pub mod wifi_network_dao;
+pub mod software_license_dao;
@@ -0,0 +1,223 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::item_model::ItemType;
+use crate::models::notification_model::{ExpiringCard, NewNotification, Notification, NotificationPreferences, PendingEmail, StalePassword};
+
+#[async_trait]
+pub trait NotificationDao {
+    async fn create_notification(&self, notification: &NewNotification) -> Result<bool, DBError>;
+    async fn get_notifications(&self, user_id: i32, include_dismissed: bool) -> Result<Vec<Notification>, DBError>;
+    async fn dismiss_notification(&self, id: i32, user_id: i32) -> Result<bool, DBError>;
+    async fn get_preferences(&self, user_id: i32) -> Result<NotificationPreferences, DBError>;
+    async fn set_preferences(&self, user_id: i32, preferences: &NotificationPreferences) -> Result<(), DBError>;
+    async fn find_expiring_cards(&self) -> Result<Vec<ExpiringCard>, DBError>;
+    async fn find_stale_passwords(&self) -> Result<Vec<StalePassword>, DBError>;
+    async fn get_pending_emails(&self) -> Result<Vec<PendingEmail>, DBError>;
+    async fn mark_emailed(&self, id: i32) -> Result<(), DBError>;
+}
+
+pub struct NotificationDaoImpl {
+    db: PgPool,
+}
+
+impl NotificationDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        NotificationDaoImpl { db }
+    }
+}
+
+struct NotificationRecord {
+    id: i32,
+    user_id: i32,
+    kind: String,
+    item_type: Option<String>,
+    item_id: Option<i32>,
+    message: String,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    dismissed_at: Option<sqlx::types::time::PrimitiveDateTime>,
+}
+
+impl TryFrom<NotificationRecord> for Notification {
+    type Error = DBError;
+
+    fn try_from(record: NotificationRecord) -> Result<Self, Self::Error> {
+        Ok(Notification {
+            id: record.id,
+            user_id: record.user_id,
+            kind: record.kind.parse().map_err(DBError::InvalidNotificationKind)?,
+            item_type: record.item_type.map(|t| t.parse().map_err(DBError::InvalidItemType)).transpose()?,
+            item_id: record.item_id,
+            message: record.message,
+            created_at: record.created_at.to_string(),
+            dismissed_at: record.dismissed_at.map(|d| d.to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationDao for NotificationDaoImpl {
+    /// Returns false if the user was already notified about the same thing.
+    async fn create_notification(&self, notification: &NewNotification) -> Result<bool, DBError> {
+        let id = sqlx::query_scalar!(r#"
+            INSERT INTO notifications (user_id, kind, item_type, item_id, dedup_key, message)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_id, kind, item_type, item_id, dedup_key) DO NOTHING
+            RETURNING id
+        "#, notification.user_id, notification.kind.as_str(), notification.item_type.as_str(),
+            notification.item_id, notification.dedup_key, notification.message
+        ).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(id.is_some())
+    }
+
+    /// Newest first.
+    async fn get_notifications(&self, user_id: i32, include_dismissed: bool) -> Result<Vec<Notification>, DBError> {
+        let records = sqlx::query_as!(NotificationRecord, r#"
+            SELECT id, user_id, kind, item_type, item_id, message, created_at, dismissed_at
+            FROM notifications
+            WHERE user_id = $1 AND ($2 OR dismissed_at IS NULL)
+            ORDER BY created_at DESC, id DESC
+        "#, user_id, include_dismissed).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(Notification::try_from).collect()
+    }
+
+    /// Returns false if the user has no such notification. Dismissing twice keeps the first time.
+    async fn dismiss_notification(&self, id: i32, user_id: i32) -> Result<bool, DBError> {
+        let result = sqlx::query!(
+            "UPDATE notifications SET dismissed_at = COALESCE(dismissed_at, now()) WHERE id = $1 AND user_id = $2",
+            id, user_id
+        ).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Users who never changed their preferences get the defaults.
+    async fn get_preferences(&self, user_id: i32) -> Result<NotificationPreferences, DBError> {
+        let record = sqlx::query!(r#"
+            SELECT card_expiry, card_expiry_days, password_rotation, email
+            FROM notification_preferences WHERE user_id = $1
+        "#, user_id).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(record.map(|r| NotificationPreferences {
+            card_expiry: r.card_expiry,
+            card_expiry_days: r.card_expiry_days,
+            password_rotation: r.password_rotation,
+            email: r.email,
+        }).unwrap_or_default())
+    }
+
+    async fn set_preferences(&self, user_id: i32, preferences: &NotificationPreferences) -> Result<(), DBError> {
+        sqlx::query!(r#"
+            INSERT INTO notification_preferences (user_id, card_expiry, card_expiry_days, password_rotation, email)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id) DO UPDATE
+            SET card_expiry = $2, card_expiry_days = $3, password_rotation = $4, email = $5
+        "#, user_id, preferences.card_expiry, preferences.card_expiry_days, preferences.password_rotation, preferences.email
+        ).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    /// Cards that are still valid but expire within their owner's `card_expiry_days`. A card is
+    /// valid through the last day of its expiration month.
+    async fn find_expiring_cards(&self) -> Result<Vec<ExpiringCard>, DBError> {
+        let defaults = NotificationPreferences::default();
+        let records = sqlx::query!(r#"
+            SELECT p.owner_id AS "owner_id!", p.id, p.name, right(p.card_number, 4) AS "last_four!",
+                   p.expiration_month, p.expiration_year
+            FROM payments p
+            LEFT JOIN notification_preferences np ON np.user_id = p.owner_id
+            WHERE p.owner_id IS NOT NULL
+              AND p.expiration_month BETWEEN 1 AND 12
+              AND COALESCE(np.card_expiry, $1)
+              AND make_date(p.expiration_year, p.expiration_month, 1) + interval '1 month' > now()
+              AND make_date(p.expiration_year, p.expiration_month, 1) + interval '1 month'
+                  <= now() + make_interval(days => COALESCE(np.card_expiry_days, $2))
+            ORDER BY p.id
+        "#, defaults.card_expiry, defaults.card_expiry_days).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|r| ExpiringCard {
+            user_id: r.owner_id,
+            payment_id: r.id,
+            name: r.name,
+            last_four: r.last_four,
+            expiration_month: r.expiration_month,
+            expiration_year: r.expiration_year,
+        }).collect())
+    }
+
+    /// Logins whose password is older than the shortest rotation interval of the collections
+    /// they are in. Reminders go to the login's owner.
+    async fn find_stale_passwords(&self) -> Result<Vec<StalePassword>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT l.owner_id AS "owner_id!", l.id, l.username, l.password_changed_at,
+                   MIN(c.rotation_days) AS "rotation_days!"
+            FROM logins l
+            JOIN collection_items ci ON ci.item_type = $1 AND ci.item_id = l.id
+            JOIN org_collections c ON c.id = ci.collection_id
+            LEFT JOIN notification_preferences np ON np.user_id = l.owner_id
+            WHERE l.owner_id IS NOT NULL
+              AND c.rotation_days IS NOT NULL
+              AND COALESCE(np.password_rotation, $2)
+            GROUP BY l.id
+            HAVING l.password_changed_at + make_interval(days => MIN(c.rotation_days)) <= now()
+            ORDER BY l.id
+        "#, ItemType::Login.as_str(), NotificationPreferences::default().password_rotation).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|r| StalePassword {
+            user_id: r.owner_id,
+            login_id: r.id,
+            username: r.username,
+            password_changed_at: r.password_changed_at.to_string(),
+            rotation_days: r.rotation_days,
+        }).collect())
+    }
+
+    /// Notifications not emailed yet, of users who want them by email. Dismissed ones are
+    /// skipped, the user has already seen them.
+    async fn get_pending_emails(&self) -> Result<Vec<PendingEmail>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT n.id, n.user_id, n.kind, n.item_type, n.item_id, n.message, n.created_at, n.dismissed_at,
+                   u.email, u.first_name
+            FROM notifications n
+            JOIN notification_preferences np ON np.user_id = n.user_id AND np.email
+            JOIN users u ON u.id = n.user_id
+            WHERE n.emailed_at IS NULL AND n.dismissed_at IS NULL
+            ORDER BY n.id
+        "#).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(|r| Ok(PendingEmail {
+            notification: Notification::try_from(NotificationRecord {
+                id: r.id,
+                user_id: r.user_id,
+                kind: r.kind,
+                item_type: r.item_type,
+                item_id: r.item_id,
+                message: r.message,
+                created_at: r.created_at,
+                dismissed_at: r.dismissed_at,
+            })?,
+            email: r.email,
+            first_name: r.first_name,
+        })).collect()
+    }
+
+    async fn mark_emailed(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("UPDATE notifications SET emailed_at = now() WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,376 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::item_model::ItemType;
+use crate::models::organization_model::{CollectionAccess, CollectionItem, Member, OrgCollection, Organization, OrgRole};
+use crate::models::password_policy_model::OrgPasswordPolicy;
+
+#[async_trait]
+pub trait OrganizationDao {
+    async fn create_organization(&self, name: &str, owner_id: i32) -> Result<Organization, DBError>;
+    async fn get_organizations(&self, user_id: i32) -> Result<Vec<Organization>, DBError>;
+    async fn delete_organization(&self, id: i32) -> Result<(), DBError>;
+    async fn get_membership(&self, organization_id: i32, user_id: i32) -> Result<Option<Member>, DBError>;
+    async fn get_member(&self, id: i32) -> Result<Member, DBError>;
+    async fn get_members(&self, organization_id: i32) -> Result<Vec<Member>, DBError>;
+    async fn invite_member(&self, organization_id: i32, user_id: i32, role: OrgRole, invited_by: i32) -> Result<Member, DBError>;
+    async fn accept_invitation(&self, member_id: i32) -> Result<(), DBError>;
+    async fn update_member_role(&self, member_id: i32, role: OrgRole) -> Result<(), DBError>;
+    async fn delete_member(&self, member_id: i32) -> Result<(), DBError>;
+    async fn create_collection(&self, organization_id: i32, name: &str) -> Result<OrgCollection, DBError>;
+    async fn get_collection(&self, id: i32) -> Result<OrgCollection, DBError>;
+    async fn get_collections(&self, organization_id: i32, member: &Member) -> Result<Vec<OrgCollection>, DBError>;
+    async fn delete_collection(&self, id: i32) -> Result<(), DBError>;
+    async fn set_collection_rotation(&self, id: i32, rotation_days: Option<i32>) -> Result<(), DBError>;
+    async fn get_collection_access(&self, collection_id: i32, member_id: i32) -> Result<Option<CollectionAccess>, DBError>;
+    async fn set_collection_access(&self, collection_id: i32, member_id: i32, access: CollectionAccess) -> Result<(), DBError>;
+    async fn delete_collection_access(&self, collection_id: i32, member_id: i32) -> Result<(), DBError>;
+    async fn get_collection_items(&self, collection_id: i32) -> Result<Vec<CollectionItem>, DBError>;
+    async fn add_collection_item(&self, collection_id: i32, item_type: ItemType, item_id: i32) -> Result<(), DBError>;
+    async fn remove_collection_item(&self, collection_id: i32, item_type: ItemType, item_id: i32) -> Result<(), DBError>;
+    async fn get_password_policy(&self, organization_id: i32) -> Result<OrgPasswordPolicy, DBError>;
+    async fn set_password_policy(&self, organization_id: i32, policy: &OrgPasswordPolicy) -> Result<(), DBError>;
+    async fn get_member_password_policy(&self, user_id: i32) -> Result<OrgPasswordPolicy, DBError>;
+}
+
+pub struct OrganizationDaoImpl {
+    db: PgPool,
+}
+
+impl OrganizationDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        OrganizationDaoImpl { db }
+    }
+}
+
+struct OrganizationRecord {
+    id: i32,
+    name: String,
+    role: String,
+    status: String,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+}
+
+impl TryFrom<OrganizationRecord> for Organization {
+    type Error = DBError;
+
+    fn try_from(record: OrganizationRecord) -> Result<Self, Self::Error> {
+        Ok(Organization {
+            id: record.id,
+            name: record.name,
+            role: record.role.parse().map_err(DBError::InvalidRole)?,
+            accepted: record.status == "accepted",
+            created_at: record.created_at.to_string(),
+        })
+    }
+}
+
+struct MemberRecord {
+    id: i32,
+    organization_id: i32,
+    user_id: i32,
+    username: String,
+    role: String,
+    status: String,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+}
+
+impl TryFrom<MemberRecord> for Member {
+    type Error = DBError;
+
+    fn try_from(record: MemberRecord) -> Result<Self, Self::Error> {
+        Ok(Member {
+            id: record.id,
+            organization_id: record.organization_id,
+            user_id: record.user_id,
+            username: record.username,
+            role: record.role.parse().map_err(DBError::InvalidRole)?,
+            accepted: record.status == "accepted",
+            created_at: record.created_at.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl OrganizationDao for OrganizationDaoImpl {
+    async fn create_organization(&self, name: &str, owner_id: i32) -> Result<Organization, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query!(
+            "INSERT INTO organizations (name) VALUES ($1) RETURNING id, name, created_at", name
+        ).fetch_one(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!(r#"
+            INSERT INTO organization_members (organization_id, user_id, role, status)
+            VALUES ($1, $2, 'owner', 'accepted')
+        "#, record.id, owner_id).execute(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(Organization {
+            id: record.id,
+            name: record.name,
+            role: OrgRole::Owner,
+            accepted: true,
+            created_at: record.created_at.to_string(),
+        })
+    }
+
+    async fn get_organizations(&self, user_id: i32) -> Result<Vec<Organization>, DBError> {
+        let records = sqlx::query_as!(OrganizationRecord, r#"
+            SELECT o.id, o.name, m.role, m.status, o.created_at
+            FROM organizations o
+            JOIN organization_members m ON m.organization_id = o.id
+            WHERE m.user_id = $1
+            ORDER BY o.id
+        "#, user_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(Organization::try_from).collect()
+    }
+
+    async fn delete_organization(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM organizations WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_membership(&self, organization_id: i32, user_id: i32) -> Result<Option<Member>, DBError> {
+        let record = sqlx::query_as!(MemberRecord, r#"
+            SELECT m.id, m.organization_id, m.user_id, u.username, m.role, m.status, m.created_at
+            FROM organization_members m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.organization_id = $1 AND m.user_id = $2
+        "#, organization_id, user_id).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        record.map(Member::try_from).transpose()
+    }
+
+    async fn get_member(&self, id: i32) -> Result<Member, DBError> {
+        let record = sqlx::query_as!(MemberRecord, r#"
+            SELECT m.id, m.organization_id, m.user_id, u.username, m.role, m.status, m.created_at
+            FROM organization_members m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Member::try_from(record)
+    }
+
+    async fn get_members(&self, organization_id: i32) -> Result<Vec<Member>, DBError> {
+        let records = sqlx::query_as!(MemberRecord, r#"
+            SELECT m.id, m.organization_id, m.user_id, u.username, m.role, m.status, m.created_at
+            FROM organization_members m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.organization_id = $1
+            ORDER BY m.id
+        "#, organization_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(Member::try_from).collect()
+    }
+
+    async fn invite_member(&self, organization_id: i32, user_id: i32, role: OrgRole, invited_by: i32) -> Result<Member, DBError> {
+        let id = sqlx::query_scalar!(r#"
+            INSERT INTO organization_members (organization_id, user_id, role, invited_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+        "#, organization_id, user_id, role.as_str(), invited_by).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        self.get_member(id).await
+    }
+
+    async fn accept_invitation(&self, member_id: i32) -> Result<(), DBError> {
+        sqlx::query!("UPDATE organization_members SET status = 'accepted' WHERE id = $1", member_id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn update_member_role(&self, member_id: i32, role: OrgRole) -> Result<(), DBError> {
+        sqlx::query!("UPDATE organization_members SET role = $1 WHERE id = $2", role.as_str(), member_id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn delete_member(&self, member_id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM organization_members WHERE id = $1", member_id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn create_collection(&self, organization_id: i32, name: &str) -> Result<OrgCollection, DBError> {
+        let record = sqlx::query!(r#"
+            INSERT INTO org_collections (organization_id, name) VALUES ($1, $2)
+            RETURNING id, organization_id, name, rotation_days, created_at
+        "#, organization_id, name).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(OrgCollection {
+            id: record.id,
+            organization_id: record.organization_id,
+            name: record.name,
+            rotation_days: record.rotation_days,
+            created_at: record.created_at.to_string(),
+        })
+    }
+
+    async fn get_collection(&self, id: i32) -> Result<OrgCollection, DBError> {
+        let record = sqlx::query!("SELECT id, organization_id, name, rotation_days, created_at FROM org_collections WHERE id = $1", id)
+            .fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(OrgCollection {
+            id: record.id,
+            organization_id: record.organization_id,
+            name: record.name,
+            rotation_days: record.rotation_days,
+            created_at: record.created_at.to_string(),
+        })
+    }
+
+    /// Owners and admins see every collection, everyone else the ones they have access to.
+    async fn get_collections(&self, organization_id: i32, member: &Member) -> Result<Vec<OrgCollection>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT c.id, c.organization_id, c.name, c.rotation_days, c.created_at
+            FROM org_collections c
+            WHERE c.organization_id = $1
+              AND ($2 OR EXISTS(SELECT 1 FROM collection_access a WHERE a.collection_id = c.id AND a.member_id = $3))
+            ORDER BY c.id
+        "#, organization_id, member.role >= OrgRole::Admin, member.id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|record| OrgCollection {
+            id: record.id,
+            organization_id: record.organization_id,
+            name: record.name,
+            rotation_days: record.rotation_days,
+            created_at: record.created_at.to_string(),
+        }).collect())
+    }
+
+    async fn delete_collection(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM org_collections WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn set_collection_rotation(&self, id: i32, rotation_days: Option<i32>) -> Result<(), DBError> {
+        sqlx::query!("UPDATE org_collections SET rotation_days = $1 WHERE id = $2", rotation_days, id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_collection_access(&self, collection_id: i32, member_id: i32) -> Result<Option<CollectionAccess>, DBError> {
+        let access = sqlx::query_scalar!(
+            "SELECT access FROM collection_access WHERE collection_id = $1 AND member_id = $2",
+            collection_id, member_id
+        ).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        access.map(|a| a.parse().map_err(DBError::InvalidAccess)).transpose()
+    }
+
+    async fn set_collection_access(&self, collection_id: i32, member_id: i32, access: CollectionAccess) -> Result<(), DBError> {
+        sqlx::query!(r#"
+            INSERT INTO collection_access (collection_id, member_id, access) VALUES ($1, $2, $3)
+            ON CONFLICT (collection_id, member_id) DO UPDATE SET access = EXCLUDED.access
+        "#, collection_id, member_id, access.as_str()).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn delete_collection_access(&self, collection_id: i32, member_id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM collection_access WHERE collection_id = $1 AND member_id = $2", collection_id, member_id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_collection_items(&self, collection_id: i32) -> Result<Vec<CollectionItem>, DBError> {
+        let records = sqlx::query!(
+            "SELECT item_type, item_id FROM collection_items WHERE collection_id = $1 ORDER BY item_type, item_id",
+            collection_id
+        ).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(|record| Ok(CollectionItem {
+            item_type: record.item_type.parse().map_err(DBError::InvalidItemType)?,
+            item_id: record.item_id,
+        })).collect()
+    }
+
+    async fn add_collection_item(&self, collection_id: i32, item_type: ItemType, item_id: i32) -> Result<(), DBError> {
+        sqlx::query!(r#"
+            INSERT INTO collection_items (collection_id, item_type, item_id) VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+        "#, collection_id, item_type.as_str(), item_id).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn remove_collection_item(&self, collection_id: i32, item_type: ItemType, item_id: i32) -> Result<(), DBError> {
+        sqlx::query!(
+            "DELETE FROM collection_items WHERE collection_id = $1 AND item_type = $2 AND item_id = $3",
+            collection_id, item_type.as_str(), item_id
+        ).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_password_policy(&self, organization_id: i32) -> Result<OrgPasswordPolicy, DBError> {
+        let record = sqlx::query!(
+            "SELECT min_password_length, min_password_score FROM organizations WHERE id = $1",
+            organization_id
+        ).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(OrgPasswordPolicy { min_length: record.min_password_length, min_score: record.min_password_score })
+    }
+
+    async fn set_password_policy(&self, organization_id: i32, policy: &OrgPasswordPolicy) -> Result<(), DBError> {
+        sqlx::query!(
+            "UPDATE organizations SET min_password_length = $1, min_password_score = $2 WHERE id = $3",
+            policy.min_length, policy.min_score, organization_id
+        ).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    /// The strictest requirements of all organizations the user is an accepted member of.
+    async fn get_member_password_policy(&self, user_id: i32) -> Result<OrgPasswordPolicy, DBError> {
+        let record = sqlx::query!(r#"
+            SELECT max(o.min_password_length) as min_length, max(o.min_password_score) as min_score
+            FROM organizations o
+            JOIN organization_members m ON m.organization_id = o.id
+            WHERE m.user_id = $1 AND m.status = 'accepted'
+        "#, user_id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(OrgPasswordPolicy { min_length: record.min_length, min_score: record.min_score })
+    }
+}
@@ -2,14 +2,19 @@ use async_trait::async_trait;
 use sqlx::PgPool;
 
 use crate::models::DBError;
-use crate::models::payment_model::{Payment, PaymentDto};
+use crate::models::item_model::ItemType;
+use crate::models::payment_model::{CardBrand, normalize_card_number, Payment, PaymentDto};
+use crate::models::share_model::Permission;
+use crate::persistence::custom_field_dao::{load_custom_fields, save_custom_fields};
 
 #[async_trait]
 pub trait PaymentDao {
     async fn create_payment(&self, payment: PaymentDto, owner_id: i32) -> Result<Payment, DBError>;
-    async fn update_payment(&self, id: i32, payment_dto: PaymentDto) -> Result<Payment, DBError>;
+    /// Bumps the revision. With `if_match`, fails with `StaleRevision` unless that is the stored revision.
+    async fn update_payment(&self, id: i32, payment_dto: PaymentDto, if_match: Option<i64>) -> Result<Payment, DBError>;
     async fn get_payment(&self, id: i32) -> Result<Payment, DBError>;
-    async fn get_payments(&self, owner_id: i32) -> Result<Vec<Payment>, DBError>;
+    /// Everything the user owns or was granted; with `since`, only what changed after that sync revision.
+    async fn get_payments(&self, user_id: i32, since: Option<i64>) -> Result<Vec<Payment>, DBError>;
     async fn delete_payment(&self, id: i32) -> Result<(), DBError>;
 }
 
@@ -28,49 +33,67 @@ impl PaymentDaoImpl {
 #[async_trait]
 impl PaymentDao for PaymentDaoImpl {
     async fn create_payment(&self, payment: PaymentDto, owner_id: i32) -> Result<Payment, DBError> {
+        let card_number = payment.card_number.as_deref().map(normalize_card_number);
+        let brand = CardBrand::detect(card_number.as_deref().unwrap_or(""));
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
         let record = sqlx::query!(
             r#"
-                  INSERT INTO payments (card_holder, card_number, security_code, expiration_month, expiration_year, name, color, note, owner_id)
-                  VALUES ($1, $2, $3, $4, $5, $6, $7,$8, $9 )
-                  RETURNING id, card_holder, card_number, security_code, expiration_month, expiration_year, name, color, note, owner_id
+                  INSERT INTO payments (card_holder, card_number, security_code, expiration_month, expiration_year, name, color, note, owner_id, brand)
+                  VALUES ($1, $2, $3, $4, $5, $6, $7,$8, $9, $10)
+                  RETURNING id, card_holder, card_number, security_code, expiration_month, expiration_year, name, color, note, owner_id, brand, revision
             "#,
             payment.card_holder,
-            payment.card_number,
+            card_number,
             payment.security_code,
             payment.expiration_month,
             payment.expiration_year,
             payment.name,
             payment.color,
             payment.note,
-            owner_id
-        ).fetch_one(&self.db)
+            owner_id,
+            brand.as_str()
+        ).fetch_one(&mut tx)
             .await
             .map_err(|e| DBError::Other(Box::new(e)))?;
 
+        let custom_fields = save_custom_fields(&mut tx, ItemType::Payment, record.id, payment.custom_fields.unwrap_or_default()).await?;
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
         return Ok(
             Payment {
                 id: record.id,
                 card_holder: record.card_holder.to_string(),
                 card_number: record.card_number.to_string(),
                 security_code: record.security_code,
+                brand: record.brand.parse().map_err(DBError::InvalidCardBrand)?,
                 expiration_month: record.expiration_month,
                 expiration_year: record.expiration_year,
                 name: record.name.to_string(),
                 color: record.color.to_string(),
                 note: record.note.unwrap_or("".to_string()),
+                custom_fields,
+                revision: record.revision,
+                shared: false,
+                permission: Permission::Owner,
             }
         );
     }
 
 
-    async fn update_payment(&self, id: i32, payment_dto: PaymentDto) -> Result<Payment, DBError> {
+    async fn update_payment(&self, id: i32, payment_dto: PaymentDto, if_match: Option<i64>) -> Result<Payment, DBError> {
         let mut payment = self.get_payment(id).await?;
 
         if let Some(card_holder) = payment_dto.card_holder {
             payment.card_holder = card_holder;
         }
         if let Some(card_number) = payment_dto.card_number {
-            payment.card_number = card_number;
+            payment.card_number = normalize_card_number(&card_number);
+            payment.brand = CardBrand::detect(&payment.card_number);
         }
         if let Some(security_code) = payment_dto.security_code {
             payment.security_code = security_code;
@@ -91,9 +114,14 @@ impl PaymentDao for PaymentDaoImpl {
             payment.note = note;
         }
 
-        sqlx::query!(r#"
-            Update payments set card_holder = $1, card_number = $2, security_code = $3, expiration_month = $4, expiration_year = $5, name = $6,color = $7, note = $8
-            where id = $9
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        payment.revision = sqlx::query_scalar!(r#"
+            Update payments set card_holder = $1, card_number = $2, security_code = $3, expiration_month = $4, expiration_year = $5, name = $6,color = $7, note = $8, brand = $9,
+                revision = revision + 1
+            where id = $10 AND ($11::bigint IS NULL OR revision = $11)
+            RETURNING revision
         "#,
             payment.card_holder,
             payment.card_number,
@@ -103,9 +131,19 @@ impl PaymentDao for PaymentDaoImpl {
             payment.name,
             payment.color,
             payment.note,
-            id
-        ).execute(&self.db)
+            payment.brand.as_str(),
+            id,
+            if_match
+        ).fetch_optional(&mut tx)
             .await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .ok_or(DBError::StaleRevision)?;
+
+        if let Some(custom_fields) = payment_dto.custom_fields {
+            payment.custom_fields = save_custom_fields(&mut tx, ItemType::Payment, id, custom_fields).await?;
+        }
+
+        tx.commit().await
             .map_err(|e| DBError::Other(Box::new(e)))?;
 
         return Ok(payment);
@@ -123,35 +161,66 @@ impl PaymentDao for PaymentDaoImpl {
             card_holder: record.card_holder.to_string(),
             card_number: record.card_number.to_string(),
             security_code: record.security_code,
+            brand: record.brand.parse().map_err(DBError::InvalidCardBrand)?,
             expiration_month: record.expiration_month,
             expiration_year: record.expiration_year,
             name: record.name.to_string(),
             color: record.color.to_string(),
             note: record.note.unwrap_or("".to_string()),
+            custom_fields: load_custom_fields(&self.db, ItemType::Payment, &[id]).await?.remove(&id).unwrap_or_default(),
+            revision: record.revision,
+            shared: false,
+            permission: Permission::Owner,
         });
     }
 
-    async fn get_payments(&self, owner_id: i32) -> Result<Vec<Payment>, DBError> {
+    async fn get_payments(&self, user_id: i32, since: Option<i64>) -> Result<Vec<Payment>, DBError> {
         let record = sqlx::query!(r#"
-                SELECT * FROM payments WHERE owner_id = $1
-            "#, owner_id ).fetch_all(&self.db)
+                SELECT p.*, COALESCE(s.permission, 'owner') as "permission!"
+                FROM payments p
+                LEFT JOIN LATERAL (
+                    SELECT permission FROM item_permissions
+                    WHERE item_type = 'payment' AND item_id = p.id AND user_id = $1
+                    ORDER BY rank DESC LIMIT 1
+                ) s ON p.owner_id IS DISTINCT FROM $1
+                WHERE (p.owner_id = $1 OR s.permission IS NOT NULL)
+                  AND ($2::bigint IS NULL OR EXISTS(
+                      SELECT 1 FROM sync_changes c
+                      WHERE c.user_id = $1 AND c.item_type = 'payment' AND c.item_id = p.id AND c.revision > $2
+                  ))
+                ORDER BY p.id
+            "#, user_id, since).fetch_all(&self.db)
             .await.map_err(|err| DBError::Other(Box::new(err)))?;
 
-        return Ok(record.iter().map(|r| Payment {
-            id: r.id,
-            card_holder: r.card_holder.to_string(),
-            card_number: r.card_number.to_string(),
-            security_code: r.security_code,
-            expiration_month: r.expiration_month,
-            expiration_year: r.expiration_year,
-            name: r.name.to_string(),
-            color: r.color.to_string(),
-            note: r.note.to_owned().unwrap_or("".to_string()),
-        }).collect());
+        let ids: Vec<i32> = record.iter().map(|r| r.id).collect();
+        let mut custom_fields = load_custom_fields(&self.db, ItemType::Payment, &ids).await?;
+
+        return record.iter().map(|r| {
+            let mut payment = Payment {
+                id: r.id,
+                card_holder: r.card_holder.to_string(),
+                card_number: r.card_number.to_string(),
+                security_code: r.security_code,
+                brand: r.brand.parse().map_err(DBError::InvalidCardBrand)?,
+                expiration_month: r.expiration_month,
+                expiration_year: r.expiration_year,
+                name: r.name.to_string(),
+                color: r.color.to_string(),
+                note: r.note.to_owned().unwrap_or("".to_string()),
+                custom_fields: custom_fields.remove(&r.id).unwrap_or_default(),
+                revision: r.revision,
+                shared: r.permission != "owner",
+                permission: r.permission.parse().map_err(DBError::InvalidPermission)?,
+            };
+            if payment.permission == Permission::HidePasswords {
+                payment.hide_secrets();
+            }
+            Ok(payment)
+        }).collect();
     }
 
     async fn delete_payment(&self, id: i32) -> Result<(), DBError> {
-        sqlx::query!(r#"DELETE FROM logins WHERE id = $1"#, id).execute(&self.db).await.map_err(
+        sqlx::query!(r#"DELETE FROM payments WHERE id = $1"#, id).execute(&self.db).await.map_err(
             |e| DBError::Other(Box::new(e))
         )?;
 
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use log::error;
+use rocket::serde::json::serde_json;
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+
+use crate::models::push_model::{PushEvent, PushMessage};
+
+const PUSH_CHANNEL: &str = "lockdown_push";
+const PUSH_BUFFER_SIZE: usize = 1024;
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Fans push events out to connected devices. Every stream subscribes to one in-process
+/// broadcast channel and picks out its user's events. With PUSH_BACKEND=postgres, events are
+/// published through Postgres NOTIFY and every instance relays what it hears to its own
+/// streams, so devices connected to other instances get them too.
+pub struct PushHub {
+    sender: broadcast::Sender<PushMessage>,
+    /// Set when events go through Postgres.
+    db: Option<PgPool>,
+}
+
+impl PushHub {
+    /// Reads PUSH_BACKEND, `local` (the default) for a single instance or `postgres`.
+    pub async fn from_env(db: PgPool) -> Self {
+        let (sender, _) = broadcast::channel(PUSH_BUFFER_SIZE);
+
+        match std::env::var("PUSH_BACKEND").unwrap_or(String::from("local")).as_str() {
+            "local" => PushHub { sender, db: None },
+            "postgres" => {
+                let mut listener = PgListener::connect_with(&db).await
+                    .expect("Failed to connect the push listener.");
+                listener.listen(PUSH_CHANNEL).await
+                    .expect("Failed to listen for push events.");
+                tokio::spawn(relay(listener, sender.clone()));
+
+                PushHub { sender, db: Some(db) }
+            }
+            other => panic!("Unknown PUSH_BACKEND: {}", other),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PushMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Sends `event` to every connected device of `user_ids`. Like audit events, pushes never
+    /// hold up or fail the request; failures are logged.
+    pub fn publish(&self, user_ids: Vec<i32>, event: PushEvent) {
+        if user_ids.is_empty() {
+            return;
+        }
+        let message = PushMessage { user_ids, event };
+
+        match &self.db {
+            // Sending only fails when no device is connected.
+            None => { let _ = self.sender.send(message); }
+            Some(db) => {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    let payload = serde_json::to_string(&message).expect("Push messages serialize");
+                    if let Err(err) = sqlx::query!("SELECT pg_notify($1, $2)", PUSH_CHANNEL, payload).execute(&db).await {
+                        error!("Failed to publish push event: {:?}", err);
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Hands events from Postgres to the local streams. The listener reconnects by itself after
+/// errors; events sent while it was disconnected are lost.
+async fn relay(mut listener: PgListener, sender: broadcast::Sender<PushMessage>) {
+    loop {
+        match listener.recv().await {
+            Ok(notification) => match serde_json::from_str::<PushMessage>(notification.payload()) {
+                Ok(message) => { let _ = sender.send(message); }
+                Err(err) => error!("Ignoring malformed push event: {}", err),
+            },
+            Err(err) => {
+                error!("Push listener failed: {:?}", err);
+                tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+            }
+        }
+    }
+}
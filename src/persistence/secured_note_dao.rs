@@ -2,20 +2,20 @@ use async_trait::async_trait;
 use sqlx::PgPool;
 
 use crate::models::DBError;
-use crate::models::secured_note::{File, FileDto, SecuredNote, SecuredNoteDto};
+use crate::models::item_model::ItemType;
+use crate::models::secured_note::{SecuredNote, SecuredNoteDto};
+use crate::models::share_model::Permission;
+use crate::persistence::custom_field_dao::{load_custom_fields, save_custom_fields};
 
 #[async_trait]
 pub trait SecuredNoteDao {
     async fn create_secured_note(&self, secured_note: SecuredNoteDto, owner_id: i32) -> Result<SecuredNote, DBError>;
     async fn get_secured_note(&self, id: i32) -> Result<SecuredNote, DBError>;
-    async fn get_secured_notes(&self, owner_id: i32) -> Result<Vec<SecuredNote>, DBError>;
-    async fn update_secured_notes(&self, id: i32, secured_note: SecuredNoteDto) -> Result<SecuredNote, DBError>;
+    /// Everything the user owns or was granted; with `since`, only what changed after that sync revision.
+    async fn get_secured_notes(&self, user_id: i32, since: Option<i64>) -> Result<Vec<SecuredNote>, DBError>;
+    /// Bumps the revision. With `if_match`, fails with `StaleRevision` unless that is the stored revision.
+    async fn update_secured_notes(&self, id: i32, secured_note: SecuredNoteDto, if_match: Option<i64>) -> Result<SecuredNote, DBError>;
     async fn delete_secured_note(&self, id: i32) -> Result<(), DBError>;
-    async fn get_secured_note_owner(&self, id: i32) -> Result<i32, DBError>;
-    async fn save_file(&self, owner_id: i32, file: FileDto, note_id: i32) -> Result<File, DBError>;
-    async fn get_secured_note_attachment(&self, id: i32) -> Result<File, DBError>;
-    async fn get_secured_note_attachments(&self, note_id: i32) -> Result<Vec<File>, DBError>;
-    async fn delete_secured_note_attachment(&self, id: i32) -> Result<(), DBError>;
 }
 
 pub struct SecuredNoteDaoImpl {
@@ -31,19 +31,27 @@ impl SecuredNoteDaoImpl {
 #[async_trait]
 impl SecuredNoteDao for SecuredNoteDaoImpl {
     async fn create_secured_note(&self, secured_note: SecuredNoteDto, owner_id: i32) -> Result<SecuredNote, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
         let record = sqlx::query!(r#"
            INSERT INTO secured_notes (name, content, color, owner_id)
             VALUES ($1, $2, $3, $4)
-            RETURNING id, name, content, created_at, modified_at, color, owner_id
+            RETURNING id, name, content, created_at, modified_at, color, owner_id, revision
         "#,
             secured_note.name,
             secured_note.content,
             secured_note.color,
             owner_id,
-        ).fetch_one(&self.db)
+        ).fetch_one(&mut tx)
             .await
             .map_err(|e| DBError::Other(Box::new(e)))?;
 
+        let custom_fields = save_custom_fields(&mut tx, ItemType::SecuredNote, record.id, secured_note.custom_fields.unwrap_or_default()).await?;
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
         Ok(SecuredNote {
             id: record.id,
             name: record.name,
@@ -51,6 +59,10 @@ impl SecuredNoteDao for SecuredNoteDaoImpl {
             created_at: record.created_at.to_string(),
             modified_at: record.modified_at.to_string(),
             color: record.color,
+            custom_fields,
+            revision: record.revision,
+            shared: false,
+            permission: Permission::Owner,
         })
     }
 
@@ -70,31 +82,50 @@ impl SecuredNoteDao for SecuredNoteDaoImpl {
             created_at: record.created_at.to_string(),
             modified_at: record.modified_at.to_string(),
             color: record.color,
+            custom_fields: load_custom_fields(&self.db, ItemType::SecuredNote, &[id]).await?.remove(&id).unwrap_or_default(),
+            revision: record.revision,
+            shared: false,
+            permission: Permission::Owner,
         })
     }
 
-    async fn get_secured_notes(&self, owner_id: i32) -> Result<Vec<SecuredNote>, DBError> {
+    async fn get_secured_notes(&self, user_id: i32, since: Option<i64>) -> Result<Vec<SecuredNote>, DBError> {
         let record = sqlx::query!(r#"
-           Select * FROM secured_notes
-            WHERE owner_id = $1
-            ORDER BY id
+           Select n.*, COALESCE(s.permission, 'owner') as "permission!" FROM secured_notes n
+            LEFT JOIN LATERAL (
+                SELECT permission FROM item_permissions
+                WHERE item_type = 'secured_note' AND item_id = n.id AND user_id = $1
+                ORDER BY rank DESC LIMIT 1
+            ) s ON n.owner_id IS DISTINCT FROM $1
+            WHERE (n.owner_id = $1 OR s.permission IS NOT NULL)
+              AND ($2::bigint IS NULL OR EXISTS(
+                  SELECT 1 FROM sync_changes c
+                  WHERE c.user_id = $1 AND c.item_type = 'secured_note' AND c.item_id = n.id AND c.revision > $2
+              ))
+            ORDER BY n.id
         "#,
-            owner_id
+            user_id, since
         ).fetch_all(&self.db).await
             .map_err(|e| DBError::Other(Box::new(e)))?;
 
+        let ids: Vec<i32> = record.iter().map(|r| r.id).collect();
+        let mut custom_fields = load_custom_fields(&self.db, ItemType::SecuredNote, &ids).await?;
 
-        Ok(record.iter().map(|r| SecuredNote {
+        record.iter().map(|r| Ok(SecuredNote {
             id: r.id,
             name: r.name.to_string(),
             content: r.content.to_string(),
             created_at: r.created_at.to_string(),
             modified_at: r.modified_at.to_string(),
             color: r.color.to_string(),
-        }).collect())
+            custom_fields: custom_fields.remove(&r.id).unwrap_or_default(),
+            revision: r.revision,
+            shared: r.permission != "owner",
+            permission: r.permission.parse().map_err(DBError::InvalidPermission)?,
+        })).collect()
     }
 
-    async fn update_secured_notes(&self, id: i32, secured_note: SecuredNoteDto) -> Result<SecuredNote, DBError> {
+    async fn update_secured_notes(&self, id: i32, secured_note: SecuredNoteDto, if_match: Option<i64>) -> Result<SecuredNote, DBError> {
         let mut _secured_note = self.get_secured_note(id).await?;
 
         if let Some(name) = secured_note.name {
@@ -109,17 +140,30 @@ impl SecuredNoteDao for SecuredNoteDaoImpl {
             _secured_note.content = content;
         }
 
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
         let record = sqlx::query!(r#"
-            UPDATE secured_notes set name = $1, content = $2, color = $3
-            WHERE id = $4
+            UPDATE secured_notes set name = $1, content = $2, color = $3, revision = revision + 1
+            WHERE id = $4 AND ($5::bigint IS NULL OR revision = $5)
             RETURNING *
         "#,
             _secured_note.name,
             _secured_note.content,
             _secured_note.color,
-            id
-        ).fetch_one(&self.db)
+            id,
+            if_match
+        ).fetch_optional(&mut tx)
             .await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .ok_or(DBError::StaleRevision)?;
+
+        let custom_fields = match secured_note.custom_fields {
+            Some(custom_fields) => save_custom_fields(&mut tx, ItemType::SecuredNote, id, custom_fields).await?,
+            None => _secured_note.custom_fields,
+        };
+
+        tx.commit().await
             .map_err(|e| DBError::Other(Box::new(e)))?;
 
         Ok(SecuredNote {
@@ -129,6 +173,10 @@ impl SecuredNoteDao for SecuredNoteDaoImpl {
             created_at: record.created_at.to_string(),
             modified_at: record.modified_at.to_string(),
             color: record.color,
+            custom_fields,
+            revision: record.revision,
+            shared: false,
+            permission: Permission::Owner,
         })
     }
 
@@ -140,81 +188,4 @@ impl SecuredNoteDao for SecuredNoteDaoImpl {
 
         Ok(())
     }
-
-    async fn get_secured_note_owner(&self, id: i32) -> Result<i32, DBError> {
-        let record = sqlx::query!(r#" SELECT owner_id FROM secured_notes WHERE id = $1"#, id).fetch_one(&self.db).await.map_err(
-            |e| DBError::Other(Box::new(e))
-        )?;
-
-        return Ok(record.owner_id.unwrap());
-    }
-
-    async fn get_secured_note_attachments(&self, note_id: i32) -> Result<Vec<File>, DBError> {
-        let records = sqlx::query!(r#"
-            SELECT type as file_type, * from note_attachments
-            where note_id = $1
-        "#, note_id).fetch_all(&self.db).await
-            .map_err(|e| DBError::Other(Box::new(e)))?;
-
-
-        Ok(records.iter().map(|record| File {
-            id: record.id,
-            name: record.name.to_string(),
-            created_at: record.created_at.to_string(),
-            size: record.size,
-            file_type: record.file_type.to_string(),
-            note_id: record.note_id,
-            owner_id: record.owner_id.unwrap(),
-        }).collect())
-    }
-
-    async fn save_file(&self, owner_id: i32, file: FileDto, note_id: i32) -> Result<File, DBError> {
-        let record = sqlx::query!(r#"
-            INSERT INTO note_attachments (name, size, type, note_id, owner_id)
-            VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, name, created_at, size, type, note_id, type as file_type, owner_id
-        "#,
-        file.name, file.size, file.file_type, note_id, owner_id
-        ).fetch_one(&self.db).await
-            .map_err(|err| DBError::Other(Box::new(err)))?;
-
-
-        Ok(File {
-            id: record.id,
-            name: record.name,
-            created_at: record.created_at.to_string(),
-            size: record.size,
-            file_type: record.file_type,
-            note_id: record.note_id,
-            owner_id: record.owner_id.unwrap(),
-        })
-    }
-
-    async fn get_secured_note_attachment(&self, id: i32) -> Result<File, DBError> {
-        let record = sqlx::query!(r#"
-            SELECT type as file_type, * from note_attachments where id = $1
-        "#, id).fetch_one(&self.db).await
-            .map_err(|err| DBError::Other(Box::new(err)))?;
-
-        Ok(File {
-            id: record.id,
-            name: record.name,
-            created_at: record.created_at.to_string(),
-            size: record.size,
-            file_type: record.file_type,
-            note_id: record.note_id,
-            owner_id: record.owner_id.unwrap(),
-        })
-    }
-
-    async fn delete_secured_note_attachment(&self, id: i32) -> Result<(), DBError> {
-        sqlx::query!(r#"DELETE from note_attachments where id = $1"#,id)
-            .execute(&self.db).await
-            .map_err(|err| DBError::Other(Box::new(err)))?;
-
-        Ok(())
-    }
 }
-
-
-
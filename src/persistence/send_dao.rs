@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use bcrypt::DEFAULT_COST;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::send_model::{DEFAULT_SEND_EXPIRY_MINUTES, MAX_SEND_PASSWORD_ATTEMPTS, SecureSend, SecureSendDto, SecureSendRecord, SEND_LOCKOUT_MINUTES};
+
+#[async_trait]
+pub trait SendDao {
+    async fn create_send(&self, owner_id: i32, token: String, send: SecureSendDto, file: Option<(String, i32)>) -> Result<SecureSend, DBError>;
+    async fn get_sends(&self, owner_id: i32) -> Result<Vec<SecureSend>, DBError>;
+    async fn get_send_owner(&self, id: i32) -> Result<i32, DBError>;
+    async fn get_send_by_token(&self, token: &str) -> Result<SecureSendRecord, DBError>;
+    async fn record_view(&self, id: i32) -> Result<bool, DBError>;
+    /// Counts a wrong password, locking the send once `MAX_SEND_PASSWORD_ATTEMPTS` is reached.
+    async fn record_failed_password(&self, id: i32) -> Result<(), DBError>;
+    async fn delete_send(&self, id: i32) -> Result<(), DBError>;
+    /// Deletes sends that expired or ran out of views, returning their ids.
+    async fn delete_finished_sends(&self) -> Result<Vec<i32>, DBError>;
+}
+
+pub struct SendDaoImpl {
+    db: PgPool,
+}
+
+impl SendDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        SendDaoImpl { db }
+    }
+}
+
+#[async_trait]
+impl SendDao for SendDaoImpl {
+    async fn create_send(&self, owner_id: i32, token: String, send: SecureSendDto, file: Option<(String, i32)>) -> Result<SecureSend, DBError> {
+        let password = match send.password.filter(|p| !p.is_empty()) {
+            Some(password) => Some(bcrypt::hash(password, DEFAULT_COST).map_err(|e| DBError::Other(Box::new(e)))?),
+            None => None,
+        };
+        let expires_in_minutes = send.expires_in_minutes.unwrap_or(DEFAULT_SEND_EXPIRY_MINUTES);
+        let kind = if file.is_some() { "file" } else { "text" };
+        let (file_name, file_size) = file.unzip();
+
+        let record = sqlx::query!(r#"
+            INSERT INTO sends (token, owner_id, kind, content, file_name, file_size, password, hide_email, max_views, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, now() + make_interval(mins => $10))
+            RETURNING id, token, kind, file_name, file_size, password, hide_email, max_views, view_count, expires_at, created_at
+        "#,
+            token,
+            owner_id,
+            kind,
+            send.text,
+            file_name,
+            file_size,
+            password,
+            send.hide_email.unwrap_or(false),
+            send.max_views,
+            expires_in_minutes
+        ).fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(SecureSend {
+            id: record.id,
+            token: record.token,
+            kind: record.kind,
+            file_name: record.file_name,
+            file_size: record.file_size,
+            has_password: record.password.is_some(),
+            hide_email: record.hide_email,
+            max_views: record.max_views,
+            view_count: record.view_count,
+            expires_at: record.expires_at.to_string(),
+            created_at: record.created_at.to_string(),
+        })
+    }
+
+    async fn get_sends(&self, owner_id: i32) -> Result<Vec<SecureSend>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT id, token, kind, file_name, file_size, password, hide_email, max_views, view_count, expires_at, created_at
+            FROM sends
+            WHERE owner_id = $1
+            ORDER BY id
+        "#, owner_id).fetch_all(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|record| SecureSend {
+            id: record.id,
+            token: record.token,
+            kind: record.kind,
+            file_name: record.file_name,
+            file_size: record.file_size,
+            has_password: record.password.is_some(),
+            hide_email: record.hide_email,
+            max_views: record.max_views,
+            view_count: record.view_count,
+            expires_at: record.expires_at.to_string(),
+            created_at: record.created_at.to_string(),
+        }).collect())
+    }
+
+    async fn get_send_owner(&self, id: i32) -> Result<i32, DBError> {
+        let record = sqlx::query!("SELECT owner_id FROM sends WHERE id = $1", id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(record.owner_id)
+    }
+
+    async fn get_send_by_token(&self, token: &str) -> Result<SecureSendRecord, DBError> {
+        let record = sqlx::query!(r#"
+            SELECT s.id, s.kind, s.content, s.file_name, s.file_size, s.password, s.max_views, s.view_count, s.expires_at,
+                   CASE WHEN s.hide_email THEN NULL ELSE u.email END as creator_email,
+                   (s.expires_at > now() AND (s.max_views IS NULL OR s.view_count < s.max_views)) as "available!",
+                   COALESCE(s.locked_until > now(), false) as "locked!"
+            FROM sends s
+            JOIN users u ON u.id = s.owner_id
+            WHERE s.token = $1
+        "#, token).fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(SecureSendRecord {
+            id: record.id,
+            kind: record.kind,
+            content: record.content,
+            file_name: record.file_name,
+            file_size: record.file_size,
+            password: record.password,
+            max_views: record.max_views,
+            view_count: record.view_count,
+            expires_at: record.expires_at.to_string(),
+            creator_email: record.creator_email,
+            available: record.available,
+            locked: record.locked,
+        })
+    }
+
+    async fn record_view(&self, id: i32) -> Result<bool, DBError> {
+        // The limits are checked again here so that concurrent views can't exceed max_views.
+        let result = sqlx::query!(r#"
+            UPDATE sends SET view_count = view_count + 1
+            WHERE id = $1 AND expires_at > now() AND (max_views IS NULL OR view_count < max_views)
+        "#, id).execute(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn record_failed_password(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!(r#"
+            UPDATE sends SET
+                failed_attempts = CASE WHEN failed_attempts + 1 >= $2 THEN 0 ELSE failed_attempts + 1 END,
+                locked_until = CASE WHEN failed_attempts + 1 >= $2 THEN now() + make_interval(mins => $3) ELSE locked_until END
+            WHERE id = $1
+        "#, id, MAX_SEND_PASSWORD_ATTEMPTS, SEND_LOCKOUT_MINUTES).execute(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn delete_send(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM sends WHERE id = $1", id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn delete_finished_sends(&self) -> Result<Vec<i32>, DBError> {
+        let records = sqlx::query!(r#"
+            DELETE FROM sends
+            WHERE expires_at <= now() OR (max_views IS NOT NULL AND view_count >= max_views)
+            RETURNING id
+        "#).fetch_all(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(records.into_iter().map(|record| record.id).collect())
+    }
+}
@@ -0,0 +1,175 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::item_model::ItemType;
+use crate::models::share_model::{Permission, Share};
+
+#[async_trait]
+pub trait ShareDao {
+    async fn get_item_owner(&self, item_type: ItemType, item_id: i32) -> Result<i32, DBError>;
+    /// Strongest permission granted through shares or organization collections.
+    async fn get_permission(&self, item_type: ItemType, item_id: i32, user_id: i32) -> Result<Option<Permission>, DBError>;
+    /// The owner and everyone the item is granted to, through shares or collections.
+    async fn get_item_users(&self, item_type: ItemType, item_id: i32) -> Result<Vec<i32>, DBError>;
+    async fn create_share(&self, owner_id: i32, shared_with_id: i32, item_type: ItemType, item_id: i32, permission: Permission) -> Result<Share, DBError>;
+    async fn get_share(&self, id: i32) -> Result<Share, DBError>;
+    async fn get_shares_with_user(&self, user_id: i32) -> Result<Vec<Share>, DBError>;
+    async fn get_shares_by_user(&self, owner_id: i32) -> Result<Vec<Share>, DBError>;
+    async fn delete_share(&self, id: i32) -> Result<(), DBError>;
+}
+
+pub struct ShareDaoImpl {
+    db: PgPool,
+}
+
+impl ShareDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        ShareDaoImpl { db }
+    }
+}
+
+struct ShareRecord {
+    id: i32,
+    item_type: String,
+    item_id: i32,
+    owner_id: i32,
+    owner_username: String,
+    shared_with_id: i32,
+    shared_with_username: String,
+    permission: String,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+}
+
+impl TryFrom<ShareRecord> for Share {
+    type Error = DBError;
+
+    fn try_from(record: ShareRecord) -> Result<Self, Self::Error> {
+        Ok(Share {
+            id: record.id,
+            item_type: record.item_type.parse().map_err(DBError::InvalidItemType)?,
+            item_id: record.item_id,
+            owner_id: record.owner_id,
+            owner_username: record.owner_username,
+            shared_with_id: record.shared_with_id,
+            shared_with_username: record.shared_with_username,
+            permission: record.permission.parse().map_err(DBError::InvalidPermission)?,
+            created_at: record.created_at.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl ShareDao for ShareDaoImpl {
+    async fn get_item_owner(&self, item_type: ItemType, item_id: i32) -> Result<i32, DBError> {
+        let owner_id = match item_type {
+            ItemType::Login => sqlx::query_scalar!("SELECT owner_id FROM logins WHERE id = $1", item_id)
+                .fetch_one(&self.db).await,
+            ItemType::Payment => sqlx::query_scalar!("SELECT owner_id FROM payments WHERE id = $1", item_id)
+                .fetch_one(&self.db).await,
+            ItemType::SecuredNote => sqlx::query_scalar!("SELECT owner_id FROM secured_notes WHERE id = $1", item_id)
+                .fetch_one(&self.db).await,
+            ItemType::Identity => sqlx::query_scalar!(r#"SELECT owner_id as "owner_id?" FROM identities WHERE id = $1"#, item_id)
+                .fetch_one(&self.db).await,
+            ItemType::SshKey => sqlx::query_scalar!(r#"SELECT owner_id as "owner_id?" FROM ssh_keys WHERE id = $1"#, item_id)
+                .fetch_one(&self.db).await,
+            ItemType::ApiCredential => sqlx::query_scalar!(r#"SELECT owner_id as "owner_id?" FROM api_credentials WHERE id = $1"#, item_id)
+                .fetch_one(&self.db).await,
+            ItemType::WifiNetwork => sqlx::query_scalar!(r#"SELECT owner_id as "owner_id?" FROM wifi_networks WHERE id = $1"#, item_id)
+                .fetch_one(&self.db).await,
+            ItemType::SoftwareLicense => sqlx::query_scalar!(r#"SELECT owner_id as "owner_id?" FROM software_licenses WHERE id = $1"#, item_id)
+                .fetch_one(&self.db).await,
+        }.map_err(|e| DBError::Other(Box::new(e)))?;
+
+        // Legacy rows without an owner belong to nobody, so nobody may access them.
+        owner_id.ok_or(DBError::Other(Box::new(sqlx::Error::RowNotFound)))
+    }
+
+    async fn get_permission(&self, item_type: ItemType, item_id: i32, user_id: i32) -> Result<Option<Permission>, DBError> {
+        let permission = sqlx::query_scalar!(r#"
+            SELECT permission as "permission!" FROM item_permissions
+            WHERE item_type = $1 AND item_id = $2 AND user_id = $3
+            ORDER BY rank DESC LIMIT 1
+        "#, item_type.as_str(), item_id, user_id).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        permission.map(|p| p.parse().map_err(DBError::InvalidPermission)).transpose()
+    }
+
+    async fn get_item_users(&self, item_type: ItemType, item_id: i32) -> Result<Vec<i32>, DBError> {
+        let owner_id = self.get_item_owner(item_type, item_id).await?;
+
+        let mut user_ids = sqlx::query_scalar!(r#"
+            SELECT DISTINCT user_id as "user_id!" FROM item_permissions
+            WHERE item_type = $1 AND item_id = $2 AND user_id <> $3
+        "#, item_type.as_str(), item_id, owner_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        user_ids.insert(0, owner_id);
+        Ok(user_ids)
+    }
+
+    async fn create_share(&self, owner_id: i32, shared_with_id: i32, item_type: ItemType, item_id: i32, permission: Permission) -> Result<Share, DBError> {
+        let id = sqlx::query_scalar!(r#"
+            INSERT INTO item_shares (item_type, item_id, owner_id, shared_with_id, permission)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (item_type, item_id, shared_with_id) DO UPDATE SET permission = EXCLUDED.permission
+            RETURNING id
+        "#, item_type.as_str(), item_id, owner_id, shared_with_id, permission.as_str()).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        self.get_share(id).await
+    }
+
+    async fn get_share(&self, id: i32) -> Result<Share, DBError> {
+        let record = sqlx::query_as!(ShareRecord, r#"
+            SELECT s.id, s.item_type, s.item_id, s.owner_id, o.username as owner_username,
+                   s.shared_with_id, w.username as shared_with_username, s.permission, s.created_at
+            FROM item_shares s
+            JOIN users o ON o.id = s.owner_id
+            JOIN users w ON w.id = s.shared_with_id
+            WHERE s.id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Share::try_from(record)
+    }
+
+    async fn get_shares_with_user(&self, user_id: i32) -> Result<Vec<Share>, DBError> {
+        let records = sqlx::query_as!(ShareRecord, r#"
+            SELECT s.id, s.item_type, s.item_id, s.owner_id, o.username as owner_username,
+                   s.shared_with_id, w.username as shared_with_username, s.permission, s.created_at
+            FROM item_shares s
+            JOIN users o ON o.id = s.owner_id
+            JOIN users w ON w.id = s.shared_with_id
+            WHERE s.shared_with_id = $1
+            ORDER BY s.id
+        "#, user_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(Share::try_from).collect()
+    }
+
+    async fn get_shares_by_user(&self, owner_id: i32) -> Result<Vec<Share>, DBError> {
+        let records = sqlx::query_as!(ShareRecord, r#"
+            SELECT s.id, s.item_type, s.item_id, s.owner_id, o.username as owner_username,
+                   s.shared_with_id, w.username as shared_with_username, s.permission, s.created_at
+            FROM item_shares s
+            JOIN users o ON o.id = s.owner_id
+            JOIN users w ON w.id = s.shared_with_id
+            WHERE s.owner_id = $1
+            ORDER BY s.id
+        "#, owner_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(Share::try_from).collect()
+    }
+
+    async fn delete_share(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM item_shares WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+}
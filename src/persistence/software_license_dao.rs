@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::software_license_model::{SoftwareLicense, SoftwareLicenseDto};
+use crate::persistence::item_dao::{commit_item, fill_item, fill_items, ItemDao};
+
+pub struct SoftwareLicenseDaoImpl {
+    db: PgPool,
+}
+
+impl SoftwareLicenseDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        SoftwareLicenseDaoImpl { db }
+    }
+}
+
+struct SoftwareLicenseRecord {
+    id: i32,
+    name: String,
+    product: String,
+    license_key: String,
+    seats: i32,
+    purchase_date: Option<sqlx::types::time::Date>,
+    licensed_to: String,
+    note: String,
+    color: String,
+    revision: i64,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    modified_at: sqlx::types::time::PrimitiveDateTime,
+    permission: String,
+}
+
+impl TryFrom<SoftwareLicenseRecord> for SoftwareLicense {
+    type Error = DBError;
+
+    /// Custom fields are loaded separately.
+    fn try_from(record: SoftwareLicenseRecord) -> Result<Self, Self::Error> {
+        Ok(SoftwareLicense {
+            id: record.id,
+            name: record.name,
+            product: record.product,
+            license_key: record.license_key,
+            seats: record.seats,
+            purchase_date: record.purchase_date.map(|d| d.to_string()),
+            licensed_to: record.licensed_to,
+            note: record.note,
+            color: record.color,
+            created_at: record.created_at.to_string(),
+            modified_at: record.modified_at.to_string(),
+            custom_fields: vec![],
+            revision: record.revision,
+            shared: record.permission != "owner",
+            permission: record.permission.parse().map_err(DBError::InvalidPermission)?,
+        })
+    }
+}
+
+#[async_trait]
+impl ItemDao<SoftwareLicense> for SoftwareLicenseDaoImpl {
+    async fn create_item(&self, software_license: SoftwareLicenseDto, owner_id: i32) -> Result<SoftwareLicense, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(SoftwareLicenseRecord, r#"
+            INSERT INTO software_licenses (owner_id, name, product, license_key, seats, purchase_date, licensed_to, note, color)
+            VALUES ($1, COALESCE($2, ''), COALESCE($3, ''), COALESCE($4, ''), COALESCE($5, 1), $6::date,
+                    COALESCE($7, ''), COALESCE($8, ''), COALESCE($9, 'red'))
+            RETURNING id, name, product, license_key, seats, purchase_date, licensed_to, note, color, revision,
+                      created_at, modified_at, 'owner' as "permission!"
+        "#,
+            owner_id,
+            software_license.name,
+            software_license.product,
+            software_license.license_key,
+            software_license.seats,
+            software_license.purchase_date.flatten().map(|d| d.to_string()) as _,
+            software_license.licensed_to,
+            software_license.note,
+            software_license.color,
+        ).fetch_one(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        commit_item(tx, SoftwareLicense::try_from(record)?, software_license.custom_fields).await
+    }
+
+    async fn get_item(&self, id: i32) -> Result<SoftwareLicense, DBError> {
+        let record = sqlx::query_as!(SoftwareLicenseRecord, r#"
+            SELECT id, name, product, license_key, seats, purchase_date, licensed_to, note, color, revision,
+                   created_at, modified_at, 'owner' as "permission!"
+            FROM software_licenses WHERE id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        fill_item(&self.db, SoftwareLicense::try_from(record)?).await
+    }
+
+    async fn get_items(&self, user_id: i32, since: Option<i64>) -> Result<Vec<SoftwareLicense>, DBError> {
+        let records = sqlx::query_as!(SoftwareLicenseRecord, r#"
+            SELECT l.id, l.name, l.product, l.license_key, l.seats, l.purchase_date, l.licensed_to, l.note, l.color,
+                   l.revision, l.created_at, l.modified_at, COALESCE(s.permission, 'owner') as "permission!"
+            FROM software_licenses l
+            LEFT JOIN LATERAL (
+                SELECT permission FROM item_permissions
+                WHERE item_type = 'software_license' AND item_id = l.id AND user_id = $1
+                ORDER BY rank DESC LIMIT 1
+            ) s ON l.owner_id IS DISTINCT FROM $1
+            WHERE (l.owner_id = $1 OR s.permission IS NOT NULL)
+              AND ($2::bigint IS NULL OR EXISTS(
+                  SELECT 1 FROM sync_changes c
+                  WHERE c.user_id = $1 AND c.item_type = 'software_license' AND c.item_id = l.id AND c.revision > $2
+              ))
+            ORDER BY l.id
+        "#, user_id, since).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let software_licenses = records.into_iter().map(SoftwareLicense::try_from).collect::<Result<Vec<_>, _>>()?;
+        fill_items(&self.db, software_licenses).await
+    }
+
+    async fn update_item(&self, id: i32, software_license: SoftwareLicenseDto, if_match: Option<i64>) -> Result<SoftwareLicense, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(SoftwareLicenseRecord, r#"
+            UPDATE software_licenses SET name = COALESCE($1, name), product = COALESCE($2, product),
+                                         license_key = COALESCE($3, license_key), seats = COALESCE($4, seats),
+                                         purchase_date = CASE WHEN $5 THEN $6::date ELSE purchase_date END,
+                                         licensed_to = COALESCE($7, licensed_to), note = COALESCE($8, note),
+                                         color = COALESCE($9, color), revision = revision + 1
+            WHERE id = $10 AND ($11::bigint IS NULL OR revision = $11)
+            RETURNING id, name, product, license_key, seats, purchase_date, licensed_to, note, color, revision,
+                      created_at, modified_at, 'owner' as "permission!"
+        "#,
+            software_license.name,
+            software_license.product,
+            software_license.license_key,
+            software_license.seats,
+            software_license.purchase_date.is_some(),
+            software_license.purchase_date.flatten().map(|d| d.to_string()) as _,
+            software_license.licensed_to,
+            software_license.note,
+            software_license.color,
+            id,
+            if_match
+        ).fetch_optional(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .ok_or(DBError::StaleRevision)?;
+
+        commit_item(tx, SoftwareLicense::try_from(record)?, software_license.custom_fields).await
+    }
+
+    async fn delete_item(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM software_licenses WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::ssh_key_model::{ssh_fingerprint, SshKey, SshKeyDto};
+use crate::persistence::item_dao::{commit_item, fill_item, fill_items, ItemDao};
+
+pub struct SshKeyDaoImpl {
+    db: PgPool,
+}
+
+impl SshKeyDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        SshKeyDaoImpl { db }
+    }
+}
+
+struct SshKeyRecord {
+    id: i32,
+    name: String,
+    private_key: String,
+    public_key: String,
+    fingerprint: String,
+    passphrase: String,
+    note: String,
+    color: String,
+    revision: i64,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    modified_at: sqlx::types::time::PrimitiveDateTime,
+    permission: String,
+}
+
+impl TryFrom<SshKeyRecord> for SshKey {
+    type Error = DBError;
+
+    /// Custom fields are loaded separately.
+    fn try_from(record: SshKeyRecord) -> Result<Self, Self::Error> {
+        Ok(SshKey {
+            id: record.id,
+            name: record.name,
+            private_key: record.private_key,
+            public_key: record.public_key,
+            fingerprint: record.fingerprint,
+            passphrase: record.passphrase,
+            note: record.note,
+            color: record.color,
+            created_at: record.created_at.to_string(),
+            modified_at: record.modified_at.to_string(),
+            custom_fields: vec![],
+            revision: record.revision,
+            shared: record.permission != "owner",
+            permission: record.permission.parse().map_err(DBError::InvalidPermission)?,
+        })
+    }
+}
+
+#[async_trait]
+impl ItemDao<SshKey> for SshKeyDaoImpl {
+    async fn create_item(&self, ssh_key: SshKeyDto, owner_id: i32) -> Result<SshKey, DBError> {
+        let public_key = ssh_key.public_key.unwrap_or_default();
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(SshKeyRecord, r#"
+            INSERT INTO ssh_keys (owner_id, name, private_key, public_key, fingerprint, passphrase, note, color)
+            VALUES ($1, COALESCE($2, ''), COALESCE($3, ''), $4, $5, COALESCE($6, ''), COALESCE($7, ''), COALESCE($8, 'red'))
+            RETURNING id, name, private_key, public_key, fingerprint, passphrase, note, color, revision, created_at,
+                      modified_at, 'owner' as "permission!"
+        "#,
+            owner_id,
+            ssh_key.name,
+            ssh_key.private_key,
+            public_key,
+            ssh_fingerprint(&public_key),
+            ssh_key.passphrase,
+            ssh_key.note,
+            ssh_key.color,
+        ).fetch_one(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        commit_item(tx, SshKey::try_from(record)?, ssh_key.custom_fields).await
+    }
+
+    async fn get_item(&self, id: i32) -> Result<SshKey, DBError> {
+        let record = sqlx::query_as!(SshKeyRecord, r#"
+            SELECT id, name, private_key, public_key, fingerprint, passphrase, note, color, revision, created_at,
+                   modified_at, 'owner' as "permission!"
+            FROM ssh_keys WHERE id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        fill_item(&self.db, SshKey::try_from(record)?).await
+    }
+
+    async fn get_items(&self, user_id: i32, since: Option<i64>) -> Result<Vec<SshKey>, DBError> {
+        let records = sqlx::query_as!(SshKeyRecord, r#"
+            SELECT k.id, k.name, k.private_key, k.public_key, k.fingerprint, k.passphrase, k.note, k.color, k.revision,
+                   k.created_at, k.modified_at, COALESCE(s.permission, 'owner') as "permission!"
+            FROM ssh_keys k
+            LEFT JOIN LATERAL (
+                SELECT permission FROM item_permissions
+                WHERE item_type = 'ssh_key' AND item_id = k.id AND user_id = $1
+                ORDER BY rank DESC LIMIT 1
+            ) s ON k.owner_id IS DISTINCT FROM $1
+            WHERE (k.owner_id = $1 OR s.permission IS NOT NULL)
+              AND ($2::bigint IS NULL OR EXISTS(
+                  SELECT 1 FROM sync_changes c
+                  WHERE c.user_id = $1 AND c.item_type = 'ssh_key' AND c.item_id = k.id AND c.revision > $2
+              ))
+            ORDER BY k.id
+        "#, user_id, since).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let ssh_keys = records.into_iter().map(SshKey::try_from).collect::<Result<Vec<_>, _>>()?;
+        fill_items(&self.db, ssh_keys).await
+    }
+
+    async fn update_item(&self, id: i32, ssh_key: SshKeyDto, if_match: Option<i64>) -> Result<SshKey, DBError> {
+        let fingerprint = ssh_key.public_key.as_deref().map(ssh_fingerprint);
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(SshKeyRecord, r#"
+            UPDATE ssh_keys SET name = COALESCE($1, name), private_key = COALESCE($2, private_key),
+                                public_key = COALESCE($3, public_key), fingerprint = COALESCE($4, fingerprint),
+                                passphrase = COALESCE($5, passphrase), note = COALESCE($6, note),
+                                color = COALESCE($7, color), revision = revision + 1
+            WHERE id = $8 AND ($9::bigint IS NULL OR revision = $9)
+            RETURNING id, name, private_key, public_key, fingerprint, passphrase, note, color, revision, created_at,
+                      modified_at, 'owner' as "permission!"
+        "#,
+            ssh_key.name,
+            ssh_key.private_key,
+            ssh_key.public_key,
+            fingerprint,
+            ssh_key.passphrase,
+            ssh_key.note,
+            ssh_key.color,
+            id,
+            if_match
+        ).fetch_optional(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .ok_or(DBError::StaleRevision)?;
+
+        commit_item(tx, SshKey::try_from(record)?, ssh_key.custom_fields).await
+    }
+
+    async fn delete_item(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM ssh_keys WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+}
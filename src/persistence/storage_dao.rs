@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::storage_model::StorageUsage;
+
+#[async_trait]
+pub trait StorageDao {
+    async fn get_storage_usage(&self, user_id: i32) -> Result<StorageUsage, DBError>;
+}
+
+pub struct StorageDaoImpl {
+    db: PgPool,
+}
+
+impl StorageDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        StorageDaoImpl { db }
+    }
+}
+
+#[async_trait]
+impl StorageDao for StorageDaoImpl {
+    async fn get_storage_usage(&self, user_id: i32) -> Result<StorageUsage, DBError> {
+        let record = sqlx::query!(r#"
+            SELECT p.name as plan,
+                   COALESCE(u.storage_quota, p.storage_quota) as "quota!",
+                   p.max_files_per_item,
+                   (SELECT COALESCE(SUM(size), 0) FROM attachments WHERE owner_id = u.id)
+                       + (SELECT COALESCE(SUM(file_size), 0) FROM sends WHERE owner_id = u.id) as "used!",
+                   (SELECT COUNT(*) FROM attachments WHERE owner_id = u.id)
+                       + (SELECT COUNT(file_size) FROM sends WHERE owner_id = u.id) as "files!"
+            FROM users u
+            JOIN plans p ON p.id = u.plan_id
+            WHERE u.id = $1
+        "#, user_id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(StorageUsage {
+            used: record.used,
+            quota: record.quota,
+            files: record.files,
+            max_files_per_item: record.max_files_per_item,
+            plan: record.plan,
+        })
+    }
+}
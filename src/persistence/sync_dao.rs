@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::sync_model::Tombstone;
+
+#[async_trait]
+pub trait SyncDao {
+    async fn get_revision(&self, user_id: i32) -> Result<i64, DBError>;
+    async fn get_tombstones(&self, user_id: i32, since: i64) -> Result<Vec<Tombstone>, DBError>;
+}
+
+pub struct SyncDaoImpl {
+    db: PgPool,
+}
+
+impl SyncDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        SyncDaoImpl { db }
+    }
+}
+
+#[async_trait]
+impl SyncDao for SyncDaoImpl {
+    /// The user's current revision, 0 before anything of theirs changed.
+    async fn get_revision(&self, user_id: i32) -> Result<i64, DBError> {
+        let revision = sqlx::query_scalar!("SELECT revision FROM user_revisions WHERE user_id = $1", user_id)
+            .fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(revision.unwrap_or(0))
+    }
+
+    async fn get_tombstones(&self, user_id: i32, since: i64) -> Result<Vec<Tombstone>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT item_type, item_id FROM sync_changes
+            WHERE user_id = $1 AND revision > $2 AND deleted
+            ORDER BY revision
+        "#, user_id, since).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(|r| Ok(Tombstone {
+            item_type: r.item_type.parse().map_err(DBError::InvalidItemType)?,
+            item_id: r.item_id,
+        })).collect()
+    }
+}
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose};
+use rand::random;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::user_token_model::{TokenPurpose, UserToken};
+
+#[async_trait]
+pub trait UserTokenDao {
+    async fn create_token(&self, user_id: i32, purpose: TokenPurpose, email: &str) -> Result<String, DBError>;
+    async fn find_token(&self, token: &str, purpose: TokenPurpose) -> Result<Option<UserToken>, DBError>;
+    async fn redeem_token(&self, token: &str, purpose: TokenPurpose) -> Result<Option<UserToken>, DBError>;
+}
+
+pub struct UserTokenDaoImpl {
+    db: PgPool,
+}
+
+impl UserTokenDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        UserTokenDaoImpl { db }
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[async_trait]
+impl UserTokenDao for UserTokenDaoImpl {
+    /// Issues a new token, revoking the user's unused tokens for the same purpose.
+    async fn create_token(&self, user_id: i32, purpose: TokenPurpose, email: &str) -> Result<String, DBError> {
+        let token = general_purpose::URL_SAFE_NO_PAD.encode(random::<[u8; 32]>());
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!(
+            "UPDATE user_tokens SET used_at = now() WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL",
+            user_id, purpose.as_str()
+        ).execute(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!(r#"
+            INSERT INTO user_tokens (user_id, purpose, token_hash, email, expires_at)
+            VALUES ($1, $2, $3, $4, now() + make_interval(mins => $5))
+        "#, user_id, purpose.as_str(), hash_token(&token), email, purpose.valid_minutes()
+        ).execute(&mut tx).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(token)
+    }
+
+    /// Looks a token up without using it.
+    async fn find_token(&self, token: &str, purpose: TokenPurpose) -> Result<Option<UserToken>, DBError> {
+        let record = sqlx::query!(r#"
+            SELECT user_id, email FROM user_tokens
+            WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > now()
+        "#, hash_token(token), purpose.as_str()).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(record.map(|r| UserToken { user_id: r.user_id, email: r.email }))
+    }
+
+    /// Marks the token used and returns it, unless it is unknown, expired or already used.
+    async fn redeem_token(&self, token: &str, purpose: TokenPurpose) -> Result<Option<UserToken>, DBError> {
+        let record = sqlx::query!(r#"
+            UPDATE user_tokens SET used_at = now()
+            WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > now()
+            RETURNING user_id, email
+        "#, hash_token(token), purpose.as_str()).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(record.map(|r| UserToken { user_id: r.user_id, email: r.email }))
+    }
+}
@@ -28,9 +28,18 @@ impl Display for UserError {
 #[async_trait]
 pub trait UsersDao {
     async fn get_user(&self, id: i32) -> Result<User, DBError>;
+    async fn get_user_by_username(&self, username: &str) -> Result<User, DBError>;
+    async fn get_user_by_email(&self, email: &str) -> Result<User, DBError>;
     async fn create_user(&self, user: UserDto) -> Result<User, DBError>;
     async fn update_user(&self, user: UserUpdateDto, user_id: i32) -> Result<User, DBError>;
     async fn delete_user(&self, user_id: i32) -> Result<(), DBError>;
+    async fn is_admin(&self, user_id: i32) -> Result<bool, DBError>;
+    async fn check_password(&self, user_id: i32, password: &str) -> Result<bool, DBError>;
+    /// Returns the new token generation.
+    async fn set_password(&self, user_id: i32, password: &str) -> Result<i32, DBError>;
+    async fn get_token_generation(&self, user_id: i32) -> Result<i32, DBError>;
+    async fn set_email(&self, user_id: i32, email: &str) -> Result<(), DBError>;
+    async fn verify_email(&self, user_id: i32, email: &str) -> Result<bool, DBError>;
     // async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError>;
 }
 
@@ -63,6 +72,49 @@ impl UsersDao for UsersDaoImpl {
             last_name: record.last_name,
             email: record.email,
             created_at: record.created_at.unwrap().to_string(),
+            email_verified: record.email_verified,
+        })
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<User, DBError> {
+        let record = sqlx::query!(
+            r#"
+                SELECT * FROM users WHERE username = $1
+            "#,
+            username
+        ).fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(User {
+            id: record.id,
+            username: record.username,
+            first_name: record.first_name,
+            last_name: record.last_name,
+            email: record.email,
+            created_at: record.created_at.unwrap().to_string(),
+            email_verified: record.email_verified,
+        })
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<User, DBError> {
+        let record = sqlx::query!(
+            r#"
+                SELECT * FROM users WHERE lower(email) = lower($1)
+            "#,
+            email
+        ).fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(User {
+            id: record.id,
+            username: record.username,
+            first_name: record.first_name,
+            last_name: record.last_name,
+            email: record.email,
+            created_at: record.created_at.unwrap().to_string(),
+            email_verified: record.email_verified,
         })
     }
 
@@ -98,6 +150,7 @@ impl UsersDao for UsersDaoImpl {
             last_name: record.last_name,
             email: record.email,
             created_at: record.created_at.unwrap().to_string(),
+            email_verified: record.email_verified,
         })
     }
 
@@ -144,6 +197,100 @@ impl UsersDao for UsersDaoImpl {
 
         Ok(())
     }
-}
 
+    async fn is_admin(&self, user_id: i32) -> Result<bool, DBError> {
+        let record = sqlx::query!("SELECT is_admin FROM users WHERE id = $1", user_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(record.is_admin)
+    }
+
+    async fn check_password(&self, user_id: i32, password: &str) -> Result<bool, DBError> {
+        let record = sqlx::query!("SELECT password, salt FROM users WHERE id = $1", user_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let mut salt = [0u8; 16];
+        salt.copy_from_slice(&general_purpose::STANDARD.decode(record.salt.as_bytes())
+            .map_err(|_| DBError::Other(Box::new(UserError::Other)))?);
+
+        match hash_with_salt(password, DEFAULT_COST, salt) {
+            Ok(hashed_password) => Ok(hashed_password.to_string() == record.password),
+            Err(_) => Err(DBError::Other(Box::new(UserError::Other))),
+        }
+    }
+
+    /// Sets a new password and signs the user out everywhere: the token generation is bumped so
+    /// earlier sessions stop working, and unused emailed links and WebAuthn ceremonies are
+    /// revoked, all in one transaction.
+    async fn set_password(&self, user_id: i32, password: &str) -> Result<i32, DBError> {
+        let salt = random();
+        let hashed_password = match hash_with_salt(password, DEFAULT_COST, salt) {
+            Ok(hashed_password) => hashed_password.to_string(),
+            Err(_) => return Err(DBError::Other(Box::new(UserError::Other))),
+        };
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let generation = sqlx::query_scalar!(
+            "UPDATE users SET password = $1, salt = $2, token_generation = token_generation + 1 WHERE id = $3 RETURNING token_generation",
+            hashed_password,
+            general_purpose::STANDARD.encode(&salt),
+            user_id
+        ).fetch_one(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!("UPDATE user_tokens SET used_at = now() WHERE user_id = $1 AND used_at IS NULL", user_id)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!("DELETE FROM webauthn_ceremonies WHERE user_id = $1", user_id)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        tx.commit().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(generation)
+    }
 
+    async fn get_token_generation(&self, user_id: i32) -> Result<i32, DBError> {
+        sqlx::query_scalar!("SELECT token_generation FROM users WHERE id = $1", user_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))
+    }
+
+    /// Switches to an address the user proved to own.
+    async fn set_email(&self, user_id: i32, email: &str) -> Result<(), DBError> {
+        sqlx::query!(
+            "UPDATE users SET email = $1, email_verified = true WHERE id = $2",
+            email,
+            user_id
+        ).execute(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    /// Marks `email` verified, unless the user changed their address since it was sent to.
+    async fn verify_email(&self, user_id: i32, email: &str) -> Result<bool, DBError> {
+        let result = sqlx::query!(
+            "UPDATE users SET email_verified = true WHERE id = $1 AND email = $2",
+            user_id,
+            email
+        ).execute(&self.db)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
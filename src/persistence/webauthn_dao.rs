@@ -0,0 +1,182 @@
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose};
+use rocket::serde::json::serde_json;
+use sqlx::PgPool;
+use webauthn_rs::prelude::{Passkey, Uuid};
+
+use crate::models::DBError;
+use crate::models::webauthn_model::{CredentialUsage, WebauthnCredential};
+
+#[async_trait]
+pub trait WebauthnDao {
+    async fn get_webauthn_id(&self, user_id: i32) -> Result<Uuid, DBError>;
+    async fn get_credentials(&self, user_id: i32) -> Result<Vec<WebauthnCredential>, DBError>;
+    async fn get_passkeys(&self, user_id: i32, usage: Option<CredentialUsage>) -> Result<Vec<(i32, Passkey)>, DBError>;
+    async fn has_credentials(&self, user_id: i32, usage: CredentialUsage) -> Result<bool, DBError>;
+    async fn create_credential(&self, user_id: i32, name: &str, usage: CredentialUsage, passkey: &Passkey) -> Result<WebauthnCredential, DBError>;
+    async fn update_passkey(&self, id: i32, passkey: &Passkey, sign_count: u32) -> Result<(), DBError>;
+    async fn get_credential_owner(&self, id: i32) -> Result<i32, DBError>;
+    async fn rename_credential(&self, id: i32, name: &str) -> Result<(), DBError>;
+    async fn delete_credential(&self, id: i32) -> Result<(), DBError>;
+    async fn save_ceremony(&self, id: &str, user_id: i32, kind: &str, state: &str) -> Result<(), DBError>;
+    async fn take_ceremony(&self, id: &str, kind: &str) -> Result<Option<(i32, String)>, DBError>;
+}
+
+pub struct WebauthnDaoImpl {
+    db: PgPool,
+}
+
+impl WebauthnDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        WebauthnDaoImpl { db }
+    }
+}
+
+struct CredentialRecord {
+    id: i32,
+    name: String,
+    usage: String,
+    sign_count: i64,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    last_used_at: Option<sqlx::types::time::PrimitiveDateTime>,
+}
+
+impl TryFrom<CredentialRecord> for WebauthnCredential {
+    type Error = DBError;
+
+    fn try_from(record: CredentialRecord) -> Result<Self, Self::Error> {
+        Ok(WebauthnCredential {
+            id: record.id,
+            name: record.name,
+            usage: record.usage.parse().map_err(DBError::InvalidCredential)?,
+            sign_count: record.sign_count,
+            created_at: record.created_at.to_string(),
+            last_used_at: record.last_used_at.map(|t| t.to_string()),
+        })
+    }
+}
+
+/// Ceremonies have to be finished within this many minutes.
+const CEREMONY_TIMEOUT_MINUTES: i32 = 5;
+
+#[async_trait]
+impl WebauthnDao for WebauthnDaoImpl {
+    async fn get_webauthn_id(&self, user_id: i32) -> Result<Uuid, DBError> {
+        sqlx::query_scalar!("SELECT webauthn_id FROM users WHERE id = $1", user_id)
+            .fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))
+    }
+
+    async fn get_credentials(&self, user_id: i32) -> Result<Vec<WebauthnCredential>, DBError> {
+        let records = sqlx::query_as!(CredentialRecord, r#"
+            SELECT id, name, usage, sign_count, created_at, last_used_at
+            FROM webauthn_credentials
+            WHERE user_id = $1
+            ORDER BY id
+        "#, user_id).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(WebauthnCredential::try_from).collect()
+    }
+
+    async fn get_passkeys(&self, user_id: i32, usage: Option<CredentialUsage>) -> Result<Vec<(i32, Passkey)>, DBError> {
+        let records = sqlx::query!(r#"
+            SELECT id, passkey FROM webauthn_credentials
+            WHERE user_id = $1 AND ($2::varchar IS NULL OR usage = $2)
+            ORDER BY id
+        "#, user_id, usage.map(|u| u.as_str())).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        records.into_iter().map(|record| {
+            let passkey = serde_json::from_str(&record.passkey)
+                .map_err(|e| DBError::InvalidCredential(e.to_string()))?;
+            Ok((record.id, passkey))
+        }).collect()
+    }
+
+    async fn has_credentials(&self, user_id: i32, usage: CredentialUsage) -> Result<bool, DBError> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM webauthn_credentials WHERE user_id = $1 AND usage = $2) as "exists!""#,
+            user_id, usage.as_str()
+        ).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))
+    }
+
+    async fn create_credential(&self, user_id: i32, name: &str, usage: CredentialUsage, passkey: &Passkey) -> Result<WebauthnCredential, DBError> {
+        let encoded = serde_json::to_string(passkey)
+            .map_err(|e| DBError::InvalidCredential(e.to_string()))?;
+        let credential_id = general_purpose::URL_SAFE_NO_PAD.encode(passkey.cred_id());
+
+        let record = sqlx::query_as!(CredentialRecord, r#"
+            INSERT INTO webauthn_credentials (user_id, name, usage, credential_id, passkey)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, usage, sign_count, created_at, last_used_at
+        "#, user_id, name, usage.as_str(), credential_id, encoded).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        WebauthnCredential::try_from(record)
+    }
+
+    async fn update_passkey(&self, id: i32, passkey: &Passkey, sign_count: u32) -> Result<(), DBError> {
+        let encoded = serde_json::to_string(passkey)
+            .map_err(|e| DBError::InvalidCredential(e.to_string()))?;
+
+        sqlx::query!(
+            "UPDATE webauthn_credentials SET passkey = $1, sign_count = $2, last_used_at = now() WHERE id = $3",
+            encoded, sign_count as i64, id
+        ).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_credential_owner(&self, id: i32) -> Result<i32, DBError> {
+        sqlx::query_scalar!("SELECT user_id FROM webauthn_credentials WHERE id = $1", id)
+            .fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))
+    }
+
+    async fn rename_credential(&self, id: i32, name: &str) -> Result<(), DBError> {
+        sqlx::query!("UPDATE webauthn_credentials SET name = $1 WHERE id = $2", name, id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn delete_credential(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM webauthn_credentials WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn save_ceremony(&self, id: &str, user_id: i32, kind: &str, state: &str) -> Result<(), DBError> {
+        sqlx::query!(
+            "DELETE FROM webauthn_ceremonies WHERE created_at < now() - make_interval(mins => $1)",
+            CEREMONY_TIMEOUT_MINUTES
+        ).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        sqlx::query!(
+            "INSERT INTO webauthn_ceremonies (id, user_id, kind, state) VALUES ($1, $2, $3, $4)",
+            id, user_id, kind, state
+        ).execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    /// Removes and returns a ceremony, so each challenge can be answered only once.
+    async fn take_ceremony(&self, id: &str, kind: &str) -> Result<Option<(i32, String)>, DBError> {
+        let record = sqlx::query!(r#"
+            DELETE FROM webauthn_ceremonies
+            WHERE id = $1 AND kind = $2
+            RETURNING user_id, state, created_at > now() - make_interval(mins => $3) as "fresh!"
+        "#, id, kind, CEREMONY_TIMEOUT_MINUTES).fetch_optional(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(record.filter(|r| r.fresh).map(|r| (r.user_id, r.state)))
+    }
+}
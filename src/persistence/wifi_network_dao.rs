@@ -0,0 +1,156 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::DBError;
+use crate::models::wifi_network_model::{WifiNetwork, WifiNetworkDto};
+use crate::persistence::item_dao::{commit_item, fill_item, fill_items, ItemDao};
+
+pub struct WifiNetworkDaoImpl {
+    db: PgPool,
+}
+
+impl WifiNetworkDaoImpl {
+    pub fn new(db: PgPool) -> Self {
+        WifiNetworkDaoImpl { db }
+    }
+}
+
+struct WifiNetworkRecord {
+    id: i32,
+    name: String,
+    ssid: String,
+    security_type: String,
+    password: String,
+    hidden: bool,
+    note: String,
+    color: String,
+    revision: i64,
+    created_at: sqlx::types::time::PrimitiveDateTime,
+    modified_at: sqlx::types::time::PrimitiveDateTime,
+    permission: String,
+}
+
+impl TryFrom<WifiNetworkRecord> for WifiNetwork {
+    type Error = DBError;
+
+    /// Custom fields are loaded separately.
+    fn try_from(record: WifiNetworkRecord) -> Result<Self, Self::Error> {
+        Ok(WifiNetwork {
+            id: record.id,
+            name: record.name,
+            ssid: record.ssid,
+            security_type: record.security_type.parse().map_err(DBError::InvalidWifiSecurity)?,
+            password: record.password,
+            hidden: record.hidden,
+            note: record.note,
+            color: record.color,
+            created_at: record.created_at.to_string(),
+            modified_at: record.modified_at.to_string(),
+            custom_fields: vec![],
+            revision: record.revision,
+            shared: record.permission != "owner",
+            permission: record.permission.parse().map_err(DBError::InvalidPermission)?,
+        })
+    }
+}
+
+#[async_trait]
+impl ItemDao<WifiNetwork> for WifiNetworkDaoImpl {
+    async fn create_item(&self, wifi_network: WifiNetworkDto, owner_id: i32) -> Result<WifiNetwork, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(WifiNetworkRecord, r#"
+            INSERT INTO wifi_networks (owner_id, name, ssid, security_type, password, hidden, note, color)
+            VALUES ($1, COALESCE($2, ''), COALESCE($3, ''), $4, COALESCE($5, ''), COALESCE($6, false),
+                    COALESCE($7, ''), COALESCE($8, 'red'))
+            RETURNING id, name, ssid, security_type, password, hidden, note, color, revision, created_at, modified_at,
+                      'owner' as "permission!"
+        "#,
+            owner_id,
+            wifi_network.name,
+            wifi_network.ssid,
+            wifi_network.security_type.unwrap_or_default().as_str(),
+            wifi_network.password,
+            wifi_network.hidden,
+            wifi_network.note,
+            wifi_network.color,
+        ).fetch_one(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        commit_item(tx, WifiNetwork::try_from(record)?, wifi_network.custom_fields).await
+    }
+
+    async fn get_item(&self, id: i32) -> Result<WifiNetwork, DBError> {
+        let record = sqlx::query_as!(WifiNetworkRecord, r#"
+            SELECT id, name, ssid, security_type, password, hidden, note, color, revision, created_at, modified_at,
+                   'owner' as "permission!"
+            FROM wifi_networks WHERE id = $1
+        "#, id).fetch_one(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        fill_item(&self.db, WifiNetwork::try_from(record)?).await
+    }
+
+    async fn get_items(&self, user_id: i32, since: Option<i64>) -> Result<Vec<WifiNetwork>, DBError> {
+        let records = sqlx::query_as!(WifiNetworkRecord, r#"
+            SELECT w.id, w.name, w.ssid, w.security_type, w.password, w.hidden, w.note, w.color, w.revision,
+                   w.created_at, w.modified_at, COALESCE(s.permission, 'owner') as "permission!"
+            FROM wifi_networks w
+            LEFT JOIN LATERAL (
+                SELECT permission FROM item_permissions
+                WHERE item_type = 'wifi_network' AND item_id = w.id AND user_id = $1
+                ORDER BY rank DESC LIMIT 1
+            ) s ON w.owner_id IS DISTINCT FROM $1
+            WHERE (w.owner_id = $1 OR s.permission IS NOT NULL)
+              AND ($2::bigint IS NULL OR EXISTS(
+                  SELECT 1 FROM sync_changes c
+                  WHERE c.user_id = $1 AND c.item_type = 'wifi_network' AND c.item_id = w.id AND c.revision > $2
+              ))
+            ORDER BY w.id
+        "#, user_id, since).fetch_all(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let wifi_networks = records.into_iter().map(WifiNetwork::try_from).collect::<Result<Vec<_>, _>>()?;
+        fill_items(&self.db, wifi_networks).await
+    }
+
+    async fn update_item(&self, id: i32, wifi_network: WifiNetworkDto, if_match: Option<i64>) -> Result<WifiNetwork, DBError> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        let record = sqlx::query_as!(WifiNetworkRecord, r#"
+            UPDATE wifi_networks SET name = COALESCE($1, name), ssid = COALESCE($2, ssid),
+                                     security_type = COALESCE($3, security_type), password = COALESCE($4, password),
+                                     hidden = COALESCE($5, hidden), note = COALESCE($6, note),
+                                     color = COALESCE($7, color), revision = revision + 1
+            WHERE id = $8 AND ($9::bigint IS NULL OR revision = $9)
+            RETURNING id, name, ssid, security_type, password, hidden, note, color, revision, created_at, modified_at,
+                      'owner' as "permission!"
+        "#,
+            wifi_network.name,
+            wifi_network.ssid,
+            wifi_network.security_type.map(|t| t.as_str()),
+            wifi_network.password,
+            wifi_network.hidden,
+            wifi_network.note,
+            wifi_network.color,
+            id,
+            if_match
+        ).fetch_optional(&mut tx)
+            .await
+            .map_err(|e| DBError::Other(Box::new(e)))?
+            .ok_or(DBError::StaleRevision)?;
+
+        commit_item(tx, WifiNetwork::try_from(record)?, wifi_network.custom_fields).await
+    }
+
+    async fn delete_item(&self, id: i32) -> Result<(), DBError> {
+        sqlx::query!("DELETE FROM wifi_networks WHERE id = $1", id)
+            .execute(&self.db).await
+            .map_err(|e| DBError::Other(Box::new(e)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{NaiveTime, Utc};
+use log::{debug, error};
+
+use crate::models::DBError;
+
+/// Background work the scheduler runs.
+#[async_trait]
+pub trait Job {
+    fn name(&self) -> &'static str;
+    async fn run(&self) -> Result<(), DBError>;
+}
+
+enum Schedule {
+    Every(Duration),
+    /// Once a day at this time, UTC.
+    Daily(NaiveTime),
+}
+
+/// Runs jobs on a fixed interval or once a day. Every job gets its own task, so a slow job
+/// doesn't hold up the others, and a job never overlaps with itself.
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<(Schedule, Arc<dyn Job + Send + Sync>)>,
+}
+
+impl Scheduler {
+    /// Runs `job` right away and then every `interval`.
+    pub fn every(mut self, interval: Duration, job: impl Job + Send + Sync + 'static) -> Self {
+        self.jobs.push((Schedule::Every(interval), Arc::new(job)));
+        self
+    }
+
+    /// Runs `job` every day at `at`, UTC.
+    pub fn daily(mut self, at: NaiveTime, job: impl Job + Send + Sync + 'static) -> Self {
+        self.jobs.push((Schedule::Daily(at), Arc::new(job)));
+        self
+    }
+
+    pub fn start(self) {
+        for (schedule, job) in self.jobs {
+            tokio::spawn(async move {
+                match schedule {
+                    Schedule::Every(interval) => {
+                        let mut interval = tokio::time::interval(interval);
+                        loop {
+                            interval.tick().await;
+                            run(job.as_ref()).await;
+                        }
+                    }
+                    Schedule::Daily(at) => loop {
+                        tokio::time::sleep(until(at)).await;
+                        run(job.as_ref()).await;
+                    },
+                }
+            });
+        }
+    }
+}
+
+/// Reads SCHEDULER_DAILY_AT, the `HH:MM` UTC time daily jobs run at. Defaults to 03:00.
+pub fn daily_at_from_env() -> NaiveTime {
+    match std::env::var("SCHEDULER_DAILY_AT") {
+        Ok(at) => NaiveTime::parse_from_str(&at, "%H:%M").expect("SCHEDULER_DAILY_AT must be a time like 03:00."),
+        Err(_) => NaiveTime::from_hms_opt(3, 0, 0).unwrap(),
+    }
+}
+
+async fn run(job: &(dyn Job + Send + Sync)) {
+    debug!("Running job {}.", job.name());
+    if let Err(err) = job.run().await {
+        error!("Job {} failed: {:?}", job.name(), err);
+    }
+}
+
+/// Time left until the next `at`, today or tomorrow.
+fn until(at: NaiveTime) -> Duration {
+    let now = Utc::now().naive_utc();
+    let mut next = now.date().and_time(at);
+    if next <= now {
+        next += chrono::Duration::days(1);
+    }
+
+    (next - now).to_std().unwrap_or(Duration::ZERO)
+}
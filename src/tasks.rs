@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::{error, info};
+use tokio::sync::Mutex;
+
+use crate::mailer::{EmailTemplate, Mailer};
+use crate::models::DBError;
+use crate::models::item_model::ItemType;
+use crate::models::notification_model::{NewNotification, NotificationKind};
+use crate::persistence::emergency_access_dao::EmergencyAccessDao;
+use crate::persistence::event_chain::CheckpointSigner;
+use crate::persistence::event_dao::EventDao;
+use crate::persistence::file_storage;
+use crate::persistence::notification_dao::NotificationDao;
+use crate::persistence::send_dao::SendDao;
+use crate::scheduler::Job;
+
+pub const EMERGENCY_ACCESS_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+pub const EVENT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+pub const SEND_CLEANUP_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Approves emergency access requests whose waiting period elapsed without the grantor
+/// rejecting them.
+pub struct EmergencyAccessJob {
+    pub emergency_access_dao: Box<dyn EmergencyAccessDao + Sync + Send>,
+}
+
+#[async_trait]
+impl Job for EmergencyAccessJob {
+    fn name(&self) -> &'static str {
+        "emergency_access"
+    }
+
+    async fn run(&self) -> Result<(), DBError> {
+        for id in self.emergency_access_dao.approve_elapsed_recoveries().await? {
+            info!("Emergency access {} approved after its waiting period.", id);
+        }
+
+        Ok(())
+    }
+}
+
+/// Deletes audit events older than `retention_days`.
+pub struct EventRetentionJob {
+    pub event_dao: Box<dyn EventDao + Sync + Send>,
+    pub retention_days: i32,
+}
+
+#[async_trait]
+impl Job for EventRetentionJob {
+    fn name(&self) -> &'static str {
+        "event_retention"
+    }
+
+    async fn run(&self) -> Result<(), DBError> {
+        let deleted = self.event_dao.delete_events_older_than(self.retention_days).await?;
+        info!("Deleted {} audit events older than {} days.", deleted, self.retention_days);
+
+        Ok(())
+    }
+}
+
+/// Deletes sends that expired or ran out of views, together with their files.
+pub struct SendCleanupJob {
+    pub send_dao: Box<dyn SendDao + Sync + Send>,
+}
+
+#[async_trait]
+impl Job for SendCleanupJob {
+    fn name(&self) -> &'static str {
+        "send_cleanup"
+    }
+
+    async fn run(&self) -> Result<(), DBError> {
+        let deleted = self.send_dao.delete_finished_sends().await?;
+
+        // Text sends have no file; removing a missing file is not an error.
+        for id in &deleted {
+            if let Err(err) = file_storage::remove(&file_storage::send_path(*id)).await {
+                error!("Failed to remove send file {}: {:?}", id, err);
+            }
+        }
+
+        if !deleted.is_empty() {
+            info!("Deleted {} expired or used up sends.", deleted.len());
+        }
+
+        Ok(())
+    }
+}
+
+/// Signs the head of the audit log, if it moved since the last checkpoint.
+pub struct EventCheckpointJob {
+    event_dao: Box<dyn EventDao + Sync + Send>,
+    signer: CheckpointSigner,
+    /// Event id of the last checkpoint, loaded on the first run.
+    last_checkpoint: Mutex<Option<i64>>,
+}
+
+impl EventCheckpointJob {
+    pub fn new(event_dao: Box<dyn EventDao + Sync + Send>, signer: CheckpointSigner) -> Self {
+        EventCheckpointJob { event_dao, signer, last_checkpoint: Mutex::new(None) }
+    }
+}
+
+#[async_trait]
+impl Job for EventCheckpointJob {
+    fn name(&self) -> &'static str {
+        "event_checkpoint"
+    }
+
+    async fn run(&self) -> Result<(), DBError> {
+        let mut last_checkpoint = self.last_checkpoint.lock().await;
+        if last_checkpoint.is_none() {
+            *last_checkpoint = self.event_dao.get_checkpoints().await?.last().map(|c| c.event_id);
+        }
+
+        let (event_id, hash) = match self.event_dao.get_last_link().await? {
+            Some(head) if Some(head.0) != *last_checkpoint => head,
+            _ => return Ok(()),
+        };
+
+        let signature = self.signer.sign(event_id, &hash).expect("Checkpoints are only scheduled with a key");
+        self.event_dao.create_checkpoint(event_id, &hash, &signature).await?;
+        info!("Signed audit log checkpoint at event {}.", event_id);
+        *last_checkpoint = Some(event_id);
+
+        Ok(())
+    }
+}
+
+/// Reminds users of cards about to expire and of passwords due for rotation, then emails the
+/// reminders of users who asked for that.
+pub struct ReminderJob {
+    pub notification_dao: Box<dyn NotificationDao + Sync + Send>,
+    pub mailer: Mailer,
+}
+
+#[async_trait]
+impl Job for ReminderJob {
+    fn name(&self) -> &'static str {
+        "reminders"
+    }
+
+    async fn run(&self) -> Result<(), DBError> {
+        for card in self.notification_dao.find_expiring_cards().await? {
+            let card_name = if card.name.is_empty() {
+                format!("Your card ending in {}", card.last_four)
+            } else {
+                format!("Your card {} ending in {}", card.name, card.last_four)
+            };
+            let notification = NewNotification {
+                user_id: card.user_id,
+                kind: NotificationKind::CardExpiring,
+                item_type: ItemType::Payment,
+                item_id: card.payment_id,
+                dedup_key: format!("{}-{:02}", card.expiration_year, card.expiration_month),
+                message: format!("{} expires at the end of {:02}/{}.", card_name, card.expiration_month, card.expiration_year),
+            };
+            self.notification_dao.create_notification(&notification).await?;
+        }
+
+        for login in self.notification_dao.find_stale_passwords().await? {
+            let notification = NewNotification {
+                user_id: login.user_id,
+                kind: NotificationKind::PasswordRotation,
+                item_type: ItemType::Login,
+                item_id: login.login_id,
+                dedup_key: login.password_changed_at,
+                message: format!("The password of {} is older than {} days. Please change it.", login.username, login.rotation_days),
+            };
+            self.notification_dao.create_notification(&notification).await?;
+        }
+
+        let link = self.mailer.url("/notifications");
+        for pending in self.notification_dao.get_pending_emails().await? {
+            let values = [
+                ("first_name", pending.first_name.as_str()),
+                ("message", pending.notification.message.as_str()),
+                ("link", link.as_str()),
+            ];
+            match self.mailer.send(&pending.email, EmailTemplate::Notification, &values).await {
+                Ok(()) => self.notification_dao.mark_emailed(pending.notification.id).await?,
+                Err(err) => error!("Failed to email notification {}: {}", pending.notification.id, err),
+            }
+        }
+
+        Ok(())
+    }
+}